@@ -4,6 +4,7 @@ from sentry_relay import DataCategory, SPAN_STATUS_CODE_TO_NAME
 def test_parse_data_category():
     assert DataCategory.parse("default") == DataCategory.DEFAULT
     assert DataCategory.parse("transaction") == DataCategory.TRANSACTION
+    assert DataCategory.parse("replay") == DataCategory.REPLAY
     assert DataCategory.parse("") is None
     assert DataCategory.parse(None) is None
     assert DataCategory.parse("something completely different") is None
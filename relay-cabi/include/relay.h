@@ -44,6 +44,12 @@ enum RelayDataCategory {
    * A profile
    */
   RELAY_DATA_CATEGORY_PROFILE = 6,
+  /**
+   * Replay recordings. Quantity is the number of recording items.
+   *
+   * This matches `DataCategory.REPLAY` in Sentry.
+   */
+  RELAY_DATA_CATEGORY_REPLAY = 7,
   /**
    * Any other data category not known by this Relay.
    */
@@ -109,6 +109,10 @@ pub enum DataCategory {
     Session = 5,
     /// A profile
     Profile = 6,
+    /// Replay recordings. Quantity is the number of recording items.
+    ///
+    /// This matches `DataCategory.REPLAY` in Sentry.
+    Replay = 7,
     /// Any other data category not known by this Relay.
     #[serde(other)]
     Unknown = -1,
@@ -125,6 +129,7 @@ impl DataCategory {
             "attachment" => Self::Attachment,
             "session" => Self::Session,
             "profile" => Self::Profile,
+            "replay" => Self::Replay,
             _ => Self::Unknown,
         }
     }
@@ -139,6 +144,7 @@ impl DataCategory {
             Self::Attachment => "attachment",
             Self::Session => "session",
             Self::Profile => "profile",
+            Self::Replay => "replay",
             Self::Unknown => "unknown",
         }
     }
@@ -14,7 +14,7 @@ use serde::de::{Unexpected, Visitor};
 use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
 
 use relay_auth::{generate_key_pair, generate_relay_id, PublicKey, RelayId, SecretKey};
-use relay_common::{Dsn, Uuid};
+use relay_common::{Dsn, ProjectKey, Uuid};
 use relay_metrics::AggregatorConfig;
 use relay_redis::RedisConfig;
 
@@ -374,6 +374,37 @@ fn is_default<T: Default + PartialEq>(t: &T) -> bool {
     *t == T::default()
 }
 
+/// Deserializes a sample rate and ensures it is within `[0, 1]`.
+fn deserialize_sample_rate<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let rate = f64::deserialize(deserializer)?;
+    if (0.0..=1.0).contains(&rate) {
+        Ok(rate)
+    } else {
+        Err(serde::de::Error::invalid_value(
+            Unexpected::Float(rate),
+            &"a sample rate between 0 and 1",
+        ))
+    }
+}
+
+fn deserialize_zstd_level<'de, D>(deserializer: D) -> Result<i32, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let level = i32::deserialize(deserializer)?;
+    if (1..=22).contains(&level) {
+        Ok(level)
+    } else {
+        Err(serde::de::Error::invalid_value(
+            Unexpected::Signed(level.into()),
+            &"a zstd compression level between 1 and 22",
+        ))
+    }
+}
+
 /// Checks if we are running in docker.
 fn is_docker() -> bool {
     if fs::metadata("/.dockerenv").is_ok() {
@@ -442,6 +473,119 @@ pub struct Relay {
     /// Validation of project identifiers can be safely skipped in these cases.
     #[serde(skip_serializing_if = "is_default")]
     pub override_project_ids: bool,
+    /// Remove sample rates from events before forwarding them to the upstream.
+    ///
+    /// This forces the upstream to re-sample events. Has no effect in processing mode, where
+    /// sample rates are written into the event payload.
+    #[serde(skip_serializing_if = "is_default")]
+    pub strip_sample_rates_on_forward: bool,
+    /// The minimum sample rate applied by dynamic sampling rules to events.
+    ///
+    /// Rules with a lower sample rate are raised to this rate. This guards against sampling rules
+    /// that accidentally drop all traffic. Must be between `0.0` and `1.0`.
+    #[serde(skip_serializing_if = "is_default")]
+    pub min_effective_sample_rate: f64,
+    /// Public keys of projects whose events are never sampled out by dynamic sampling.
+    ///
+    /// This is intended for temporarily disabling sampling while debugging a single project.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub force_keep_projects: Vec<ProjectKey>,
+    /// Public keys of projects whose events and attachments are never scrubbed.
+    ///
+    /// This is intended for internal projects, such as Relay's own error monitoring, where
+    /// scrubbing would remove information required for debugging.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub scrubbing_exempt_keys: Vec<ProjectKey>,
+    /// Drop only the event of an envelope if it fails normalization.
+    ///
+    /// By default, an invalid transaction fails the entire envelope including its attachments and
+    /// profiles. If enabled, the remaining items are processed and forwarded without the event.
+    #[serde(skip_serializing_if = "is_default")]
+    pub keep_items_of_invalid_events: bool,
+    /// Drop transactions without a valid trace id in their trace context.
+    ///
+    /// Trace ids must be 32-character hex strings. Processing Relays always reject transactions
+    /// with malformed trace ids during normalization.
+    #[serde(skip_serializing_if = "is_default")]
+    pub validate_trace_ids: bool,
+    /// Drop envelopes unless they are signed by a trusted Relay.
+    ///
+    /// Every envelope must carry an `X-Sentry-Relay-Id` and a recent `X-Sentry-Relay-Signature`
+    /// header. The signature must verify against the sending Relay's key, which must be one of the
+    /// project's trusted Relays. Envelopes without a signature, including those sent directly by
+    /// SDKs, are dropped.
+    #[serde(skip_serializing_if = "is_default")]
+    pub require_trusted_relay_signature: bool,
+    /// Drop error events that do not carry a timestamp.
+    ///
+    /// By default, Relay assigns the time of receipt to events without a timestamp. Transactions
+    /// are not affected, since they are always validated for start and end timestamps.
+    #[serde(skip_serializing_if = "is_default")]
+    pub require_event_timestamp: bool,
+    /// Drop attachments with a filename that already occurred earlier in the same envelope.
+    ///
+    /// By default, repeated attachments are forwarded. This protects against SDKs uploading the
+    /// same minidump multiple times.
+    #[serde(skip_serializing_if = "is_default")]
+    pub dedupe_attachments_by_filename: bool,
+    /// Record the duration of processing stages as spans on Relay's own transactions.
+    ///
+    /// This is intended for self-monitoring of Relay. Only transactions sent to the DSN in the
+    /// `sentry` section are instrumented, customer transactions are never modified. Spans are
+    /// attached with operation `relay.processing` and the name of the stage as description.
+    #[serde(skip_serializing_if = "is_default")]
+    pub self_instrument_transactions: bool,
+    /// The region in which this Relay is deployed.
+    ///
+    /// If set, outcomes emitted by this Relay are tagged with the region for reconciliation in
+    /// multi-region deployments.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// The environment assigned to events and sessions that do not specify one.
+    ///
+    /// By default, the environment is left empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_environment: Option<String>,
+    /// Tags added to all events that pass through this Relay.
+    ///
+    /// Tags already set by the client take precedence and are never overwritten.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub static_event_tags: BTreeMap<String, String>,
+    /// Minimum SDK versions by SDK name, such as `sentry.python: "1.5.0"`.
+    ///
+    /// Envelopes from older SDKs are dropped as filtered. The minimum versions must be valid
+    /// semver, otherwise loading the config fails. Client versions that cannot be parsed as semver
+    /// are accepted.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub min_sdk_versions: BTreeMap<String, semver::Version>,
+    /// Attach a SHA-256 hash of the envelope items as `payload_hash` header to forwarded envelopes.
+    ///
+    /// The next Relay in the chain can use this hash to detect duplicate envelopes.
+    #[serde(skip_serializing_if = "is_default")]
+    pub attach_payload_hash: bool,
+    /// Name of an additional header that carries the client IP address to the upstream.
+    ///
+    /// The `X-Forwarded-For` header is always sent. Some upstreams expect the client IP in a
+    /// dedicated header, such as `X-Real-IP`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_ip_header: Option<String>,
+    /// A secondary upstream that receives copies of items listed in `mirror_item_types`.
+    ///
+    /// Mirroring is best-effort. Failures to send to the mirror are logged and do not affect
+    /// forwarding to the primary upstream.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirror_upstream: Option<UpstreamDescriptor<'static>>,
+    /// Envelope item types that are copied to the `mirror_upstream`, such as `"attachment"`.
+    ///
+    /// Relay refuses to start if this contains an unknown item type.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mirror_item_types: Vec<String>,
+    /// An inline PII config that is applied to all events in addition to the project's config.
+    ///
+    /// This is intended for testing PII scrubbing and is only honored in capture mode. It cannot
+    /// be combined with processing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_pii_config: Option<serde_json::Value>,
 }
 
 impl Default for Relay {
@@ -455,6 +599,25 @@ impl Default for Relay {
             tls_identity_path: None,
             tls_identity_password: None,
             override_project_ids: false,
+            strip_sample_rates_on_forward: false,
+            min_effective_sample_rate: 0.0,
+            force_keep_projects: Vec::new(),
+            scrubbing_exempt_keys: Vec::new(),
+            keep_items_of_invalid_events: false,
+            validate_trace_ids: false,
+            require_trusted_relay_signature: false,
+            require_event_timestamp: false,
+            dedupe_attachments_by_filename: false,
+            self_instrument_transactions: false,
+            region: None,
+            default_environment: None,
+            static_event_tags: BTreeMap::new(),
+            min_sdk_versions: BTreeMap::new(),
+            attach_payload_hash: false,
+            client_ip_header: None,
+            mirror_upstream: None,
+            mirror_item_types: Vec::new(),
+            test_pii_config: None,
         }
     }
 }
@@ -484,6 +647,14 @@ struct Metrics {
     /// For example, a value of `0.3` means that only 30% of the emitted metrics will be sent.
     /// Defaults to `1.0` (100%).
     sample_rate: f32,
+    /// Emit a counter for the number of PII rule matches applied while scrubbing events.
+    ///
+    /// Defaults to `false`.
+    pii_rule_hits: bool,
+    /// Emit a counter for every envelope received, before it is queued for processing.
+    ///
+    /// Defaults to `false`.
+    envelopes_received: bool,
 }
 
 impl Default for Metrics {
@@ -495,6 +666,8 @@ impl Default for Metrics {
             hostname_tag: None,
             buffering: true,
             sample_rate: 1.0,
+            pii_rule_hits: false,
+            envelopes_received: false,
         }
     }
 }
@@ -513,16 +686,56 @@ struct Limits {
     max_concurrent_queries: usize,
     /// The maximum payload size for events.
     max_event_size: ByteSize,
+    /// The payload size above which events are only accepted at `soft_max_event_sample_rate`.
+    ///
+    /// Events larger than `max_event_size` are always dropped.
+    soft_max_event_size: Option<ByteSize>,
+    /// The rate at which events exceeding `soft_max_event_size` are accepted.
+    ///
+    /// Must be between `0.0` and `1.0`.
+    #[serde(deserialize_with = "deserialize_sample_rate")]
+    soft_max_event_sample_rate: f64,
     /// The maximum size for each attachment.
     max_attachment_size: ByteSize,
     /// The maximum combined size for all attachments in an envelope or request.
     max_attachments_size: ByteSize,
+    /// The combined size of attachments retained in an envelope during processing.
+    ///
+    /// If exceeded, the largest attachments are dropped until the remaining ones fit. Unlike
+    /// `max_attachments_size`, this does not reject the entire request.
+    max_total_attachment_bytes: Option<ByteSize>,
     /// The maximum combined size for all client reports in an envelope or request.
     max_client_reports_size: ByteSize,
     /// The maximum payload size for an entire envelopes. Individual limits still apply.
     max_envelope_size: ByteSize,
     /// The maximum number of session items per envelope.
     max_session_count: usize,
+    /// The maximum number of aggregates in a single sessions item.
+    max_session_aggregates: usize,
+    /// The maximum sequence number of session updates. Sessions with a higher sequence are dropped.
+    max_session_sequence: u64,
+    /// The maximum size of request bodies retained on events. Larger bodies are truncated.
+    max_request_body_size: ByteSize,
+    /// The maximum number of breadcrumbs retained on an event.
+    max_breadcrumbs: usize,
+    /// The maximum depth of values in event payloads. Deeper values are trimmed.
+    ///
+    /// Disabled by default, in which case only the contents of databags are limited in depth.
+    max_json_depth: Option<usize>,
+    /// The maximum number of exceptions retained in the exception chain of an event.
+    max_exceptions: usize,
+    /// The maximum number of threads retained on an event. Crashed threads are always retained.
+    max_event_threads: usize,
+    /// The maximum number of entries retained in the fingerprint of an event.
+    max_fingerprint_entries: usize,
+    /// The maximum number of measurements retained on a transaction.
+    max_measurements: usize,
+    /// The maximum number of characters in log messages of events.
+    max_message_length: usize,
+    /// The maximum number of tags retained on an event.
+    max_event_tags: usize,
+    /// The maximum number of characters in releases of events and sessions.
+    max_release_length: usize,
     /// The maximum payload size for general API requests.
     max_api_payload_size: ByteSize,
     /// The maximum payload size for file uploads and chunks.
@@ -549,6 +762,12 @@ struct Limits {
     /// The maximum number of seconds to wait for pending envelopes after receiving a shutdown
     /// signal.
     shutdown_timeout: u64,
+    /// The maximum number of envelopes retained in capture mode.
+    ///
+    /// Once exceeded, the least recently used captures are evicted.
+    max_captured_envelopes: usize,
+    /// The number of seconds after which unused captured envelopes are evicted in capture mode.
+    captured_envelope_ttl: u64,
 }
 
 impl Default for Limits {
@@ -557,11 +776,26 @@ impl Default for Limits {
             max_concurrent_requests: 100,
             max_concurrent_queries: 5,
             max_event_size: ByteSize::mebibytes(1),
+            soft_max_event_size: None,
+            soft_max_event_sample_rate: 0.1,
             max_attachment_size: ByteSize::mebibytes(100),
             max_attachments_size: ByteSize::mebibytes(100),
+            max_total_attachment_bytes: None,
             max_client_reports_size: ByteSize::kibibytes(4),
             max_envelope_size: ByteSize::mebibytes(100),
             max_session_count: 100,
+            max_session_aggregates: 100,
+            max_session_sequence: u64::MAX,
+            max_request_body_size: ByteSize::kibibytes(8),
+            max_breadcrumbs: 100,
+            max_json_depth: None,
+            max_exceptions: 25,
+            max_event_threads: 100,
+            max_fingerprint_entries: 32,
+            max_measurements: 30,
+            max_message_length: 8192,
+            max_event_tags: 50,
+            max_release_length: 250,
             max_api_payload_size: ByteSize::mebibytes(20),
             max_api_file_upload_size: ByteSize::mebibytes(40),
             max_api_chunk_upload_size: ByteSize::mebibytes(100),
@@ -572,6 +806,8 @@ impl Default for Limits {
             max_pending_connections: 2048,
             max_connections: 25_000,
             shutdown_timeout: 10,
+            max_captured_envelopes: 10_000,
+            captured_envelope_ttl: 3600,
         }
     }
 }
@@ -590,6 +826,16 @@ pub struct Routing {
     /// Defaults to `true` for all Relay modes other than processing mode. In processing mode, this
     /// is disabled by default since the item cannot be handled.
     accept_unknown_items: Option<bool>,
+    /// Reject envelopes that do not contain any items.
+    ///
+    /// By default, empty envelopes are accepted and silently dropped. Enabling this responds with
+    /// an error instead, which helps to detect broken SDKs.
+    reject_empty_envelopes: bool,
+    /// Reject all new envelopes with a retriable error.
+    ///
+    /// This is intended for upstream maintenance. Clients receive a `503` response and can submit
+    /// their data again later. Envelopes that are already queued are still sent.
+    read_only_mode: bool,
 }
 
 /// Http content encoding for both incoming and outgoing web requests.
@@ -616,6 +862,12 @@ pub enum HttpEncoding {
     Gzip,
     /// A format using the [Brotli](https://en.wikipedia.org/wiki/Brotli) algorithm.
     Br,
+    /// A format using the [Zstandard](https://en.wikipedia.org/wiki/Zstd) algorithm.
+    ///
+    /// Zstandard compresses better than gzip at a lower CPU cost than brotli. The compression
+    /// level is configured with `http.zstd_level`. Sentry does not accept this encoding, so it
+    /// can only be used if the upstream is a Relay that supports zstd.
+    Zstd,
 }
 
 impl HttpEncoding {
@@ -628,6 +880,8 @@ impl HttpEncoding {
             Self::Gzip
         } else if str.eq_ignore_ascii_case("deflate") {
             Self::Deflate
+        } else if str.eq_ignore_ascii_case("zstd") {
+            Self::Zstd
         } else {
             Self::Identity
         }
@@ -642,6 +896,7 @@ impl HttpEncoding {
             Self::Deflate => Some("deflate"),
             Self::Gzip => Some("gzip"),
             Self::Br => Some("br"),
+            Self::Zstd => Some("zstd"),
         }
     }
 }
@@ -685,6 +940,11 @@ struct Http {
     /// During a network outage relay will try to reconnect and will buffer all upstream messages
     /// until it manages to reconnect.
     outage_grace_period: u64,
+    /// The interval in seconds at which Relay probes the upstream for liveness.
+    ///
+    /// Heartbeats detect connectivity issues before real traffic arrives and count towards the
+    /// network outage grace period. Defaults to `None`, which disables heartbeats.
+    heartbeat_interval: Option<u64>,
     /// Content encoding to apply to upstream store requests.
     ///
     /// By default, Relay applies `gzip` content encoding to compress upstream requests. Compression
@@ -699,7 +959,14 @@ struct Http {
     ///  - `deflate`: Compression using a zlib header with deflate encoding.
     ///  - `gzip` (default): Compression using gzip.
     ///  - `br`: Compression using the brotli algorithm.
+    ///  - `zstd`: Compression using the zstandard algorithm. Sentry does not accept this encoding,
+    ///    so it requires the upstream to be a Relay that supports zstd.
     encoding: HttpEncoding,
+    /// The compression level for the `zstd` encoding, between `1` and `22`.
+    ///
+    /// Higher levels compress better at the expense of CPU time. Defaults to `3`.
+    #[serde(deserialize_with = "deserialize_zstd_level")]
+    zstd_level: i32,
 }
 
 impl Default for Http {
@@ -711,11 +978,29 @@ impl Default for Http {
             host_header: None,
             auth_interval: Some(600), // 10 minutes
             outage_grace_period: DEFAULT_NETWORK_OUTAGE_GRACE_PERIOD,
+            heartbeat_interval: None,
             encoding: HttpEncoding::Gzip,
+            zstd_level: 3,
         }
     }
 }
 
+/// Controls how envelopes are handled when the envelope buffer is full.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BufferOverflowPolicy {
+    /// Rejects incoming envelopes until there is space in the buffer again.
+    RejectNew,
+    /// Drops the oldest queued envelope to make space for the incoming envelope.
+    DropOldest,
+}
+
+impl Default for BufferOverflowPolicy {
+    fn default() -> Self {
+        BufferOverflowPolicy::RejectNew
+    }
+}
+
 /// Controls internal caching behavior.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(default)]
@@ -725,6 +1010,15 @@ struct Cache {
     /// Continue using project state this many seconds after cache expiry while a new state is
     /// being fetched. This is added on top of `project_expiry` and `miss_expiry`. Default is 0.
     project_grace_period: u32,
+    /// Keep serving the previous project state for this many seconds after the first update that
+    /// fails to parse. Default is 0, which marks the project invalid immediately.
+    invalid_state_grace_period: u32,
+    /// Skip enforcement of cached rate limits for this many seconds after a project state has
+    /// been loaded. Quotas from the project state are still enforced. Default is 0.
+    rate_limit_grace_after_state_load: u32,
+    /// Discard cached rate limits this many seconds after they were last received, even if they
+    /// have not expired yet. Defaults to `None`, which keeps rate limits until they expire.
+    rate_limit_max_age: Option<u32>,
     /// The cache timeout for downstream relay info (public keys) in seconds.
     relay_expiry: u32,
     /// The cache timeout for envelopes (store) before dropping them.
@@ -733,6 +1027,10 @@ struct Cache {
     /// The maximum amount of envelopes to queue before dropping them.
     #[serde(alias = "event_buffer_size")]
     envelope_buffer_size: u32,
+    /// Controls which envelopes are dropped when the envelope buffer is full.
+    ///
+    /// Can be `reject_new` (default) or `drop_oldest`.
+    buffer_overflow_policy: BufferOverflowPolicy,
     /// The cache timeout for non-existing entries.
     miss_expiry: u32,
     /// The buffer timeout for batched queries before sending them upstream in ms.
@@ -745,6 +1043,22 @@ struct Cache {
     file_interval: u32,
     /// Interval for evicting outdated project configs from memory.
     eviction_interval: u32,
+    /// The maximum number of projects held in the project cache.
+    ///
+    /// Once exceeded, the least recently used projects are evicted. Defaults to `None`, which
+    /// does not limit the number of projects.
+    max_projects: Option<usize>,
+    /// The maximum number of projects requested from the upstream at the same time.
+    ///
+    /// This further limits the batched requests bounded by `batch_size` and
+    /// `limits.max_concurrent_queries`. Further projects are queued for the next batched request.
+    /// Defaults to `None`, which does not add a limit.
+    max_concurrent_fetches: Option<usize>,
+    /// Store cached project states compressed in memory.
+    ///
+    /// This reduces memory consumption for Relays serving many projects, at the cost of
+    /// decompressing the state on every access. Defaults to `false`.
+    compress_project_states: bool,
 }
 
 impl Default for Cache {
@@ -752,14 +1066,21 @@ impl Default for Cache {
         Cache {
             project_expiry: 300, // 5 minutes
             project_grace_period: 0,
+            invalid_state_grace_period: 0,
+            rate_limit_grace_after_state_load: 0,
+            rate_limit_max_age: None,
             relay_expiry: 3600,   // 1 hour
             envelope_expiry: 600, // 10 minutes
             envelope_buffer_size: 1000,
+            buffer_overflow_policy: BufferOverflowPolicy::default(),
             miss_expiry: 60,     // 1 minute
             batch_interval: 100, // 100ms
             batch_size: 500,
             file_interval: 10,     // 10 seconds
             eviction_interval: 60, // 60 seconds
+            max_projects: None,
+            max_concurrent_fetches: None,
+            compress_project_states: false,
         }
     }
 }
@@ -783,8 +1104,14 @@ pub enum KafkaTopic {
     MetricsSessions,
     /// Any metric that is extracted from transactions.
     MetricsTransactions,
+    /// Any metric that is extracted from error events.
+    MetricsErrors,
+    /// Metadata describing custom metrics.
+    MetricMeta,
     /// Profiles
     Profiles,
+    /// Continuous profiling chunks, not bound to a transaction.
+    ProfileChunks,
     /// ReplayRecording, large blobs sent by the replay sdk
     ReplayRecordings,
 }
@@ -805,16 +1132,22 @@ pub struct TopicAssignments {
     pub outcomes_billing: Option<TopicAssignment>,
     /// Session health topic name.
     pub sessions: TopicAssignment,
-    /// Default topic name for all aggregate metrics. Specialized topics for session-based and
-    /// transaction-based metrics can be configured via `metrics_sessions` and
-    /// `metrics_transactions` each.
+    /// Default topic name for all aggregate metrics. Specialized topics for session-based,
+    /// transaction-based and error-based metrics can be configured via `metrics_sessions`,
+    /// `metrics_transactions` and `metrics_errors` each.
     pub metrics: TopicAssignment,
     /// Topic name for metrics extracted from sessions. Defaults to the assignment of `metrics`.
     pub metrics_sessions: Option<TopicAssignment>,
     /// Topic name for metrics extracted from transactions. Defaults to the assignment of `metrics`.
     pub metrics_transactions: Option<TopicAssignment>,
+    /// Topic name for metrics extracted from error events. Defaults to the assignment of `metrics`.
+    pub metrics_errors: Option<TopicAssignment>,
+    /// Metric metadata topic name.
+    pub metric_meta: TopicAssignment,
     /// Stacktrace topic name
     pub profiles: TopicAssignment,
+    /// Profile chunks topic name.
+    pub profile_chunks: TopicAssignment,
     /// Recordings topic name.
     pub replay_recordings: TopicAssignment,
 }
@@ -833,7 +1166,10 @@ impl TopicAssignments {
             KafkaTopic::MetricsTransactions => {
                 self.metrics_transactions.as_ref().unwrap_or(&self.metrics)
             }
+            KafkaTopic::MetricsErrors => self.metrics_errors.as_ref().unwrap_or(&self.metrics),
+            KafkaTopic::MetricMeta => &self.metric_meta,
             KafkaTopic::Profiles => &self.profiles,
+            KafkaTopic::ProfileChunks => &self.profile_chunks,
             KafkaTopic::ReplayRecordings => &self.replay_recordings,
         }
     }
@@ -851,7 +1187,10 @@ impl Default for TopicAssignments {
             metrics: "ingest-metrics".to_owned().into(),
             metrics_sessions: None,
             metrics_transactions: None,
+            metrics_errors: None,
+            metric_meta: "ingest-metric-meta".to_owned().into(),
             profiles: "profiles".to_owned().into(),
+            profile_chunks: "profile-chunks".to_owned().into(),
             replay_recordings: "ingest-replay-recordings".to_owned().into(),
         }
     }
@@ -944,6 +1283,10 @@ fn default_max_rate_limit() -> Option<u32> {
     Some(300) // 5 minutes
 }
 
+fn default_log_sample_rate() -> f64 {
+    1.0
+}
+
 /// Controls Sentry-internal event processing.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Processing {
@@ -955,12 +1298,35 @@ pub struct Processing {
     /// Maximum future timestamp of ingested events.
     #[serde(default = "default_max_secs_in_future")]
     pub max_secs_in_future: u32,
+    /// Clamp timestamps of events beyond `max_secs_in_future` to the time they were received.
+    #[serde(default)]
+    pub clamp_future_timestamps: bool,
     /// Maximum age of ingested events. Older events will be adjusted to `now()`.
     #[serde(default = "default_max_secs_in_past")]
     pub max_secs_in_past: u32,
     /// Maximum age of ingested sessions. Older sessions will be dropped.
     #[serde(default = "default_max_session_secs_in_past")]
     pub max_session_secs_in_past: u32,
+    /// Canonicalize user IP addresses on events and remove invalid ones.
+    #[serde(default)]
+    pub canonicalize_ip_addresses: bool,
+    /// Map level aliases to canonical levels and replace unknown levels with `error`.
+    #[serde(default)]
+    pub normalize_levels: bool,
+    /// Derive a `device.class` tag from the device context of events.
+    #[serde(default)]
+    pub derive_device_class: bool,
+    /// Redact the values of query string parameters in request URLs of events.
+    #[serde(default)]
+    pub scrub_query_strings: bool,
+    /// Collapse consecutive identical breadcrumbs on events into one.
+    #[serde(default)]
+    pub dedupe_breadcrumbs: bool,
+    /// Debug images added to all events that contain debug meta, such as org-wide system images.
+    ///
+    /// Every image must be valid and declare a debug identifier, otherwise Relay refuses to start.
+    #[serde(default)]
+    pub global_debug_images: Vec<serde_json::Value>,
     /// Kafka producer configurations.
     pub kafka_config: Vec<KafkaConfigParam>,
     /// Additional kafka producer configurations.
@@ -999,6 +1365,11 @@ pub struct Processing {
     /// Maximum rate limit to report to clients.
     #[serde(default = "default_max_rate_limit")]
     pub max_rate_limit: Option<u32>,
+    /// The fraction of high-frequency per-envelope debug and trace logs that are emitted.
+    ///
+    /// Must be between `0.0` and `1.0`.
+    #[serde(default = "default_log_sample_rate")]
+    pub log_sample_rate: f64,
 }
 
 impl Default for Processing {
@@ -1008,8 +1379,15 @@ impl Default for Processing {
             enabled: false,
             geoip_path: None,
             max_secs_in_future: default_max_secs_in_future(),
+            clamp_future_timestamps: false,
             max_secs_in_past: default_max_secs_in_past(),
             max_session_secs_in_past: default_max_session_secs_in_past(),
+            canonicalize_ip_addresses: false,
+            normalize_levels: false,
+            derive_device_class: false,
+            scrub_query_strings: false,
+            dedupe_breadcrumbs: false,
+            global_debug_images: Vec::new(),
             kafka_config: Vec::new(),
             secondary_kafka_configs: BTreeMap::new(),
             topics: TopicAssignments::default(),
@@ -1017,6 +1395,7 @@ impl Default for Processing {
             attachment_chunk_size: default_chunk_size(),
             projectconfig_cache_prefix: default_projectconfig_cache_prefix(),
             max_rate_limit: default_max_rate_limit(),
+            log_sample_rate: default_log_sample_rate(),
         }
     }
 }
@@ -1029,6 +1408,8 @@ pub struct OutcomeAggregatorConfig {
     pub bucket_interval: u64,
     /// Defines how often all buckets are flushed, in seconds.
     pub flush_interval: u64,
+    /// The maximum number of buckets held before they are flushed, regardless of the interval.
+    pub max_batch_size: usize,
 }
 
 impl Default for OutcomeAggregatorConfig {
@@ -1036,6 +1417,7 @@ impl Default for OutcomeAggregatorConfig {
         Self {
             bucket_interval: 60,
             flush_interval: 120,
+            max_batch_size: 1000,
         }
     }
 }
@@ -1115,6 +1497,20 @@ impl<'de> Deserialize<'de> for EmitOutcomes {
     }
 }
 
+/// A field of client reports that can be emitted as outcomes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientOutcomeField {
+    /// Events filtered by an inbound data filter.
+    FilteredEvents,
+    /// Events filtered by a sampling rule.
+    FilteredSamplingEvents,
+    /// Events dropped due to rate limits.
+    RateLimitedEvents,
+    /// Events discarded on the client side.
+    DiscardedEvents,
+}
+
 /// Outcome generation specific configuration values.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(default)]
@@ -1125,6 +1521,14 @@ pub struct Outcomes {
     pub emit_outcomes: EmitOutcomes,
     /// Controls wheather client reported outcomes should be emitted.
     pub emit_client_outcomes: bool,
+    /// The client report fields that are emitted as outcomes.
+    ///
+    /// Possible values are `discarded_events`, `rate_limited_events`, `filtered_events`, and
+    /// `filtered_sampling_events`. Defaults to all fields. Only applies if `emit_client_outcomes`
+    /// is enabled.
+    pub client_outcome_fields: Option<Vec<ClientOutcomeField>>,
+    /// Forwards client reports unchanged instead of converting them into outcomes.
+    pub forward_client_reports_raw: bool,
     /// The maximum number of outcomes that are batched before being sent
     /// via http to the upstream (only applies to non processing relays).
     pub batch_size: usize,
@@ -1143,6 +1547,8 @@ impl Default for Outcomes {
         Outcomes {
             emit_outcomes: EmitOutcomes::AsClientReports,
             emit_client_outcomes: true,
+            client_outcome_fields: None,
+            forward_client_reports_raw: false,
             batch_size: 1000,
             batch_interval: 500,
             source: None,
@@ -1333,6 +1739,7 @@ impl Config {
             return Err(ConfigError::new(ConfigErrorKind::ProcessingNotAvailable).file(&path));
         }
 
+        config.validate()?;
         Ok(config)
     }
 
@@ -1340,12 +1747,34 @@ impl Config {
     ///
     /// This is mostly useful for tests.
     pub fn from_json_value(value: serde_json::Value) -> Result<Config, ConfigError> {
-        Ok(Config {
+        let config = Config {
             values: serde_json::from_value(value)
                 .map_err(|err| ConfigError::wrap(err, ConfigErrorKind::BadJson))?,
             credentials: None,
             path: PathBuf::new(),
-        })
+        };
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks loaded values that cannot be validated during deserialization.
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.processing_enabled() && self.values.relay.test_pii_config.is_some() {
+            return Err(ConfigError::new(ConfigErrorKind::InvalidValue).field("test_pii_config"));
+        }
+
+        if !(0.0..=1.0).contains(&self.values.relay.min_effective_sample_rate) {
+            return Err(
+                ConfigError::new(ConfigErrorKind::InvalidValue).field("min_effective_sample_rate")
+            );
+        }
+
+        if !(0.0..=1.0).contains(&self.values.processing.log_sample_rate) {
+            return Err(ConfigError::new(ConfigErrorKind::InvalidValue).field("log_sample_rate"));
+        }
+
+        Ok(())
     }
 
     /// Override configuration with values coming from other sources (e.g. env variables or
@@ -1577,11 +2006,33 @@ impl Config {
         self.values.relay.mode
     }
 
+    /// Returns the inline PII config for testing.
+    ///
+    /// This is only available in capture mode and never if processing is enabled. The value is
+    /// not validated and must be parsed into a PII config by the caller.
+    pub fn test_pii_config(&self) -> Option<&serde_json::Value> {
+        if self.relay_mode() != RelayMode::Capture || self.processing_enabled() {
+            return None;
+        }
+
+        self.values.relay.test_pii_config.as_ref()
+    }
+
     /// Returns the upstream target as descriptor.
     pub fn upstream_descriptor(&self) -> &UpstreamDescriptor<'_> {
         &self.values.relay.upstream
     }
 
+    /// Returns the secondary upstream that receives mirrored items, if configured.
+    pub fn mirror_upstream_descriptor(&self) -> Option<&UpstreamDescriptor<'static>> {
+        self.values.relay.mirror_upstream.as_ref()
+    }
+
+    /// Returns the envelope item types that are copied to the mirror upstream.
+    pub fn mirror_item_types(&self) -> &[String] {
+        &self.values.relay.mirror_item_types
+    }
+
     /// Returns the custom HTTP "Host" header.
     pub fn http_host_header(&self) -> Option<&str> {
         self.values.http.host_header.as_deref()
@@ -1619,6 +2070,92 @@ impl Config {
         self.values.relay.override_project_ids
     }
 
+    /// Returns `true` if sample rates should be removed from forwarded events.
+    ///
+    /// Defaults to `false`.
+    pub fn strip_sample_rates_on_forward(&self) -> bool {
+        self.values.relay.strip_sample_rates_on_forward
+    }
+
+    /// Returns the minimum sample rate that dynamic sampling applies to events.
+    ///
+    /// Defaults to `0.0`, which does not raise any sample rate.
+    pub fn min_effective_sample_rate(&self) -> f64 {
+        self.values.relay.min_effective_sample_rate
+    }
+
+    /// Returns the public keys of projects that bypass dynamic sampling of events.
+    pub fn force_keep_projects(&self) -> &[ProjectKey] {
+        &self.values.relay.force_keep_projects
+    }
+
+    /// Returns the public keys of projects that are exempt from data scrubbing.
+    pub fn scrubbing_exempt_keys(&self) -> &[ProjectKey] {
+        &self.values.relay.scrubbing_exempt_keys
+    }
+
+    /// Returns `true` if only the event is dropped when it fails normalization.
+    ///
+    /// Defaults to `false`, which fails the entire envelope.
+    pub fn keep_items_of_invalid_events(&self) -> bool {
+        self.values.relay.keep_items_of_invalid_events
+    }
+
+    /// Returns `true` if transactions with malformed trace ids are dropped.
+    pub fn validate_trace_ids(&self) -> bool {
+        self.values.relay.validate_trace_ids
+    }
+
+    /// Returns `true` if all envelopes must be signed by a trusted Relay of the project.
+    pub fn require_trusted_relay_signature(&self) -> bool {
+        self.values.relay.require_trusted_relay_signature
+    }
+
+    /// Returns `true` if error events without a timestamp are dropped.
+    pub fn require_event_timestamp(&self) -> bool {
+        self.values.relay.require_event_timestamp
+    }
+
+    /// Returns `true` if repeated attachments with the same filename are dropped.
+    pub fn dedupe_attachments_by_filename(&self) -> bool {
+        self.values.relay.dedupe_attachments_by_filename
+    }
+
+    /// Returns `true` if processing stages are recorded as spans on Relay's own transactions.
+    pub fn self_instrument_transactions(&self) -> bool {
+        self.values.relay.self_instrument_transactions
+    }
+
+    /// Returns the region in which this Relay is deployed, if configured.
+    pub fn relay_region(&self) -> Option<&str> {
+        self.values.relay.region.as_deref()
+    }
+
+    /// Returns the environment assigned to events and sessions without one, if configured.
+    pub fn default_environment(&self) -> Option<&str> {
+        self.values.relay.default_environment.as_deref()
+    }
+
+    /// Returns the tags added to all events that do not set them already.
+    pub fn static_event_tags(&self) -> &BTreeMap<String, String> {
+        &self.values.relay.static_event_tags
+    }
+
+    /// Returns the minimum accepted SDK version for each SDK name.
+    pub fn min_sdk_versions(&self) -> &BTreeMap<String, semver::Version> {
+        &self.values.relay.min_sdk_versions
+    }
+
+    /// Returns `true` if forwarded envelopes carry a hash of their payload.
+    pub fn attach_payload_hash(&self) -> bool {
+        self.values.relay.attach_payload_hash
+    }
+
+    /// Returns the name of the header that carries the client IP to the upstream, if configured.
+    pub fn client_ip_header_name(&self) -> Option<&str> {
+        self.values.relay.client_ip_header.as_deref()
+    }
+
     /// Returns `true` if Relay requires authentication for readiness.
     ///
     /// See [`ReadinessCondition`] for more information.
@@ -1649,11 +2186,24 @@ impl Config {
         Duration::from_secs(self.values.http.outage_grace_period)
     }
 
+    /// Returns the interval at which Relay sends heartbeats to the upstream, if enabled.
+    pub fn upstream_heartbeat_interval(&self) -> Option<Duration> {
+        match self.values.http.heartbeat_interval {
+            None | Some(0) => None,
+            Some(secs) => Some(Duration::from_secs(secs)),
+        }
+    }
+
     /// Content encoding of upstream requests.
     pub fn http_encoding(&self) -> HttpEncoding {
         self.values.http.encoding
     }
 
+    /// Compression level of upstream requests with the `zstd` encoding.
+    pub fn http_zstd_level(&self) -> i32 {
+        self.values.http.zstd_level
+    }
+
     /// Returns whether this Relay should emit outcomes.
     ///
     /// This is `true` either if `outcomes.emit_outcomes` is explicitly enabled, or if this Relay is
@@ -1678,6 +2228,21 @@ impl Config {
         self.values.outcomes.emit_client_outcomes
     }
 
+    /// Returns the client report fields that are emitted as outcomes.
+    ///
+    /// If `None`, outcomes of all fields are emitted.
+    pub fn client_outcome_fields(&self) -> Option<&[ClientOutcomeField]> {
+        self.values.outcomes.client_outcome_fields.as_deref()
+    }
+
+    /// Returns whether client reports should be forwarded as-is.
+    ///
+    /// If enabled, client reports are retained in the envelope and are not converted into
+    /// outcomes. This takes precedence over [`emit_client_outcomes`](Self::emit_client_outcomes).
+    pub fn forward_client_reports_raw(&self) -> bool {
+        self.values.outcomes.forward_client_reports_raw
+    }
+
     /// Returns the maximum number of outcomes that are batched before being sent
     pub fn outcome_batch_size(&self) -> usize {
         self.values.outcomes.batch_size
@@ -1749,6 +2314,16 @@ impl Config {
         self.values.metrics.sample_rate
     }
 
+    /// Returns true if the number of PII rule matches should be reported as metric.
+    pub fn metrics_pii_rule_hits(&self) -> bool {
+        self.values.metrics.pii_rule_hits
+    }
+
+    /// Returns true if every received envelope should be reported as metric.
+    pub fn metrics_envelopes_received(&self) -> bool {
+        self.values.metrics.envelopes_received
+    }
+
     /// Returns the default timeout for all upstream HTTP requests.
     pub fn http_timeout(&self) -> Duration {
         Duration::from_secs(self.values.http.timeout.into())
@@ -1784,6 +2359,11 @@ impl Config {
         self.values.cache.envelope_buffer_size
     }
 
+    /// Returns the policy for envelopes exceeding the envelope buffer.
+    pub fn buffer_overflow_policy(&self) -> BufferOverflowPolicy {
+        self.values.cache.buffer_overflow_policy
+    }
+
     /// Returns the expiry timeout for cached misses before trying to refetch.
     pub fn cache_miss_expiry(&self) -> Duration {
         Duration::from_secs(self.values.cache.miss_expiry.into())
@@ -1794,6 +2374,26 @@ impl Config {
         Duration::from_secs(self.values.cache.project_grace_period.into())
     }
 
+    /// Returns the duration after the first unparseable update during which the previous project
+    /// state is retained.
+    pub fn invalid_project_state_grace_period(&self) -> Duration {
+        Duration::from_secs(self.values.cache.invalid_state_grace_period.into())
+    }
+
+    /// Returns the duration after a project state load during which cached rate limits are not
+    /// enforced.
+    pub fn rate_limit_grace_after_state_load(&self) -> Duration {
+        Duration::from_secs(self.values.cache.rate_limit_grace_after_state_load.into())
+    }
+
+    /// Returns the duration after which cached rate limits are discarded, if limited.
+    pub fn rate_limit_max_age(&self) -> Option<Duration> {
+        self.values
+            .cache
+            .rate_limit_max_age
+            .map(|secs| Duration::from_secs(secs.into()))
+    }
+
     /// Returns the number of seconds during which batchable queries are collected before sending
     /// them in a single request.
     pub fn query_batch_interval(&self) -> Duration {
@@ -1811,11 +2411,42 @@ impl Config {
         Duration::from_secs(self.values.cache.eviction_interval.into())
     }
 
+    /// Returns the maximum number of projects held in the project cache, if limited.
+    pub fn max_cached_projects(&self) -> Option<usize> {
+        self.values.cache.max_projects
+    }
+
+    /// Returns the maximum number of project states requested from the upstream at once, if
+    /// limited.
+    pub fn max_concurrent_project_fetches(&self) -> Option<usize> {
+        self.values.cache.max_concurrent_fetches
+    }
+
+    /// Returns `true` if cached project states are stored compressed in memory.
+    pub fn compress_cached_project_states(&self) -> bool {
+        self.values.cache.compress_project_states
+    }
+
     /// Returns the maximum size of an event payload in bytes.
     pub fn max_event_size(&self) -> usize {
         self.values.limits.max_event_size.as_bytes()
     }
 
+    /// Returns the event payload size in bytes above which events are sampled, if configured.
+    pub fn soft_max_event_size(&self) -> Option<usize> {
+        self.values
+            .limits
+            .soft_max_event_size
+            .map(|size| size.as_bytes())
+    }
+
+    /// Returns the rate at which events exceeding the soft maximum event size are accepted.
+    ///
+    /// Defaults to `0.1`.
+    pub fn soft_max_event_sample_rate(&self) -> f64 {
+        self.values.limits.soft_max_event_sample_rate
+    }
+
     /// Returns the maximum size of each attachment.
     pub fn max_attachment_size(&self) -> usize {
         self.values.limits.max_attachment_size.as_bytes()
@@ -1827,6 +2458,14 @@ impl Config {
         self.values.limits.max_attachments_size.as_bytes()
     }
 
+    /// Returns the combined size of attachments in bytes retained during processing, if configured.
+    pub fn max_total_attachment_bytes(&self) -> Option<usize> {
+        self.values
+            .limits
+            .max_total_attachment_bytes
+            .map(|size| size.as_bytes())
+    }
+
     /// Returns the maxmium combined size of client reports in bytes.
     pub fn max_client_reports_size(&self) -> usize {
         self.values.limits.max_client_reports_size.as_bytes()
@@ -1844,6 +2483,68 @@ impl Config {
         self.values.limits.max_session_count
     }
 
+    /// Returns the maximum number of aggregates in a single sessions item.
+    pub fn max_session_aggregates(&self) -> usize {
+        self.values.limits.max_session_aggregates
+    }
+
+    /// Returns the maximum sequence number of session updates.
+    pub fn max_session_sequence(&self) -> u64 {
+        self.values.limits.max_session_sequence
+    }
+
+    /// Returns the maximum size of request bodies retained on events in bytes.
+    pub fn max_request_body_size(&self) -> usize {
+        self.values.limits.max_request_body_size.as_bytes()
+    }
+
+    /// Returns the maximum number of breadcrumbs retained on an event.
+    pub fn max_breadcrumbs(&self) -> usize {
+        self.values.limits.max_breadcrumbs
+    }
+
+    /// Returns the maximum depth of values in event payloads, if enabled.
+    pub fn max_json_depth(&self) -> Option<usize> {
+        self.values.limits.max_json_depth
+    }
+
+    /// Returns the maximum number of exceptions retained in the exception chain of an event.
+    pub fn max_exceptions(&self) -> usize {
+        self.values.limits.max_exceptions
+    }
+
+    /// Returns the maximum number of threads retained on an event.
+    pub fn max_event_threads(&self) -> usize {
+        self.values.limits.max_event_threads
+    }
+
+    /// Returns the maximum number of entries retained in the fingerprint of an event.
+    pub fn max_fingerprint_entries(&self) -> usize {
+        self.values.limits.max_fingerprint_entries
+    }
+
+    /// Returns the maximum number of measurements retained on a transaction.
+    pub fn max_measurements(&self) -> usize {
+        self.values.limits.max_measurements
+    }
+
+    /// Returns the maximum number of characters in log messages of events.
+    pub fn max_message_length(&self) -> usize {
+        self.values.limits.max_message_length
+    }
+
+    /// Returns the maximum number of tags retained on an event.
+    pub fn max_event_tags(&self) -> usize {
+        self.values.limits.max_event_tags
+    }
+
+    /// Returns the maximum number of characters in releases of events and sessions.
+    ///
+    /// Longer releases are truncated to this length.
+    pub fn max_release_length(&self) -> usize {
+        self.values.limits.max_release_length
+    }
+
     /// Returns the maximum payload size for general API requests.
     pub fn max_api_payload_size(&self) -> usize {
         self.values.limits.max_api_payload_size.as_bytes()
@@ -1900,6 +2601,16 @@ impl Config {
         Duration::from_secs(self.values.limits.shutdown_timeout)
     }
 
+    /// Returns the maximum number of envelopes retained in capture mode.
+    pub fn max_captured_envelopes(&self) -> usize {
+        self.values.limits.max_captured_envelopes
+    }
+
+    /// Returns the duration after which unused captured envelopes are evicted in capture mode.
+    pub fn captured_envelope_ttl(&self) -> Duration {
+        Duration::from_secs(self.values.limits.captured_envelope_ttl)
+    }
+
     /// Returns the number of cores to use for thread pools.
     pub fn cpu_concurrency(&self) -> usize {
         self.values.limits.max_thread_count
@@ -1932,6 +2643,14 @@ impl Config {
         self.values.processing.max_secs_in_future.into()
     }
 
+    /// Returns `true` if event timestamps beyond [`max_secs_in_future`](Self::max_secs_in_future)
+    /// should be clamped to the time the event was received.
+    ///
+    /// Defaults to `false`.
+    pub fn clamp_future_timestamps(&self) -> bool {
+        self.values.processing.clamp_future_timestamps
+    }
+
     /// Maximum age of ingested events. Older events will be adjusted to `now()`.
     pub fn max_secs_in_past(&self) -> i64 {
         self.values.processing.max_secs_in_past.into()
@@ -1942,6 +2661,55 @@ impl Config {
         self.values.processing.max_session_secs_in_past.into()
     }
 
+    /// Returns `true` if user IP addresses on events should be canonicalized.
+    ///
+    /// Defaults to `false`.
+    pub fn canonicalize_ip_addresses(&self) -> bool {
+        self.values.processing.canonicalize_ip_addresses
+    }
+
+    /// Returns `true` if invalid event levels should be normalized.
+    ///
+    /// Defaults to `false`.
+    pub fn normalize_levels(&self) -> bool {
+        self.values.processing.normalize_levels
+    }
+
+    /// Returns `true` if a `device.class` tag should be derived from the device context of events.
+    ///
+    /// Defaults to `false`.
+    pub fn derive_device_class(&self) -> bool {
+        self.values.processing.derive_device_class
+    }
+
+    /// Returns `true` if query string values in request URLs should be redacted.
+    ///
+    /// Defaults to `false`.
+    pub fn scrub_query_strings(&self) -> bool {
+        self.values.processing.scrub_query_strings
+    }
+
+    /// Returns `true` if consecutive identical breadcrumbs should be collapsed.
+    ///
+    /// Defaults to `false`.
+    pub fn dedupe_breadcrumbs(&self) -> bool {
+        self.values.processing.dedupe_breadcrumbs
+    }
+
+    /// Returns the fraction of per-envelope debug and trace logs that are emitted.
+    ///
+    /// Defaults to `1.0`, which emits all logs.
+    pub fn processing_log_sample_rate(&self) -> f64 {
+        self.values.processing.log_sample_rate
+    }
+
+    /// Returns the debug images added to all events with debug meta.
+    ///
+    /// The images are not validated and must be parsed by the caller.
+    pub fn global_debug_images(&self) -> &[serde_json::Value] {
+        &self.values.processing.global_debug_images
+    }
+
     /// Topic name and list of Kafka configuration parameters for a given topic.
     pub fn kafka_topic_name(&self, topic: KafkaTopic) -> &str {
         self.values.processing.topics.get(topic).topic_name()
@@ -2004,6 +2772,16 @@ impl Config {
         forward.unwrap_or_else(|| !self.processing_enabled())
     }
 
+    /// Returns `true` if envelopes without items should be rejected.
+    pub fn reject_empty_envelopes(&self) -> bool {
+        self.values.routing.reject_empty_envelopes
+    }
+
+    /// Returns `true` if new envelopes should be rejected during upstream maintenance.
+    pub fn read_only_mode(&self) -> bool {
+        self.values.routing.read_only_mode
+    }
+
     /// Returns the host and port of the AWS lambda runtime API.
     pub fn aws_runtime_api(&self) -> Option<&str> {
         self.values.aws.runtime_api.as_deref()
@@ -2051,6 +2829,152 @@ cache:
         }
     }
 
+    #[test]
+    fn test_test_pii_config() {
+        let pii_config = serde_json::json!({"applications": {"$string": ["@email"]}});
+
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"mode": "capture", "test_pii_config": pii_config}
+        }))
+        .unwrap();
+        assert_eq!(config.test_pii_config(), Some(&pii_config));
+
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"mode": "managed", "test_pii_config": pii_config}
+        }))
+        .unwrap();
+        assert_eq!(config.test_pii_config(), None);
+
+        let result = Config::from_json_value(serde_json::json!({
+            "relay": {"mode": "capture", "test_pii_config": pii_config},
+            "processing": {"enabled": true, "kafka_config": []}
+        }));
+        assert_eq!(result.unwrap_err().kind(), ConfigErrorKind::InvalidValue);
+    }
+
+    #[test]
+    fn test_soft_max_event_sample_rate() {
+        let config = Config::from_json_value(serde_json::json!({
+            "limits": {"soft_max_event_sample_rate": 0.5}
+        }))
+        .unwrap();
+        assert_eq!(config.soft_max_event_sample_rate(), 0.5);
+
+        for rate in &[-0.1, 1.5] {
+            let result = Config::from_json_value(serde_json::json!({
+                "limits": {"soft_max_event_sample_rate": rate}
+            }));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn test_client_outcome_fields() {
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {"client_outcome_fields": ["discarded_events", "rate_limited_events"]}
+        }))
+        .unwrap();
+        assert_eq!(
+            config.client_outcome_fields(),
+            Some(
+                &[
+                    ClientOutcomeField::DiscardedEvents,
+                    ClientOutcomeField::RateLimitedEvents
+                ][..]
+            )
+        );
+
+        let result = Config::from_json_value(serde_json::json!({
+            "outcomes": {"client_outcome_fields": ["dropped_events"]}
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_min_effective_sample_rate() {
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"min_effective_sample_rate": 0.1}
+        }))
+        .unwrap();
+        assert_eq!(config.min_effective_sample_rate(), 0.1);
+
+        for rate in &[-0.1, 1.5] {
+            let result = Config::from_json_value(serde_json::json!({
+                "relay": {"min_effective_sample_rate": rate}
+            }));
+            assert_eq!(result.unwrap_err().kind(), ConfigErrorKind::InvalidValue);
+        }
+    }
+
+    #[test]
+    fn test_log_sample_rate() {
+        let config = Config::from_json_value(serde_json::json!({
+            "processing": {"kafka_config": [], "log_sample_rate": 0.01}
+        }))
+        .unwrap();
+        assert_eq!(config.processing_log_sample_rate(), 0.01);
+
+        for rate in &[-0.1, 1.5] {
+            let result = Config::from_json_value(serde_json::json!({
+                "processing": {"kafka_config": [], "log_sample_rate": rate}
+            }));
+            assert_eq!(result.unwrap_err().kind(), ConfigErrorKind::InvalidValue);
+        }
+    }
+
+    #[test]
+    fn test_min_sdk_versions() {
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"min_sdk_versions": {"sentry.python": "1.5.0"}}
+        }))
+        .unwrap();
+        assert_eq!(
+            config.min_sdk_versions().get("sentry.python"),
+            Some(&semver::Version::new(1, 5, 0))
+        );
+
+        let result = Config::from_json_value(serde_json::json!({
+            "relay": {"min_sdk_versions": {"sentry.python": "1.5"}}
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_zstd_level() {
+        let config = Config::from_json_value(serde_json::json!({
+            "http": {"encoding": "zstd", "zstd_level": 10}
+        }))
+        .unwrap();
+        assert_eq!(config.http_zstd_level(), 10);
+
+        for level in &[0, 23] {
+            let result = Config::from_json_value(serde_json::json!({
+                "http": {"zstd_level": level}
+            }));
+            assert!(result.is_err());
+        }
+    }
+
+    #[test]
+    fn test_upstream_heartbeat_interval() {
+        assert_eq!(Config::default().upstream_heartbeat_interval(), None);
+
+        let config = Config::from_json_value(serde_json::json!({
+            "http": {"heartbeat_interval": 30}
+        }))
+        .unwrap();
+        assert_eq!(
+            config.upstream_heartbeat_interval(),
+            Some(Duration::from_secs(30))
+        );
+
+        let config = Config::from_json_value(serde_json::json!({
+            "http": {"heartbeat_interval": 0}
+        }))
+        .unwrap();
+        assert_eq!(config.upstream_heartbeat_interval(), None);
+    }
+
     #[test]
     fn test_emit_outcomes_invalid() {
         assert!(matches!(
@@ -126,6 +126,9 @@ pub enum FilterStatKey {
 
     /// Filtered due to invalid CSP policy.
     InvalidCsp,
+
+    /// Filtered by a denied exception type.
+    DeniedException,
 }
 
 // An event grouped to a removed group.
@@ -152,6 +155,7 @@ impl FilterStatKey {
             FilterStatKey::Localhost => "localhost",
             FilterStatKey::WebCrawlers => "web-crawlers",
             FilterStatKey::InvalidCsp => "invalid-csp",
+            FilterStatKey::DeniedException => "denied-exception",
         }
     }
 }
@@ -175,6 +179,7 @@ impl<'a> TryFrom<&'a str> for FilterStatKey {
             "localhost" => FilterStatKey::Localhost,
             "web-crawlers" => FilterStatKey::WebCrawlers,
             "invalid-csp" => FilterStatKey::InvalidCsp,
+            "denied-exception" => FilterStatKey::DeniedException,
             other => {
                 return Err(other);
             }
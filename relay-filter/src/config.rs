@@ -143,6 +143,20 @@ impl ErrorMessagesFilterConfig {
     }
 }
 
+/// Configuration for the denied exception types filter.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct DeniedExceptionTypesFilterConfig {
+    /// List of exception type patterns that will be filtered.
+    pub patterns: GlobPatterns,
+}
+
+impl DeniedExceptionTypesFilterConfig {
+    /// Returns true if no configuration for this filter is given.
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+}
+
 /// Configuration for the releases filter.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct ReleasesFilterConfig {
@@ -199,6 +213,13 @@ pub struct FiltersConfig {
     #[serde(default, skip_serializing_if = "ErrorMessagesFilterConfig::is_empty")]
     pub error_messages: ErrorMessagesFilterConfig,
 
+    /// Configuration for the Denied Exception Types filter.
+    #[serde(
+        default,
+        skip_serializing_if = "DeniedExceptionTypesFilterConfig::is_empty"
+    )]
+    pub denied_exception_types: DeniedExceptionTypesFilterConfig,
+
     /// Configuration for the Legacy Browsers filter.
     #[serde(default, skip_serializing_if = "LegacyBrowsersFilterConfig::is_empty")]
     pub legacy_browsers: LegacyBrowsersFilterConfig,
@@ -220,6 +241,7 @@ impl FiltersConfig {
             && self.web_crawlers.is_empty()
             && self.csp.is_empty()
             && self.error_messages.is_empty()
+            && self.denied_exception_types.is_empty()
             && self.legacy_browsers.is_empty()
             && self.localhost.is_empty()
             && self.releases.is_empty()
@@ -250,6 +272,9 @@ mod tests {
             error_messages: ErrorMessagesFilterConfig {
                 patterns: [],
             },
+            denied_exception_types: DeniedExceptionTypesFilterConfig {
+                patterns: [],
+            },
             legacy_browsers: LegacyBrowsersFilterConfig {
                 is_enabled: false,
                 browsers: {},
@@ -285,6 +310,9 @@ mod tests {
             error_messages: ErrorMessagesFilterConfig {
                 patterns: GlobPatterns::new(vec!["Panic".to_string()]),
             },
+            denied_exception_types: DeniedExceptionTypesFilterConfig {
+                patterns: GlobPatterns::new(vec!["NoisyError".to_string()]),
+            },
             legacy_browsers: LegacyBrowsersFilterConfig {
                 is_enabled: false,
                 browsers: [LegacyBrowser::Ie9].iter().cloned().collect(),
@@ -318,6 +346,11 @@ mod tests {
               "Panic"
             ]
           },
+          "deniedExceptionTypes": {
+            "patterns": [
+              "NoisyError"
+            ]
+          },
           "legacyBrowsers": {
             "isEnabled": false,
             "options": [
@@ -0,0 +1,97 @@
+//! Implements event filtering based on the exception type.
+//!
+//! A user may configure a list of exception types that should never be ingested, for instance,
+//! because they are thrown by a noisy third-party library.
+
+use relay_general::protocol::Event;
+
+use crate::{DeniedExceptionTypesFilterConfig, FilterStatKey};
+
+/// Filters events containing an exception with a denied type.
+pub fn should_filter(
+    event: &Event,
+    config: &DeniedExceptionTypesFilterConfig,
+) -> Result<(), FilterStatKey> {
+    if config.is_empty() {
+        return Ok(());
+    }
+
+    let exceptions = event
+        .exceptions
+        .value()
+        .and_then(|values| values.values.value());
+
+    for exception in exceptions.into_iter().flatten() {
+        let ty = exception
+            .value()
+            .and_then(|exception| exception.ty.as_str());
+        if let Some(ty) = ty {
+            if config.patterns.is_match(ty) {
+                return Err(FilterStatKey::DeniedException);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use relay_general::protocol::{Exception, Values};
+    use relay_general::types::Annotated;
+
+    use crate::GlobPatterns;
+
+    fn get_event_with_exception_types(types: &[&str]) -> Event {
+        let exceptions = types
+            .iter()
+            .map(|ty| {
+                Annotated::new(Exception {
+                    ty: Annotated::new(ty.to_string()),
+                    ..Exception::default()
+                })
+            })
+            .collect();
+
+        Event {
+            exceptions: Annotated::new(Values::new(exceptions)),
+            ..Event::default()
+        }
+    }
+
+    #[test]
+    fn test_exception_type_filtering() {
+        let config = DeniedExceptionTypesFilterConfig {
+            patterns: GlobPatterns::new(vec!["NoisyLib*Error".to_string()]),
+        };
+
+        let examples = &[
+            (&["NoisyLibTimeoutError"][..], true),
+            (&["ValueError", "NoisyLibIOError"][..], true),
+            (&["noisylibtimeouterror"][..], true),
+            (&["ValueError"][..], false),
+            (&["NoisyLibWarning"][..], false),
+            (&[][..], false),
+        ];
+
+        for &(types, should_filter_event) in examples {
+            let event = get_event_with_exception_types(types);
+            let expected = if should_filter_event {
+                Err(FilterStatKey::DeniedException)
+            } else {
+                Ok(())
+            };
+
+            assert_eq!(should_filter(&event, &config), expected, "{:?}", types);
+        }
+    }
+
+    #[test]
+    fn test_exception_type_filtering_empty_config() {
+        let config = DeniedExceptionTypesFilterConfig::default();
+        let event = get_event_with_exception_types(&["NoisyLibTimeoutError"]);
+        assert_eq!(should_filter(&event, &config), Ok(()));
+    }
+}
@@ -6,6 +6,7 @@
 //! * browser extensions (filter events caused by known problematic browser extensions)
 //! * web crawlers (filter events sent by user agents known to be web crawlers)
 //! * legacy browsers (filter events originating from legacy browsers, can be configured)
+//! * denied exception types (filter events containing exceptions of configured types)
 #![warn(missing_docs)]
 #![doc(
     html_logo_url = "https://raw.githubusercontent.com/getsentry/relay/master/artwork/relay-icon.png",
@@ -26,6 +27,7 @@ pub mod web_crawlers;
 
 mod common;
 mod config;
+mod exception_types;
 mod releases;
 
 #[cfg(test)]
@@ -51,6 +53,7 @@ pub fn should_filter(
     client_ips::should_filter(client_ip, &config.client_ips)?;
     releases::should_filter(event, &config.releases)?;
     error_messages::should_filter(event, &config.error_messages)?;
+    exception_types::should_filter(event, &config.denied_exception_types)?;
     localhost::should_filter(event, &config.localhost)?;
     browser_extensions::should_filter(event, &config.browser_extensions)?;
     legacy_browsers::should_filter(event, &config.legacy_browsers)?;
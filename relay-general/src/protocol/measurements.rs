@@ -50,6 +50,29 @@ impl IntoValue for MetricUnit {
 
 impl ProcessValue for MetricUnit {}
 
+/// Names of the standard web vitals.
+pub const WEB_VITALS: &[&str] = &["fcp", "lcp", "fid", "fp", "ttfb", "ttfb.requesttime", "cls"];
+
+/// Names of the core web vitals, a subset of [`WEB_VITALS`].
+pub const CORE_WEB_VITALS: &[&str] = &["lcp", "fcp", "fid", "cls"];
+
+/// Names of the standard mobile vitals, including those reported by React Native.
+pub const MOBILE_VITALS: &[&str] = &[
+    "app_start_cold",
+    "app_start_warm",
+    "frames_total",
+    "frames_slow",
+    "frames_frozen",
+    "stall_count",
+    "stall_total_time",
+    "stall_longest_time",
+];
+
+/// Returns `true` if the measurement is a standard web or mobile vital.
+pub fn is_vital(name: &str) -> bool {
+    WEB_VITALS.contains(&name) || MOBILE_VITALS.contains(&name)
+}
+
 /// An individual observed measurement.
 #[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, IntoValue, ProcessValue)]
 pub struct Measurement {
@@ -25,6 +25,7 @@ mod stacktrace;
 mod tags;
 mod templateinfo;
 mod thread;
+mod transaction;
 mod types;
 mod user;
 mod user_report;
@@ -51,7 +52,9 @@ pub use self::event::{
 pub use self::exception::Exception;
 pub use self::fingerprint::Fingerprint;
 pub use self::logentry::{LogEntry, Message};
-pub use self::measurements::{Measurement, Measurements};
+pub use self::measurements::{
+    is_vital, Measurement, Measurements, CORE_WEB_VITALS, MOBILE_VITALS, WEB_VITALS,
+};
 pub use self::mechanism::{CError, MachException, Mechanism, MechanismMeta, PosixSignal};
 pub use self::metrics::{Metrics, SampleRate};
 pub use self::relay_info::RelayInfo;
@@ -68,6 +71,7 @@ pub use self::stacktrace::{Frame, FrameData, FrameVars, RawStacktrace, Stacktrac
 pub use self::tags::{TagEntry, Tags};
 pub use self::templateinfo::TemplateInfo;
 pub use self::thread::{Thread, ThreadId};
+pub use self::transaction::{TransactionInfo, TransactionSource};
 pub use self::types::{
     datetime_to_timestamp, Addr, AsPair, InvalidRegVal, IpAddr, JsonLenientString, LenientString,
     Level, PairList, ParseLevelError, RegVal, Timestamp, Values,
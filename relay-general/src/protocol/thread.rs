@@ -122,6 +122,11 @@ pub struct Thread {
     /// A flag indicating whether the thread was in the foreground.  Defaults to `false`.
     pub current: Annotated<bool>,
 
+    /// The state of the thread at the time of the event, such as `running`, `blocked` or
+    /// `waiting`.
+    #[metastructure(max_chars = "enumlike")]
+    pub state: Annotated<String>,
+
     /// Additional arbitrary fields for forwards compatibility.
     #[metastructure(additional_properties)]
     pub other: Object<Value>,
@@ -159,6 +164,7 @@ fn test_thread_roundtrip() {
   "name": "myname",
   "crashed": true,
   "current": true,
+  "state": "blocked",
   "other": "value"
 }"#;
     let thread = Annotated::new(Thread {
@@ -168,6 +174,7 @@ fn test_thread_roundtrip() {
         raw_stacktrace: Annotated::empty(),
         crashed: Annotated::new(true),
         current: Annotated::new(true),
+        state: Annotated::new("blocked".to_string()),
         other: {
             let mut map = Map::new();
             map.insert(
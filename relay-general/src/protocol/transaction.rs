@@ -0,0 +1,128 @@
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::processor::ProcessValue;
+use crate::types::{Annotated, Object, Value};
+
+/// Describes how the name of the transaction was determined.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "jsonschema", derive(JsonSchema))]
+#[cfg_attr(feature = "jsonschema", schemars(rename_all = "lowercase"))]
+pub enum TransactionSource {
+    /// User-defined name set through `set_transaction_name`.
+    Custom,
+    /// Raw URL, potentially containing identifiers.
+    Url,
+    /// Parametrized URL or route.
+    Route,
+    /// Name of the view handling the request.
+    View,
+    /// Named after a software component, such as a function or class name.
+    Component,
+    /// Name of a background task (e.g. a Celery task).
+    Task,
+    /// The name was canonicalized by Relay, replacing identifiers with placeholders.
+    Sanitized,
+    /// This is the default value set by Relay for legacy SDKs.
+    Unknown,
+    /// Any other unknown source that is not explicitly defined above.
+    Other(String),
+}
+
+impl TransactionSource {
+    /// Returns the string representation of this transaction source.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Custom => "custom",
+            Self::Url => "url",
+            Self::Route => "route",
+            Self::View => "view",
+            Self::Component => "component",
+            Self::Task => "task",
+            Self::Sanitized => "sanitized",
+            Self::Unknown => "unknown",
+            Self::Other(ref s) => s,
+        }
+    }
+}
+
+impl FromStr for TransactionSource {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "custom" => Self::Custom,
+            "url" => Self::Url,
+            "route" => Self::Route,
+            "view" => Self::View,
+            "component" => Self::Component,
+            "task" => Self::Task,
+            "sanitized" => Self::Sanitized,
+            "unknown" => Self::Unknown,
+            s => Self::Other(s.to_owned()),
+        })
+    }
+}
+
+impl fmt::Display for TransactionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+derive_string_meta_structure!(TransactionSource, "a transaction source");
+
+impl ProcessValue for TransactionSource {}
+
+/// Additional information about the name of the transaction.
+#[derive(Clone, Debug, Default, PartialEq, Empty, FromValue, IntoValue, ProcessValue)]
+#[cfg_attr(feature = "jsonschema", derive(JsonSchema))]
+pub struct TransactionInfo {
+    /// Describes how the name of the transaction was determined.
+    ///
+    /// This will be used by the server to decide whether or not to scrub identifiers from the
+    /// transaction name, or replace the entire name with a placeholder.
+    pub source: Annotated<TransactionSource>,
+
+    /// The unmodified transaction name as obtained by the source.
+    ///
+    /// This value will only be set if the transaction name was modified during event processing.
+    #[metastructure(max_chars = "culprit", trim_whitespace = "true")]
+    pub original: Annotated<String>,
+
+    /// Additional arbitrary fields for forwards compatibility.
+    #[metastructure(additional_properties, retain = "true")]
+    pub other: Object<Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction_info_roundtrip() {
+        let json = r#"{
+  "source": "route",
+  "original": "/auth/login/john123/"
+}"#;
+
+        let info = Annotated::new(TransactionInfo {
+            source: Annotated::new(TransactionSource::Route),
+            original: Annotated::new("/auth/login/john123/".to_owned()),
+            ..Default::default()
+        });
+
+        assert_eq_dbg!(info, Annotated::from_json(json).unwrap());
+        assert_eq_str!(json, info.to_json_pretty().unwrap());
+    }
+
+    #[test]
+    fn test_transaction_source_other() {
+        let source = Annotated::<TransactionSource>::from_json(r#""something""#).unwrap();
+        assert_eq_dbg!(
+            source.value(),
+            Some(&TransactionSource::Other("something".to_owned()))
+        );
+    }
+}
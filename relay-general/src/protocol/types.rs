@@ -653,6 +653,15 @@ impl Level {
             _ => return None,
         })
     }
+
+    /// Parses a level, additionally accepting common aliases such as `warn` and `err`.
+    pub fn parse_lenient(string: &str) -> Result<Self, ParseLevelError> {
+        match string {
+            "warn" => Ok(Level::Warning),
+            "err" => Ok(Level::Error),
+            _ => string.parse(),
+        }
+    }
 }
 
 impl FromStr for Level {
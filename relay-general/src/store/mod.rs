@@ -22,10 +22,15 @@ mod trimming;
 
 pub use self::clock_drift::ClockDriftProcessor;
 pub use self::geo::{GeoIpError, GeoIpLookup};
+pub use normalize::breadcrumbs::trim_breadcrumbs;
 pub use normalize::breakdowns::{
     get_breakdown_measurements, BreakdownConfig, BreakdownsConfig, SpanOperationsConfig,
 };
-pub use normalize::{is_valid_platform, normalize_dist};
+pub use normalize::device_class::DeviceClass;
+pub use normalize::transaction_name::{
+    normalize_transaction_name, TransactionNameConfig, TransactionNameRule,
+};
+pub use normalize::{is_valid_platform, normalize_dist, GlobalDebugImages};
 pub use transactions::{get_measurement, get_transaction_op, validate_timestamps};
 
 /// The config for store.
@@ -56,11 +61,73 @@ pub struct StoreConfig {
     /// When `true` it adds context information extracted from the user agent
     pub normalize_user_agent: Option<bool>,
 
+    /// When `true`, the user's IP address is replaced with its canonical form. Invalid IP addresses
+    /// are removed.
+    pub canonicalize_ip_addresses: Option<bool>,
+
+    /// When `true`, invalid levels are normalized. Aliases such as `warn` are mapped to their
+    /// canonical level, and unknown levels are replaced with `error`.
+    pub normalize_levels: Option<bool>,
+
+    /// When `true`, consecutive identical breadcrumbs are collapsed into one.
+    pub dedupe_breadcrumbs: Option<bool>,
+
+    /// The maximum number of breadcrumbs retained on an event. Defaults to `100`.
+    pub max_breadcrumbs: Option<usize>,
+
+    /// The maximum number of exceptions retained in an event's exception chain. Defaults to `25`.
+    ///
+    /// Both the innermost and the outermost exceptions are retained.
+    pub max_exceptions: Option<usize>,
+
+    /// The maximum number of threads retained on an event. Defaults to `100`.
+    ///
+    /// Crashed threads are always retained.
+    pub max_threads: Option<usize>,
+
+    /// When `true`, the values of query string parameters in request URLs are redacted.
+    pub scrub_query_strings: Option<bool>,
+
+    /// When `true`, a `device.class` tag is derived from the device context of the event.
+    pub derive_device_class: Option<bool>,
+
     /// Emit breakdowns based on given configuration.
     pub breakdowns: Option<normalize::breakdowns::BreakdownsConfig>,
 
     /// Emit additional span attributes based on given configuration.
     pub span_attributes: BTreeSet<SpanAttribute>,
+
+    /// The maximum number of tags retained on an event. Defaults to `50`.
+    pub max_tags: Option<usize>,
+
+    /// The maximum number of measurements retained on a transaction. Defaults to `30`.
+    pub max_measurements: Option<usize>,
+
+    /// Names of measurements that are never removed by `max_measurements`.
+    pub priority_measurements: BTreeSet<String>,
+
+    /// The maximum number of entries retained in the fingerprint. Defaults to `32`.
+    pub max_fingerprint_entries: Option<usize>,
+
+    /// The maximum size of the request body in bytes. Defaults to `8192`.
+    pub max_request_body_bytes: Option<usize>,
+
+    /// The maximum length of log messages in characters. Defaults to `8192`.
+    pub max_message_length: Option<usize>,
+
+    /// The maximum depth of values in the entire event payload. Disabled by default.
+    ///
+    /// Values reaching this depth are trimmed like the contents of databags. Only applies if
+    /// trimming is enabled.
+    pub max_json_depth: Option<usize>,
+
+    /// Debug images that are added to every event with debug meta.
+    ///
+    /// Images that the event already contains with the same debug identifier are not duplicated.
+    pub global_debug_images: GlobalDebugImages,
+
+    /// Replace identifiers in transaction names based on given configuration.
+    pub transaction_name_config: Option<normalize::transaction_name::TransactionNameConfig>,
 }
 
 /// The processor that normalizes events for store.
@@ -125,8 +192,13 @@ impl<'a> Processor for StoreProcessor<'a> {
         }
 
         if enable_trimming {
+            let mut trimming_processor = match self.config.max_json_depth {
+                Some(max_depth) => trimming::TrimmingProcessor::with_max_depth(max_depth),
+                None => trimming::TrimmingProcessor::new(),
+            };
+
             // Trim large strings and databags down
-            trimming::TrimmingProcessor::new().process_event(event, meta, state)?;
+            trimming_processor.process_event(event, meta, state)?;
         }
 
         Ok(())
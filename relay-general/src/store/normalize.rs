@@ -1,4 +1,5 @@
 use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
 use std::hash::{Hash, Hasher};
 use std::mem;
 use std::sync::Arc;
@@ -7,31 +8,363 @@ use chrono::{Duration, Utc};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use smallvec::SmallVec;
 
 use crate::processor::{MaxChars, ProcessValue, ProcessingState, Processor};
 use crate::protocol::{
     self, AsPair, Breadcrumb, ClientSdkInfo, Context, Contexts, DebugImage, Event, EventId,
-    EventType, Exception, Frame, HeaderName, HeaderValue, Headers, IpAddr, Level, LogEntry,
-    Request, SpanStatus, Stacktrace, Tags, TraceContext, User, VALID_PLATFORMS,
+    EventType, Exception, Fingerprint, Frame, HeaderName, HeaderValue, Headers, IpAddr, Level,
+    LogEntry, Measurements, ParseLevelError, Request, SpanStatus, Stacktrace, TagEntry, Tags,
+    Thread, TraceContext, User, Values, VALID_PLATFORMS,
 };
 use crate::store::{ClockDriftProcessor, GeoIpLookup, StoreConfig};
 use crate::types::{
-    Annotated, Empty, Error, ErrorKind, FromValue, Meta, Object, ProcessingAction,
-    ProcessingResult, Value,
+    Annotated, Empty, Error, ErrorKind, FromValue, IntoValue, Meta, Object, ProcessingAction,
+    ProcessingResult, Remark, RemarkType, SerializePayload, SkipSerialization, Value,
 };
 
+use self::device_class::DeviceClass;
+
+pub mod breadcrumbs;
 pub mod breakdowns;
 mod contexts;
+pub mod device_class;
 mod logentry;
 mod mechanism;
 mod request;
 mod spans;
 mod stacktrace;
+pub mod transaction_name;
 
 #[cfg(feature = "uaparser")]
 mod user_agent;
 
+/// The default maximum number of tags retained on an event.
+const DEFAULT_MAX_TAGS: usize = 50;
+
+/// The default maximum size of the request body in bytes.
+const DEFAULT_MAX_REQUEST_BODY_BYTES: usize = 8 * 1024;
+
+/// The default maximum length of log messages in characters.
+const DEFAULT_MAX_MESSAGE_LENGTH: usize = 8192;
+
+/// The default maximum number of measurements retained on a transaction.
+const DEFAULT_MAX_MEASUREMENTS: usize = 30;
+
+/// The default maximum number of breadcrumbs retained on an event.
+const DEFAULT_MAX_BREADCRUMBS: usize = 100;
+
+/// The default maximum number of threads retained on an event.
+const DEFAULT_MAX_THREADS: usize = 100;
+
+/// The default maximum number of exceptions retained in an event's exception chain.
+const DEFAULT_MAX_EXCEPTIONS: usize = 25;
+
+/// The default maximum number of entries retained in an event's fingerprint.
+const DEFAULT_MAX_FINGERPRINT_ENTRIES: usize = 32;
+
+/// Tags that are preferably retained when the tags of an event exceed the limit.
+const WELL_KNOWN_TAGS: &[&str] = &[
+    "server_name",
+    "site",
+    "level",
+    "logger",
+    "transaction",
+    "url",
+    "browser",
+    "browser.name",
+    "os",
+    "os.name",
+    "device",
+    "device.class",
+    "runtime",
+    "runtime.name",
+    "mechanism",
+    "handled",
+];
+
+/// Removes tags exceeding `max_tags`, preferably keeping [`WELL_KNOWN_TAGS`].
+///
+/// The order of the remaining tags is preserved. If tags are removed, the original length and a
+/// remark are recorded in the meta data of the tags.
+fn trim_tags(tags: &mut Annotated<Tags>, max_tags: usize) {
+    let (tags, meta) = match tags {
+        Annotated(Some(tags), meta) => (&mut (tags.0).0, meta),
+        Annotated(None, _) => return,
+    };
+
+    let original_length = tags.len();
+    if original_length <= max_tags {
+        return;
+    }
+
+    let is_well_known = |entry: &Annotated<TagEntry>| {
+        entry
+            .value()
+            .and_then(|tag| tag.key())
+            .map_or(false, |key| WELL_KNOWN_TAGS.contains(&key))
+    };
+
+    let well_known_count = tags.iter().filter(|entry| is_well_known(entry)).count();
+    let mut well_known_budget = max_tags;
+    let mut other_budget = max_tags.saturating_sub(well_known_count);
+
+    tags.retain(|entry| {
+        let budget = if is_well_known(entry) {
+            &mut well_known_budget
+        } else {
+            &mut other_budget
+        };
+
+        if *budget > 0 {
+            *budget -= 1;
+            true
+        } else {
+            false
+        }
+    });
+
+    meta.set_original_length(Some(original_length));
+    meta.add_remark(Remark::new(RemarkType::Removed, "!limit"));
+}
+
+/// Removes measurements exceeding `max_measurements`, preferably keeping web and mobile vitals.
+///
+/// Priority measurements are always retained and do not count towards the limit. If measurements
+/// are removed, the original length and a remark are recorded in the meta data of the
+/// measurements.
+fn trim_measurements(
+    measurements: &mut Annotated<Measurements>,
+    max_measurements: usize,
+    priority_measurements: &BTreeSet<String>,
+) {
+    let (measurements, meta) = match measurements {
+        Annotated(Some(measurements), meta) => (&mut measurements.0, meta),
+        Annotated(None, _) => return,
+    };
+
+    let original_length = measurements.len();
+    let priority_count = measurements
+        .keys()
+        .filter(|name| priority_measurements.contains(*name))
+        .count();
+    if original_length - priority_count <= max_measurements {
+        return;
+    }
+
+    let vitals_count = measurements
+        .keys()
+        .filter(|name| !priority_measurements.contains(*name))
+        .filter(|name| protocol::is_vital(name))
+        .count();
+    let mut other_budget = max_measurements.saturating_sub(vitals_count);
+    let mut vitals_budget = max_measurements;
+
+    let removed: Vec<_> = measurements
+        .keys()
+        .filter(|name| {
+            if priority_measurements.contains(*name) {
+                return false;
+            }
+
+            let budget = if protocol::is_vital(name) {
+                &mut vitals_budget
+            } else {
+                &mut other_budget
+            };
+
+            if *budget > 0 {
+                *budget -= 1;
+                false
+            } else {
+                true
+            }
+        })
+        .cloned()
+        .collect();
+
+    for name in removed {
+        measurements.remove(&name);
+    }
+
+    meta.set_original_length(Some(original_length));
+    meta.add_remark(Remark::new(RemarkType::Removed, "!limit"));
+}
+
+/// Removes fingerprint entries exceeding `max_entries`.
+///
+/// If entries are removed, the original length and a remark are recorded in the meta data of the
+/// fingerprint.
+fn trim_fingerprint(fingerprint: &mut Annotated<Fingerprint>, max_entries: usize) {
+    let (fingerprint, meta) = match fingerprint {
+        Annotated(Some(fingerprint), meta) => (fingerprint, meta),
+        Annotated(None, _) => return,
+    };
+
+    let original_length = fingerprint.len();
+    if original_length <= max_entries {
+        return;
+    }
+
+    fingerprint.truncate(max_entries);
+    meta.set_original_length(Some(original_length));
+    meta.add_remark(Remark::new(RemarkType::Removed, "!limit"));
+}
+
+/// Removes threads exceeding `max_threads`, always retaining crashed threads.
+fn trim_threads(threads: &mut Annotated<Values<Thread>>, max_threads: usize) {
+    let values = match threads.value_mut() {
+        Some(threads) => &mut threads.values,
+        None => return,
+    };
+
+    let (values, meta) = match values {
+        Annotated(Some(values), meta) => (values, meta),
+        Annotated(None, _) => return,
+    };
+
+    let original_length = values.len();
+    if original_length <= max_threads {
+        return;
+    }
+
+    let is_crashed =
+        |thread: &Annotated<Thread>| thread.value().and_then(|t| t.crashed.value()) == Some(&true);
+
+    let crashed = values.iter().filter(|thread| is_crashed(thread)).count();
+    let mut remaining = max_threads.saturating_sub(crashed);
+    values.retain(|thread| {
+        if is_crashed(thread) {
+            true
+        } else if remaining > 0 {
+            remaining -= 1;
+            true
+        } else {
+            false
+        }
+    });
+
+    meta.set_original_length(Some(original_length));
+    meta.add_remark(Remark::new(RemarkType::Removed, "!limit"));
+}
+
+/// Removes exceptions from the middle of a chain that exceeds `max_exceptions`.
+///
+/// Exceptions are ordered from the innermost cause to the outermost exception. Both ends of the
+/// chain are retained, since they are most relevant for grouping and debugging.
+fn trim_exceptions(exceptions: &mut Annotated<Values<Exception>>, max_exceptions: usize) {
+    let values = match exceptions.value_mut() {
+        Some(exceptions) => &mut exceptions.values,
+        None => return,
+    };
+
+    let (values, meta) = match values {
+        Annotated(Some(values), meta) => (values, meta),
+        Annotated(None, _) => return,
+    };
+
+    let original_length = values.len();
+    if original_length <= max_exceptions {
+        return;
+    }
+
+    let head = max_exceptions / 2;
+    let tail = max_exceptions - head;
+    values.drain(head..original_length - tail);
+
+    meta.set_original_length(Some(original_length));
+    meta.add_remark(Remark::new(RemarkType::Removed, "!limit"));
+}
+
+/// Parses the IP address and replaces it with its canonical representation.
+///
+/// Surrounding whitespace is ignored. IP addresses that cannot be parsed are removed with an
+/// error. The `{{auto}}` marker is retained.
+fn canonicalize_ip_address(ip_address: &mut Annotated<IpAddr>) -> ProcessingResult {
+    ip_address.apply(|ip_address, meta| {
+        if ip_address.is_auto() {
+            return Ok(());
+        }
+
+        match ip_address.as_str().trim().parse::<std::net::IpAddr>() {
+            Ok(parsed) => {
+                *ip_address = IpAddr::from(parsed);
+                Ok(())
+            }
+            Err(_) => {
+                meta.add_error(Error::expected("an ip address"));
+                Err(ProcessingAction::DeleteValueSoft)
+            }
+        }
+    })
+}
+
+/// Returns the identifier used to detect duplicate debug images.
+fn debug_image_id(image: &DebugImage) -> Option<String> {
+    match image {
+        DebugImage::Apple(image) => image.uuid.value().map(|uuid| uuid.to_string()),
+        DebugImage::Proguard(image) => image.uuid.value().map(|uuid| uuid.to_string()),
+        DebugImage::Symbolic(image)
+        | DebugImage::MachO(image)
+        | DebugImage::Elf(image)
+        | DebugImage::Pe(image)
+        | DebugImage::Wasm(image) => image.debug_id.value().map(|id| id.to_string()),
+        DebugImage::Other(_) => None,
+    }
+}
+
+/// Debug images that are added to every event with debug meta, such as org-wide system images.
+///
+/// The images are parsed once when deserializing. Deserialization fails if an image is invalid or
+/// does not declare a debug identifier. The images are shared, so cloning is cheap.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GlobalDebugImages(Arc<Vec<Annotated<DebugImage>>>);
+
+impl GlobalDebugImages {
+    /// Returns `true` if there are no global debug images.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Serialize for GlobalDebugImages {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let images =
+            (self.0.iter()).map(|image| SerializePayload(image, SkipSerialization::default()));
+        serializer.collect_seq(images)
+    }
+}
+
+impl<'de> Deserialize<'de> for GlobalDebugImages {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let values = Vec::<serde_json::Value>::deserialize(deserializer)?;
+        let mut images = Vec::with_capacity(values.len());
+
+        for value in values {
+            let image = DebugImage::from_value(Annotated::<Value>::from(value));
+            let valid = !IntoValue::extract_meta_tree(&image).has_errors()
+                && image.value().and_then(debug_image_id).is_some();
+
+            if !valid {
+                return Err(serde::de::Error::custom(
+                    "invalid global debug image or missing debug id",
+                ));
+            }
+
+            images.push(image);
+        }
+
+        Ok(GlobalDebugImages(Arc::new(images)))
+    }
+}
+
 /// Validate fields that go into a `sentry.models.BoundedIntegerField`.
 fn validate_bounded_integer_field(value: u64) -> ProcessingResult {
     if value < 2_147_483_647 {
@@ -117,9 +450,28 @@ impl<'a> NormalizeProcessor<'a> {
         if event.ty.value() != Some(&EventType::Transaction) {
             // Only transaction events may have a measurements interface
             event.measurements = Annotated::empty();
+        } else {
+            let max_measurements = self
+                .config
+                .max_measurements
+                .unwrap_or(DEFAULT_MAX_MEASUREMENTS);
+            trim_measurements(
+                &mut event.measurements,
+                max_measurements,
+                &self.config.priority_measurements,
+            );
         }
     }
 
+    /// Limits the number of entries in the fingerprint.
+    fn normalize_fingerprint(&self, event: &mut Event) {
+        let max_entries = self
+            .config
+            .max_fingerprint_entries
+            .unwrap_or(DEFAULT_MAX_FINGERPRINT_ENTRIES);
+        trim_fingerprint(&mut event.fingerprint, max_entries);
+    }
+
     /// Emit any breakdowns
     fn normalize_breakdowns(&self, event: &mut Event) {
         match &self.config.breakdowns {
@@ -128,6 +480,41 @@ impl<'a> NormalizeProcessor<'a> {
         }
     }
 
+    /// Collapses consecutive identical breadcrumbs, if configured.
+    fn dedupe_breadcrumbs(&self, event: &mut Event) {
+        if self.config.dedupe_breadcrumbs.unwrap_or(false) {
+            breadcrumbs::dedupe_breadcrumbs(event);
+        }
+    }
+
+    /// Removes the oldest breadcrumbs exceeding `max_breadcrumbs`.
+    fn trim_breadcrumbs(&self, event: &mut Event) {
+        let max_breadcrumbs = self
+            .config
+            .max_breadcrumbs
+            .unwrap_or(DEFAULT_MAX_BREADCRUMBS);
+        breadcrumbs::trim_breadcrumbs(event, max_breadcrumbs);
+    }
+
+    /// Removes exceptions exceeding `max_exceptions` while retaining both ends of the chain.
+    fn trim_exceptions(&self, event: &mut Event) {
+        let max_exceptions = self.config.max_exceptions.unwrap_or(DEFAULT_MAX_EXCEPTIONS);
+        trim_exceptions(&mut event.exceptions, max_exceptions);
+    }
+
+    /// Removes threads exceeding `max_threads` while retaining the crashed thread.
+    fn trim_threads(&self, event: &mut Event) {
+        let max_threads = self.config.max_threads.unwrap_or(DEFAULT_MAX_THREADS);
+        trim_threads(&mut event.threads, max_threads);
+    }
+
+    /// Replaces identifiers in transaction names, if configured.
+    fn normalize_transaction_name(&self, event: &mut Event) {
+        if let Some(ref config) = self.config.transaction_name_config {
+            transaction_name::normalize_transaction_name(event, config);
+        }
+    }
+
     fn normalize_spans(&self, event: &mut Event) {
         if event.ty.value() == Some(&EventType::Transaction) {
             spans::normalize_spans(event, &self.config.span_attributes);
@@ -191,6 +578,12 @@ impl<'a> NormalizeProcessor<'a> {
 
     /// Removes internal tags and adds tags for well-known attributes.
     fn normalize_event_tags(&self, event: &mut Event) -> ProcessingResult {
+        let device_class = if self.config.derive_device_class.unwrap_or(false) {
+            DeviceClass::from_event(event)
+        } else {
+            None
+        };
+
         let tags = &mut event.tags.value_mut().get_or_insert_with(Tags::default).0;
         let environment = &mut event.environment;
         if environment.is_empty() {
@@ -249,6 +642,14 @@ impl<'a> NormalizeProcessor<'a> {
             tags.insert("site".to_string(), site);
         }
 
+        if let Some(device_class) = device_class {
+            let value = Annotated::new(device_class.name().to_string());
+            tags.insert("device.class".to_string(), value);
+        }
+
+        let max_tags = self.config.max_tags.unwrap_or(DEFAULT_MAX_TAGS);
+        trim_tags(&mut event.tags, max_tags);
+
         Ok(())
     }
 
@@ -419,6 +820,62 @@ impl<'a> NormalizeProcessor<'a> {
         Ok(())
     }
 
+    /// Recovers levels that failed to parse from their original value.
+    ///
+    /// Known aliases are mapped to their canonical level. Unknown levels are replaced with
+    /// [`Level::Error`], retaining the original value.
+    fn normalize_level(&self, event: &mut Event) {
+        if !self.config.normalize_levels.unwrap_or(false) || event.level.value().is_some() {
+            return;
+        }
+
+        let parsed = match event.level.meta().original_value() {
+            Some(Value::String(level)) => Level::parse_lenient(level),
+            _ => return,
+        };
+
+        match parsed {
+            Ok(level) => event.level = Annotated::new(level),
+            Err(ParseLevelError) => {
+                event.level.set_value(Some(Level::Error));
+                event
+                    .level
+                    .meta_mut()
+                    .add_remark(Remark::new(RemarkType::Substituted, "!level"));
+            }
+        }
+    }
+
+    /// Adds the configured global debug images to events with debug meta.
+    ///
+    /// Images are skipped if the event already contains an image with the same debug identifier.
+    /// This runs before the event is recursively normalized, so the added images are validated
+    /// like images sent by the client.
+    fn merge_global_debug_images(&self, event: &mut Event) {
+        if self.config.global_debug_images.is_empty() {
+            return;
+        }
+
+        let debug_meta = match event.debug_meta.value_mut() {
+            Some(debug_meta) => debug_meta,
+            None => return,
+        };
+
+        let images = debug_meta.images.get_or_insert_with(Vec::new);
+        let mut debug_ids: BTreeSet<_> = images
+            .iter()
+            .filter_map(|image| image.value().and_then(debug_image_id))
+            .collect();
+
+        for image in self.config.global_debug_images.0.iter() {
+            if let Some(debug_id) = image.value().and_then(debug_image_id) {
+                if debug_ids.insert(debug_id) {
+                    images.push(image.clone());
+                }
+            }
+        }
+    }
+
     fn normalize_user_agent(&self, _event: &mut Event) {
         if self.config.normalize_user_agent.unwrap_or(false) {
             #[cfg(feature = "uaparser")]
@@ -443,6 +900,9 @@ impl<'a> Processor for NormalizeProcessor<'a> {
         // Insert IP addrs before recursing, since geo lookup depends on it.
         self.normalize_ip_addresses(event);
 
+        // Add global debug images before recursing, so they are normalized along with the event.
+        self.merge_global_debug_images(event);
+
         event.process_child_values(self, state)?;
 
         // Override internal attributes, even if they were set in the payload
@@ -486,6 +946,8 @@ impl<'a> Processor for NormalizeProcessor<'a> {
             }
         })?;
 
+        self.normalize_level(event);
+
         // Default required attributes, even if they have errors
         event.errors.get_or_insert_with(Vec::new);
         event.id.get_or_insert_with(EventId::new);
@@ -504,11 +966,17 @@ impl<'a> Processor for NormalizeProcessor<'a> {
         self.normalize_release_dist(event);
         self.normalize_timestamps(event, meta, state)?;
         self.normalize_event_tags(event)?;
+        self.normalize_fingerprint(event);
         self.normalize_exceptions(event)?;
+        self.trim_exceptions(event);
+        self.dedupe_breadcrumbs(event);
+        self.trim_breadcrumbs(event);
+        self.trim_threads(event);
         self.normalize_user_agent(event);
         self.normalize_measurements(event);
         self.normalize_breakdowns(event);
         self.normalize_spans(event);
+        self.normalize_transaction_name(event);
 
         Ok(())
     }
@@ -540,8 +1008,17 @@ impl<'a> Processor for NormalizeProcessor<'a> {
     ) -> ProcessingResult {
         request.process_child_values(self, state)?;
 
+        let max_body_bytes = self
+            .config
+            .max_request_body_bytes
+            .unwrap_or(DEFAULT_MAX_REQUEST_BODY_BYTES);
+        request::limit_request_body(request, max_body_bytes);
         request::normalize_request(request)?;
 
+        if self.config.scrub_query_strings.unwrap_or(false) {
+            request::scrub_query_strings(request);
+        }
+
         Ok(())
     }
 
@@ -558,6 +1035,10 @@ impl<'a> Processor for NormalizeProcessor<'a> {
 
         user.process_child_values(self, state)?;
 
+        if self.config.canonicalize_ip_addresses.unwrap_or(false) {
+            canonicalize_ip_address(&mut user.ip_address)?;
+        }
+
         // Infer user.geo from user.ip_address
         if user.geo.value().is_none() {
             if let Some(geoip_lookup) = self.geoip_lookup {
@@ -593,7 +1074,15 @@ impl<'a> Processor for NormalizeProcessor<'a> {
         meta: &mut Meta,
         _state: &ProcessingState<'_>,
     ) -> ProcessingResult {
-        logentry::normalize_logentry(logentry, meta)
+        logentry::normalize_logentry(logentry, meta)?;
+
+        let max_length = self
+            .config
+            .max_message_length
+            .unwrap_or(DEFAULT_MAX_MESSAGE_LENGTH);
+        logentry::limit_logentry(logentry, max_length);
+
+        Ok(())
     }
 
     fn process_exception(
@@ -718,7 +1207,7 @@ impl<'a> Processor for NormalizeProcessor<'a> {
 #[cfg(test)]
 use crate::{
     processor::process_value,
-    protocol::{PairList, TagEntry},
+    protocol::{NativeImagePath, PairList},
 };
 
 #[cfg(test)]
@@ -875,6 +1364,46 @@ fn test_user_ip_from_invalid_remote_addr() {
     assert_eq_dbg!(Annotated::empty(), event.value().unwrap().user);
 }
 
+#[test]
+fn test_canonicalize_ip_addresses() {
+    let config = StoreConfig {
+        canonicalize_ip_addresses: Some(true),
+        ..StoreConfig::default()
+    };
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+
+    let mut event = Annotated::new(Event {
+        user: Annotated::new(User {
+            ip_address: Annotated::new(IpAddr("2001:DB8:0:0::1 ".to_string())),
+            ..User::default()
+        }),
+        ..Event::default()
+    });
+
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let ip_addr = get_value!(event.user.ip_address!);
+    assert_eq_dbg!(ip_addr, &IpAddr("2001:db8::1".to_string()));
+
+    let mut event = Annotated::new(Event {
+        user: Annotated::new(User {
+            ip_address: Annotated::new(IpAddr("fe80::1%eth0".to_string())),
+            ..User::default()
+        }),
+        ..Event::default()
+    });
+
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let ip_address = &get_value!(event.user!).ip_address;
+    assert!(ip_address.value().is_none());
+    assert!(ip_address.meta().has_errors());
+    assert_eq_dbg!(
+        ip_address.meta().original_value(),
+        Some(&Value::String("fe80::1%eth0".to_string()))
+    );
+}
+
 #[test]
 fn test_user_ip_from_client_ip_without_auto() {
     let mut event = Annotated::new(Event {
@@ -947,6 +1476,99 @@ fn test_event_level_defaulted() {
     assert_eq_dbg!(get_value!(event.level), Some(&Level::Error));
 }
 
+#[test]
+fn test_normalize_levels() {
+    let config = StoreConfig {
+        normalize_levels: Some(true),
+        ..StoreConfig::default()
+    };
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+
+    let mut event = Annotated::<Event>::from_json(r#"{"level": "warn"}"#).unwrap();
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+    assert_eq_dbg!(get_value!(event.level), Some(&Level::Warning));
+    assert!(!get_value!(event!).level.meta().has_errors());
+
+    let mut event = Annotated::<Event>::from_json(r#"{"level": "err"}"#).unwrap();
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+    assert_eq_dbg!(get_value!(event.level), Some(&Level::Error));
+
+    let mut event = Annotated::<Event>::from_json(r#"{"level": "verbose"}"#).unwrap();
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+    let level = &get_value!(event!).level;
+    assert_eq_dbg!(level.value(), Some(&Level::Error));
+    assert_eq_dbg!(
+        level.meta().iter_remarks().collect::<Vec<_>>(),
+        vec![&Remark::new(RemarkType::Substituted, "!level")]
+    );
+}
+
+#[test]
+fn test_merge_global_debug_images() {
+    let global_debug_images = serde_json::from_value(serde_json::json!([
+        {
+            "type": "elf",
+            "code_file": "libc.so.6",
+            "debug_id": "b2d2f4e1-9c3a-4a1b-8d6e-1f2a3b4c5d6e",
+            "image_addr": "0x7f0000000000",
+        },
+        {
+            "type": "elf",
+            "code_file": "libm.so.6",
+            "debug_id": "0c8e1a52-3b7f-4d9e-a1c2-9f8e7d6c5b4a",
+            "image_addr": "0x7f1000000000",
+        },
+    ]))
+    .unwrap();
+
+    let config = StoreConfig {
+        global_debug_images,
+        ..StoreConfig::default()
+    };
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+
+    let json = r#"{
+        "debug_meta": {
+            "images": [
+                {
+                    "type": "elf",
+                    "code_file": "/lib/libc.so.6",
+                    "debug_id": "b2d2f4e1-9c3a-4a1b-8d6e-1f2a3b4c5d6e",
+                    "image_addr": "0x7f0000000000"
+                }
+            ]
+        }
+    }"#;
+
+    let mut event = Annotated::<Event>::from_json(json).unwrap();
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let images = get_value!(event.debug_meta.images!);
+    let code_files: Vec<_> = images
+        .iter()
+        .map(|image| match image.value() {
+            Some(DebugImage::Elf(image)) => image.code_file.value().map(NativeImagePath::as_str),
+            _ => None,
+        })
+        .collect();
+
+    assert_eq_dbg!(code_files, vec![Some("/lib/libc.so.6"), Some("libm.so.6")]);
+}
+
+#[test]
+fn test_global_debug_images_invalid() {
+    let invalid = [
+        serde_json::json!([{"type": "elf", "code_file": "libc.so.6"}]),
+        serde_json::json!([{"type": "elf", "debug_id": "invalid"}]),
+        serde_json::json!([{"type": "unknown", "debug_id": "b2d2f4e1-9c3a-4a1b-8d6e-1f2a3b4c5d6e"}]),
+    ];
+
+    for value in &invalid {
+        let result = serde_json::from_value::<GlobalDebugImages>(value.clone());
+        assert!(result.is_err(), "accepted {}", value);
+    }
+}
+
 #[test]
 fn test_transaction_level_untouched() {
     let processor = &mut NormalizeProcessor::default();
@@ -1096,6 +1718,288 @@ fn test_top_level_keys_moved_into_tags() {
     );
 }
 
+#[test]
+fn test_max_fingerprint_entries() {
+    let entries: Vec<_> = (0..5).map(|i| format!("entry{}", i)).collect();
+
+    let mut event = Annotated::new(Event {
+        fingerprint: Annotated::new(entries.into()),
+        ..Event::default()
+    });
+
+    let config = StoreConfig {
+        max_fingerprint_entries: Some(3),
+        ..StoreConfig::default()
+    };
+
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let fingerprint = &event.value().unwrap().fingerprint;
+    assert_eq_dbg!(
+        get_value!(fingerprint!).to_vec(),
+        vec!["entry0", "entry1", "entry2"]
+    );
+    assert_eq_dbg!(fingerprint.meta().original_length(), Some(5));
+    assert_eq_dbg!(
+        fingerprint.meta().iter_remarks().collect::<Vec<_>>(),
+        vec![&Remark::new(RemarkType::Removed, "!limit")]
+    );
+}
+
+#[test]
+fn test_max_exceptions() {
+    let exceptions: Vec<_> = (0..6)
+        .map(|i| {
+            Annotated::new(Exception {
+                ty: Annotated::new(format!("Error{}", i)),
+                ..Exception::default()
+            })
+        })
+        .collect();
+
+    let mut event = Annotated::new(Event {
+        exceptions: Annotated::new(Values::new(exceptions)),
+        ..Event::default()
+    });
+
+    let config = StoreConfig {
+        max_exceptions: Some(3),
+        ..StoreConfig::default()
+    };
+
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let values = &get_value!(event.exceptions!).values;
+    let types: Vec<_> = get_value!(values!)
+        .iter()
+        .map(|exception| exception.value().unwrap().ty.as_str())
+        .collect();
+    assert_eq_dbg!(types, vec![Some("Error0"), Some("Error4"), Some("Error5")]);
+    assert_eq_dbg!(values.meta().original_length(), Some(6));
+    assert_eq_dbg!(
+        values.meta().iter_remarks().collect::<Vec<_>>(),
+        vec![&Remark::new(RemarkType::Removed, "!limit")]
+    );
+}
+
+#[test]
+fn test_max_threads() {
+    use crate::protocol::ThreadId;
+
+    let threads: Vec<_> = (0..5)
+        .map(|i| {
+            Annotated::new(Thread {
+                id: Annotated::new(ThreadId::Int(i)),
+                crashed: Annotated::new(i == 4),
+                ..Thread::default()
+            })
+        })
+        .collect();
+
+    let mut event = Annotated::new(Event {
+        threads: Annotated::new(Values::new(threads)),
+        ..Event::default()
+    });
+
+    let config = StoreConfig {
+        max_threads: Some(3),
+        ..StoreConfig::default()
+    };
+
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let values = &get_value!(event.threads!).values;
+    let ids: Vec<_> = get_value!(values!)
+        .iter()
+        .map(|thread| thread.value().unwrap().id.value().cloned())
+        .collect();
+    assert_eq_dbg!(
+        ids,
+        vec![
+            Some(ThreadId::Int(0)),
+            Some(ThreadId::Int(1)),
+            Some(ThreadId::Int(4))
+        ]
+    );
+    assert_eq_dbg!(values.meta().original_length(), Some(5));
+    assert_eq_dbg!(
+        values.meta().iter_remarks().collect::<Vec<_>>(),
+        vec![&Remark::new(RemarkType::Removed, "!limit")]
+    );
+}
+
+#[test]
+fn test_max_tags() {
+    let mut tags: Vec<_> = (0..5)
+        .map(|i| {
+            Annotated::new(TagEntry(
+                Annotated::new(format!("custom{}", i)),
+                Annotated::new("value".to_string()),
+            ))
+        })
+        .collect();
+
+    tags.push(Annotated::new(TagEntry(
+        Annotated::new("level".to_string()),
+        Annotated::new("fatal".to_string()),
+    )));
+
+    let mut event = Annotated::new(Event {
+        tags: Annotated::new(Tags(PairList(tags))),
+        ..Event::default()
+    });
+
+    let config = StoreConfig {
+        max_tags: Some(3),
+        ..StoreConfig::default()
+    };
+
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let tags = &event.value().unwrap().tags;
+    let keys: Vec<_> = get_value!(tags!)
+        .iter()
+        .filter_map(|entry| entry.value().and_then(|tag| tag.key()))
+        .collect();
+
+    assert_eq_dbg!(keys, vec!["custom0", "custom1", "level"]);
+    assert_eq_dbg!(tags.meta().original_length(), Some(6));
+    assert_eq_dbg!(
+        tags.meta().iter_remarks().collect::<Vec<_>>(),
+        vec![&Remark::new(RemarkType::Removed, "!limit")]
+    );
+}
+
+#[test]
+fn test_max_measurements() {
+    use crate::protocol::Measurement;
+
+    let mut measurements = Object::new();
+    for name in &["custom0", "custom1", "custom2", "lcp", "zzz"] {
+        measurements.insert(
+            name.to_string(),
+            Annotated::new(Measurement {
+                value: Annotated::new(1.0),
+                ..Measurement::default()
+            }),
+        );
+    }
+
+    let mut event = Annotated::new(Event {
+        ty: Annotated::new(EventType::Transaction),
+        measurements: Annotated::new(Measurements(measurements)),
+        ..Event::default()
+    });
+
+    let config = StoreConfig {
+        max_measurements: Some(3),
+        ..StoreConfig::default()
+    };
+
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let measurements = &event.value().unwrap().measurements;
+    let names: Vec<_> = get_value!(measurements!).0.keys().collect();
+
+    assert_eq_dbg!(names, vec!["custom0", "custom1", "lcp"]);
+    assert_eq_dbg!(measurements.meta().original_length(), Some(5));
+    assert_eq_dbg!(
+        measurements.meta().iter_remarks().collect::<Vec<_>>(),
+        vec![&Remark::new(RemarkType::Removed, "!limit")]
+    );
+}
+
+#[test]
+fn test_max_measurements_priority() {
+    use crate::protocol::Measurement;
+
+    let mut measurements = Object::new();
+    for name in &["custom0", "custom1", "lcp", "time_to_full_display"] {
+        measurements.insert(
+            name.to_string(),
+            Annotated::new(Measurement {
+                value: Annotated::new(1.0),
+                ..Measurement::default()
+            }),
+        );
+    }
+
+    let mut event = Annotated::new(Event {
+        ty: Annotated::new(EventType::Transaction),
+        measurements: Annotated::new(Measurements(measurements)),
+        ..Event::default()
+    });
+
+    let config = StoreConfig {
+        max_measurements: Some(1),
+        priority_measurements: vec!["time_to_full_display".to_owned()]
+            .into_iter()
+            .collect(),
+        ..StoreConfig::default()
+    };
+
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let measurements = &event.value().unwrap().measurements;
+    let names: Vec<_> = get_value!(measurements!).0.keys().collect();
+
+    assert_eq_dbg!(names, vec!["lcp", "time_to_full_display"]);
+    assert_eq_dbg!(measurements.meta().original_length(), Some(4));
+}
+
+#[test]
+fn test_device_class_tag() {
+    let mut event = Annotated::<Event>::from_json(
+        r#"{
+            "contexts": {
+                "device": {
+                    "type": "device",
+                    "memory_size": 5368709120
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let config = StoreConfig {
+        derive_device_class: Some(true),
+        ..StoreConfig::default()
+    };
+
+    let mut processor = NormalizeProcessor::new(Arc::new(config), None);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let tags = get_value!(event.tags!);
+    assert_eq_dbg!(tags.get("device.class"), Some("medium"));
+}
+
+#[test]
+fn test_device_class_tag_disabled() {
+    let mut event = Annotated::<Event>::from_json(
+        r#"{
+            "contexts": {
+                "device": {
+                    "type": "device",
+                    "memory_size": 5368709120
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let mut processor = NormalizeProcessor::new(Arc::new(StoreConfig::default()), None);
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+    let tags = get_value!(event.tags!);
+    assert_eq_dbg!(tags.get("device.class"), None);
+}
+
 #[test]
 fn test_internal_tags_removed() {
     let mut event = Annotated::new(Event {
@@ -0,0 +1,170 @@
+use crate::protocol::{Breadcrumb, Event};
+use crate::types::{Annotated, Object, Remark, RemarkType, Value};
+
+/// Returns `true` if the breadcrumb repeats the previous one.
+fn is_repeated(previous: &Breadcrumb, current: &Breadcrumb) -> bool {
+    previous.message.value() == current.message.value()
+        && previous.category.value() == current.category.value()
+        && previous.level.value() == current.level.value()
+}
+
+/// Annotates a breadcrumb with the number of times it occurred consecutively.
+fn set_repeated(breadcrumb: Option<&mut Annotated<Breadcrumb>>, count: u64) {
+    if count <= 1 {
+        return;
+    }
+
+    if let Some(breadcrumb) = breadcrumb.and_then(Annotated::value_mut) {
+        breadcrumb
+            .data
+            .get_or_insert_with(Object::new)
+            .insert("repeated".to_owned(), Annotated::new(Value::U64(count)));
+    }
+}
+
+/// Collapses consecutive breadcrumbs with the same message, category and level into one.
+///
+/// The first breadcrumb of a run is retained and receives a `repeated` entry in its data with the
+/// total number of occurrences.
+pub fn dedupe_breadcrumbs(event: &mut Event) {
+    let breadcrumbs = match event.breadcrumbs.value_mut() {
+        Some(breadcrumbs) => breadcrumbs,
+        None => return,
+    };
+
+    let values = match breadcrumbs.values.value_mut() {
+        Some(values) => values,
+        None => return,
+    };
+
+    let mut deduped: Vec<Annotated<Breadcrumb>> = Vec::with_capacity(values.len());
+    let mut count = 1;
+
+    for breadcrumb in values.drain(..) {
+        let previous = deduped.last().and_then(Annotated::value);
+        if let (Some(previous), Some(current)) = (previous, breadcrumb.value()) {
+            if is_repeated(previous, current) {
+                count += 1;
+                continue;
+            }
+        }
+
+        set_repeated(deduped.last_mut(), count);
+        deduped.push(breadcrumb);
+        count = 1;
+    }
+
+    set_repeated(deduped.last_mut(), count);
+    *values = deduped;
+}
+
+/// Removes the oldest breadcrumbs exceeding `max_breadcrumbs`.
+///
+/// Breadcrumbs are ordered chronologically, so the most recent ones at the end are retained. If
+/// breadcrumbs are removed, the original length and a remark are recorded in the meta data of the
+/// breadcrumb values.
+pub fn trim_breadcrumbs(event: &mut Event, max_breadcrumbs: usize) {
+    let breadcrumbs = match event.breadcrumbs.value_mut() {
+        Some(breadcrumbs) => breadcrumbs,
+        None => return,
+    };
+
+    let (values, meta) = match &mut breadcrumbs.values {
+        Annotated(Some(values), meta) => (values, meta),
+        Annotated(None, _) => return,
+    };
+
+    let original_length = values.len();
+    if original_length <= max_breadcrumbs {
+        return;
+    }
+
+    values.drain(..original_length - max_breadcrumbs);
+    meta.set_original_length(Some(original_length));
+    meta.add_remark(Remark::new(RemarkType::Removed, "!limit"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::{Level, Values};
+
+    fn breadcrumb(message: &str) -> Annotated<Breadcrumb> {
+        Annotated::new(Breadcrumb {
+            category: Annotated::new("http".to_owned()),
+            level: Annotated::new(Level::Warning),
+            message: Annotated::new(message.to_owned()),
+            ..Breadcrumb::default()
+        })
+    }
+
+    fn messages(event: &Event) -> Vec<&str> {
+        let values = event.breadcrumbs.value().unwrap().values.value().unwrap();
+        values
+            .iter()
+            .map(|b| b.value().unwrap().message.as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_collapse_repeated() {
+        let mut event = Event {
+            breadcrumbs: Annotated::new(Values::new(vec![
+                breadcrumb("retry"),
+                breadcrumb("retry"),
+                breadcrumb("retry"),
+            ])),
+            ..Event::default()
+        };
+
+        dedupe_breadcrumbs(&mut event);
+        assert_eq!(messages(&event), vec!["retry"]);
+
+        let values = event.breadcrumbs.value().unwrap().values.value().unwrap();
+        let data = values[0].value().unwrap().data.value().unwrap();
+        assert_eq!(data.get("repeated"), Some(&Annotated::new(Value::U64(3))));
+    }
+
+    #[test]
+    fn test_retain_distinct() {
+        let mut event = Event {
+            breadcrumbs: Annotated::new(Values::new(vec![
+                breadcrumb("a"),
+                breadcrumb("a"),
+                breadcrumb("b"),
+                breadcrumb("a"),
+            ])),
+            ..Event::default()
+        };
+
+        dedupe_breadcrumbs(&mut event);
+        assert_eq!(messages(&event), vec!["a", "b", "a"]);
+
+        let values = event.breadcrumbs.value().unwrap().values.value().unwrap();
+        assert!(values[1].value().unwrap().data.value().is_none());
+        assert!(values[2].value().unwrap().data.value().is_none());
+    }
+
+    #[test]
+    fn test_trim_breadcrumbs() {
+        let mut event = Event {
+            breadcrumbs: Annotated::new(Values::new(vec![
+                breadcrumb("a"),
+                breadcrumb("b"),
+                breadcrumb("c"),
+            ])),
+            ..Event::default()
+        };
+
+        trim_breadcrumbs(&mut event, 2);
+        assert_eq!(messages(&event), vec!["b", "c"]);
+
+        let values = &event.breadcrumbs.value().unwrap().values;
+        assert_eq!(values.meta().original_length(), Some(3));
+        assert_eq!(
+            values.meta().iter_remarks().collect::<Vec<_>>(),
+            vec![&Remark::new(RemarkType::Removed, "!limit")]
+        );
+    }
+}
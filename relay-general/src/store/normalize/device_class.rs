@@ -0,0 +1,96 @@
+//! Classification of devices into performance tiers.
+
+use std::fmt;
+
+use crate::protocol::{Context, ContextInner, DeviceContext, Event};
+use crate::types::Annotated;
+
+/// One gibibyte in bytes.
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// The performance tier of a device, derived from its device context.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeviceClass {
+    /// Devices with less than 4 GiB of memory.
+    Low,
+    /// Devices with less than 6 GiB of memory.
+    Medium,
+    /// Devices with at least 6 GiB of memory.
+    High,
+}
+
+impl DeviceClass {
+    /// Derives the device class from the memory size of the device.
+    ///
+    /// Returns `None` if the device does not report its memory size.
+    pub fn from_device_context(device: &DeviceContext) -> Option<Self> {
+        let memory_size = *device.memory_size.value()?;
+
+        Some(if memory_size < 4 * GIB {
+            DeviceClass::Low
+        } else if memory_size < 6 * GIB {
+            DeviceClass::Medium
+        } else {
+            DeviceClass::High
+        })
+    }
+
+    /// Derives the device class from the device context of the event.
+    pub fn from_event(event: &Event) -> Option<Self> {
+        let contexts = event.contexts.value()?;
+        match contexts
+            .get(DeviceContext::default_key())
+            .and_then(Annotated::value)
+        {
+            Some(ContextInner(Context::Device(device))) => Self::from_device_context(device),
+            _ => None,
+        }
+    }
+
+    /// Returns the string representation of this device class.
+    pub fn name(self) -> &'static str {
+        match self {
+            DeviceClass::Low => "low",
+            DeviceClass::Medium => "medium",
+            DeviceClass::High => "high",
+        }
+    }
+}
+
+impl fmt::Display for DeviceClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device_with_memory(memory_size: u64) -> DeviceContext {
+        DeviceContext {
+            memory_size: Annotated::new(memory_size),
+            ..DeviceContext::default()
+        }
+    }
+
+    #[test]
+    fn test_device_class() {
+        assert_eq!(
+            DeviceClass::from_device_context(&device_with_memory(2 * GIB)),
+            Some(DeviceClass::Low)
+        );
+        assert_eq!(
+            DeviceClass::from_device_context(&device_with_memory(4 * GIB)),
+            Some(DeviceClass::Medium)
+        );
+        assert_eq!(
+            DeviceClass::from_device_context(&device_with_memory(8 * GIB)),
+            Some(DeviceClass::High)
+        );
+        assert_eq!(
+            DeviceClass::from_device_context(&DeviceContext::default()),
+            None
+        );
+    }
+}
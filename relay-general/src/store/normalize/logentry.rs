@@ -4,8 +4,10 @@ use std::borrow::Cow;
 
 use dynfmt::{Argument, Format, FormatArgs, PythonFormat, SimpleCurlyFormat};
 
-use crate::protocol::LogEntry;
-use crate::types::{Annotated, Empty, Error, Meta, ProcessingAction, ProcessingResult, Value};
+use crate::protocol::{LogEntry, Message};
+use crate::types::{
+    Annotated, Empty, Error, Meta, ProcessingAction, ProcessingResult, Remark, RemarkType, Value,
+};
 
 impl FormatArgs for Value {
     fn get_index(&self, index: usize) -> Result<Option<Argument<'_>>, ()> {
@@ -80,6 +82,39 @@ pub fn normalize_logentry(logentry: &mut LogEntry, meta: &mut Meta) -> Processin
     Ok(())
 }
 
+/// Truncates a message exceeding `max_length` characters and suffixes it with an ellipsis.
+fn limit_message(message: &mut Annotated<Message>, max_length: usize) {
+    let text = match message.value() {
+        Some(text) => text.as_ref(),
+        None => return,
+    };
+
+    let original_length = text.chars().count();
+    if original_length <= max_length {
+        return;
+    }
+
+    let end = max_length.saturating_sub(3);
+    let mut truncated: String = text.chars().take(end).collect();
+    truncated.push_str("...");
+
+    message.set_value(Some(truncated.into()));
+
+    let meta = message.meta_mut();
+    meta.set_original_length(Some(original_length));
+    meta.add_remark(Remark::with_range(
+        RemarkType::Substituted,
+        "!limit",
+        (end, end + 3),
+    ));
+}
+
+/// Truncates the formatted and raw message of the log entry to `max_length` characters.
+pub fn limit_logentry(logentry: &mut LogEntry, max_length: usize) {
+    limit_message(&mut logentry.formatted, max_length);
+    limit_message(&mut logentry.message, max_length);
+}
+
 #[cfg(test)]
 use crate::types::Object;
 
@@ -203,3 +238,38 @@ fn test_empty_logentry() {
     assert_eq_dbg!(normalize_logentry(&mut logentry, &mut meta), Ok(()));
     assert!(!meta.has_errors());
 }
+
+#[test]
+fn test_limit_logentry() {
+    let mut logentry = LogEntry {
+        message: Annotated::new("x".repeat(100).into()),
+        ..LogEntry::default()
+    };
+
+    normalize_logentry(&mut logentry, &mut Meta::default());
+    limit_logentry(&mut logentry, 20);
+
+    assert_eq_dbg!(logentry.formatted.as_str(), Some("xxxxxxxxxxxxxxxxx..."));
+    assert_eq_dbg!(
+        logentry.formatted.meta().iter_remarks().next(),
+        Some(&Remark::with_range(
+            RemarkType::Substituted,
+            "!limit",
+            (17, 20)
+        ))
+    );
+    assert_eq_dbg!(logentry.formatted.meta().original_length(), Some(100));
+}
+
+#[test]
+fn test_limit_logentry_short() {
+    let mut logentry = LogEntry {
+        formatted: Annotated::new("hello".to_string().into()),
+        ..LogEntry::default()
+    };
+
+    limit_logentry(&mut logentry, 20);
+
+    assert_eq_dbg!(logentry.formatted.as_str(), Some("hello"));
+    assert!(logentry.formatted.meta().is_empty());
+}
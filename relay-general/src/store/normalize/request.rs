@@ -2,8 +2,11 @@ use lazy_static::lazy_static;
 use regex::Regex;
 use url::Url;
 
+use crate::processor::estimate_size;
 use crate::protocol::{Query, Request};
-use crate::types::{Annotated, ErrorKind, Meta, ProcessingAction, ProcessingResult, Value};
+use crate::types::{
+    Annotated, ErrorKind, Meta, ProcessingAction, ProcessingResult, Remark, RemarkType, Value,
+};
 
 const ELLIPSIS: char = '\u{2026}';
 
@@ -180,6 +183,107 @@ fn normalize_cookies(request: &mut Request) {
     }
 }
 
+/// Bounds the size of the request body to `max_bytes`.
+///
+/// Raw bodies are truncated and suffixed with an ellipsis, so that they can no longer be parsed
+/// into structured data. Structured bodies retain their leading entries up to the limit. The
+/// contents of retained entries are further trimmed by the databag limits of the request body.
+pub fn limit_request_body(request: &mut Request, max_bytes: usize) {
+    let data = &mut request.data;
+    if estimate_size(data.value()) <= max_bytes {
+        return;
+    }
+
+    let original_length = match data.value_mut() {
+        Some(Value::String(raw)) => {
+            if raw.len() <= max_bytes {
+                return;
+            }
+
+            let original_length = raw.len();
+            let mut end = max_bytes.saturating_sub(3);
+            while !raw.is_char_boundary(end) {
+                end -= 1;
+            }
+            raw.truncate(end);
+            raw.push_str("...");
+
+            let meta = data.meta_mut();
+            meta.set_original_length(Some(original_length));
+            meta.add_remark(Remark::with_range(
+                RemarkType::Substituted,
+                "!limit",
+                (end, end + 3),
+            ));
+            return;
+        }
+        Some(Value::Array(items)) => {
+            let original_length = items.len();
+            let retained = count_within_limit(items.iter(), max_bytes);
+            items.truncate(retained);
+            original_length
+        }
+        Some(Value::Object(object)) => {
+            let original_length = object.len();
+            let retained = count_within_limit(object.values(), max_bytes);
+            if let Some(split_key) = object.keys().nth(retained).cloned() {
+                let _ = object.split_off(&split_key);
+            }
+            original_length
+        }
+        _ => return,
+    };
+
+    let meta = data.meta_mut();
+    meta.set_original_length(Some(original_length));
+    meta.add_remark(Remark::new(RemarkType::Removed, "!limit"));
+}
+
+/// Returns the number of leading values whose combined estimated size fits into `max_bytes`.
+fn count_within_limit<'a>(
+    values: impl Iterator<Item = &'a Annotated<Value>>,
+    max_bytes: usize,
+) -> usize {
+    let mut remaining = max_bytes;
+    values
+        .take_while(|value| {
+            // Account for one byte of separator per entry.
+            let size = estimate_size(value.value()) + 1;
+            match remaining.checked_sub(size) {
+                Some(rest) => {
+                    remaining = rest;
+                    true
+                }
+                None => false,
+            }
+        })
+        .count()
+}
+
+/// Redacts the values of all query string parameters, retaining their keys.
+///
+/// This runs after [`normalize_request`], which moves the query string out of the URL into the
+/// `query_string` field.
+pub fn scrub_query_strings(request: &mut Request) {
+    let query = match request.query_string.value_mut() {
+        Some(query) => query,
+        None => return,
+    };
+
+    for item in query.iter_mut() {
+        if let Some((_, value)) = item.value_mut() {
+            if value.value().is_none() {
+                continue;
+            }
+
+            value.set_value(Some("[Filtered]".to_owned().into()));
+            value
+                .meta_mut()
+                .add_remark(Remark::new(RemarkType::Substituted, "!query_string"));
+        }
+    }
+}
+
 pub fn normalize_request(request: &mut Request) -> ProcessingResult {
     request.method.apply(normalize_method)?;
     normalize_url(request);
@@ -194,6 +298,83 @@ use crate::protocol::PairList;
 #[cfg(test)]
 use crate::types::Object;
 
+#[test]
+fn test_limit_raw_request_body() {
+    let mut request = Request {
+        data: Annotated::new(Value::String(format!(
+            "{{\"data\": \"{}\"}}",
+            "x".repeat(100)
+        ))),
+        ..Request::default()
+    };
+
+    limit_request_body(&mut request, 20);
+    normalize_request(&mut request).unwrap();
+
+    // The truncated JSON body cannot be parsed anymore and remains a string.
+    assert_eq_dbg!(request.data.as_str(), Some("{\"data\": \"xxxxxxx..."));
+    assert_eq_dbg!(
+        request.data.meta().iter_remarks().next(),
+        Some(&Remark::with_range(
+            RemarkType::Substituted,
+            "!limit",
+            (17, 20)
+        ))
+    );
+    assert_eq_dbg!(request.data.meta().original_length(), Some(112));
+}
+
+#[test]
+fn test_limit_raw_request_body_bytes() {
+    let mut request = Request {
+        data: Annotated::new(Value::String("ä".repeat(20))),
+        ..Request::default()
+    };
+
+    limit_request_body(&mut request, 20);
+
+    // Each character takes two bytes, so the limit applies to bytes rather than characters.
+    assert_eq_dbg!(request.data.as_str(), Some("ääääääää..."));
+    assert_eq_dbg!(request.data.meta().original_length(), Some(40));
+}
+
+#[test]
+fn test_limit_structured_request_body() {
+    let mut request = Request {
+        data: Annotated::new(Value::Array(vec![
+            Annotated::new(Value::String("x".repeat(10))),
+            Annotated::new(Value::String("y".repeat(100))),
+        ])),
+        ..Request::default()
+    };
+
+    limit_request_body(&mut request, 20);
+
+    assert_eq_dbg!(
+        request.data.value(),
+        Some(&Value::Array(vec![Annotated::new(Value::String(
+            "x".repeat(10)
+        ))]))
+    );
+    assert_eq_dbg!(request.data.meta().original_length(), Some(2));
+    assert_eq_dbg!(
+        request.data.meta().iter_remarks().next(),
+        Some(&Remark::new(RemarkType::Removed, "!limit"))
+    );
+}
+
+#[test]
+fn test_limit_request_body_within_bounds() {
+    let mut request = Request {
+        data: Annotated::new(Value::String("hello".to_owned())),
+        ..Request::default()
+    };
+
+    limit_request_body(&mut request, 20);
+    assert_eq_dbg!(request.data.as_str(), Some("hello"));
+    assert!(request.data.meta().is_empty());
+}
+
 #[test]
 fn test_url_truncation() {
     let mut request = Request {
@@ -374,6 +555,50 @@ fn test_cookies_in_header() {
     assert_eq_dbg!(request.headers.value().unwrap().get_header("Cookie"), None);
 }
 
+#[test]
+fn test_scrub_query_strings() {
+    let mut request = Request {
+        url: Annotated::new("http://example.com/path?token=secret&page=2".to_string()),
+        ..Request::default()
+    };
+
+    normalize_request(&mut request).unwrap();
+    scrub_query_strings(&mut request);
+
+    assert_eq_dbg!(request.url.as_str(), Some("http://example.com/path"));
+
+    let query = request.query_string.value().unwrap();
+    let (key, value) = query[0].value().unwrap();
+    assert_eq_dbg!(key.as_str(), Some("token"));
+    assert_eq_dbg!(value.as_str(), Some("[Filtered]"));
+    assert_eq_dbg!(
+        value.meta().iter_remarks().collect::<Vec<_>>(),
+        vec![&Remark::new(RemarkType::Substituted, "!query_string")]
+    );
+
+    let (key, value) = query[1].value().unwrap();
+    assert_eq_dbg!(key.as_str(), Some("page"));
+    assert_eq_dbg!(value.as_str(), Some("[Filtered]"));
+}
+
+#[test]
+fn test_scrub_query_strings_skips_empty() {
+    let mut request = Request {
+        query_string: Annotated::new(Query(PairList(vec![Annotated::new((
+            Annotated::new("user".to_string()),
+            Annotated::empty(),
+        ))]))),
+        ..Request::default()
+    };
+
+    scrub_query_strings(&mut request);
+
+    let query = request.query_string.value().unwrap();
+    let (_, value) = query[0].value().unwrap();
+    assert_eq_dbg!(value.value(), None);
+    assert_eq_dbg!(value.meta().iter_remarks().count(), 0);
+}
+
 #[test]
 fn test_cookies_in_header_dont_override_cookies() {
     use crate::protocol::{Cookies, Headers};
@@ -0,0 +1,217 @@
+//! Canonicalization of high-cardinality transaction names.
+//!
+//! Transaction names derived from URLs often contain identifiers, such as `/user/12345/profile`.
+//! This module replaces such path segments with placeholders based on a set of rules.
+
+use serde::{Deserialize, Serialize};
+
+use crate::pii::Pattern;
+use crate::protocol::{Event, EventType, TransactionInfo, TransactionSource};
+use crate::types::Annotated;
+
+/// A rule that replaces matching path segments of a transaction name.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct TransactionNameRule {
+    /// The pattern matched against every individual path segment.
+    pub pattern: Pattern,
+    /// The placeholder that replaces a matching segment.
+    pub replacement: String,
+}
+
+/// Configuration for transaction name canonicalization.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct TransactionNameConfig {
+    /// Rules applied to path segments in order. The first matching rule wins.
+    ///
+    /// Defaults to replacing numeric and UUID segments with `:id`.
+    pub rules: Vec<TransactionNameRule>,
+}
+
+impl Default for TransactionNameConfig {
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                TransactionNameRule {
+                    pattern: r"^\d+$".into(),
+                    replacement: ":id".to_owned(),
+                },
+                TransactionNameRule {
+                    pattern: concat!(
+                        r"^[0-9a-fA-F]{8}-?[0-9a-fA-F]{4}-?[0-9a-fA-F]{4}-?",
+                        r"[0-9a-fA-F]{4}-?[0-9a-fA-F]{12}$"
+                    )
+                    .into(),
+                    replacement: ":id".to_owned(),
+                },
+            ],
+        }
+    }
+}
+
+impl TransactionNameConfig {
+    /// Returns the canonicalized name, or `None` if no segment matched any rule.
+    fn canonicalize(&self, name: &str) -> Option<String> {
+        let mut changed = false;
+
+        let segments: Vec<_> = name
+            .split('/')
+            .map(|segment| {
+                let rule = match segment {
+                    "" => None,
+                    _ => self
+                        .rules
+                        .iter()
+                        .find(|rule| rule.pattern.is_match(segment)),
+                };
+
+                match rule {
+                    Some(rule) => {
+                        changed = true;
+                        rule.replacement.as_str()
+                    }
+                    None => segment,
+                }
+            })
+            .collect();
+
+        if changed {
+            Some(segments.join("/"))
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns `true` if a transaction name from the given source may contain identifiers.
+///
+/// Raw URLs and names from SDKs that do not declare a source are canonicalized. All other sources,
+/// such as routes or custom names, are trusted to have low cardinality.
+fn is_high_cardinality_source(source: Option<&TransactionSource>) -> bool {
+    match source {
+        None | Some(TransactionSource::Url) | Some(TransactionSource::Unknown) => true,
+        Some(TransactionSource::Other(_)) => true,
+        Some(_) => false,
+    }
+}
+
+/// Replaces identifiers in the transaction name of transaction events with placeholders.
+///
+/// Only names with a high-cardinality `transaction_info.source` are canonicalized, see
+/// [`TransactionSource`]. If the name changes, the original name is retained in
+/// `transaction_info.original` and the source is marked as [`TransactionSource::Sanitized`].
+pub fn normalize_transaction_name(event: &mut Event, config: &TransactionNameConfig) {
+    if event.ty.value() != Some(&EventType::Transaction) {
+        return;
+    }
+
+    let source = event
+        .transaction_info
+        .value()
+        .and_then(|info| info.source.value());
+
+    if !is_high_cardinality_source(source) {
+        return;
+    }
+
+    let canonical = match event.transaction.as_str() {
+        Some(name) => config.canonicalize(name),
+        None => None,
+    };
+
+    let canonical = match canonical {
+        Some(canonical) => canonical,
+        None => return,
+    };
+
+    let original = std::mem::replace(&mut event.transaction, Annotated::new(canonical));
+    let info = event
+        .transaction_info
+        .get_or_insert_with(TransactionInfo::default);
+
+    if info.original.value().is_none() {
+        info.original = original;
+    }
+    info.source = Annotated::new(TransactionSource::Sanitized);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transaction(name: &str) -> Event {
+        Event {
+            ty: Annotated::new(EventType::Transaction),
+            transaction: Annotated::new(name.to_owned()),
+            ..Event::default()
+        }
+    }
+
+    #[test]
+    fn test_numeric_segment() {
+        let mut event = transaction("/user/12345/profile");
+        normalize_transaction_name(&mut event, &TransactionNameConfig::default());
+
+        assert_eq!(event.transaction.as_str(), Some("/user/:id/profile"));
+
+        let info = event.transaction_info.value().unwrap();
+        assert_eq!(info.original.as_str(), Some("/user/12345/profile"));
+        assert_eq!(info.source.value(), Some(&TransactionSource::Sanitized));
+    }
+
+    #[test]
+    fn test_uuid_segment() {
+        let mut event = transaction("/orders/9ec79c33-ec99-42ab-8353-589fcb2e04dc/");
+        normalize_transaction_name(&mut event, &TransactionNameConfig::default());
+
+        assert_eq!(event.transaction.as_str(), Some("/orders/:id/"));
+    }
+
+    #[test]
+    fn test_unchanged() {
+        let mut event = transaction("/user/me/profile");
+        normalize_transaction_name(&mut event, &TransactionNameConfig::default());
+
+        assert_eq!(event.transaction.as_str(), Some("/user/me/profile"));
+        assert!(event.transaction_info.value().is_none());
+    }
+
+    #[test]
+    fn test_source() {
+        let with_source = |source| {
+            let mut event = transaction("/user/12345/profile");
+            event.transaction_info = Annotated::new(TransactionInfo {
+                source: Annotated::new(source),
+                ..TransactionInfo::default()
+            });
+            normalize_transaction_name(&mut event, &TransactionNameConfig::default());
+            event
+        };
+
+        let event = with_source(TransactionSource::Url);
+        assert_eq!(event.transaction.as_str(), Some("/user/:id/profile"));
+
+        let info = event.transaction_info.value().unwrap();
+        assert_eq!(info.source.value(), Some(&TransactionSource::Sanitized));
+
+        let event = with_source(TransactionSource::Route);
+        assert_eq!(event.transaction.as_str(), Some("/user/12345/profile"));
+
+        let info = event.transaction_info.value().unwrap();
+        assert_eq!(info.source.value(), Some(&TransactionSource::Route));
+        assert!(info.original.value().is_none());
+    }
+
+    #[test]
+    fn test_custom_rule() {
+        let config: TransactionNameConfig = serde_json::from_str(
+            r#"{"rules": [{"pattern": "^[a-z]+@[a-z.]+$", "replacement": ":email"}]}"#,
+        )
+        .unwrap();
+
+        let mut event = transaction("/invite/jane@example.org/12");
+        normalize_transaction_name(&mut event, &config);
+
+        assert_eq!(event.transaction.as_str(), Some("/invite/:email/12"));
+    }
+}
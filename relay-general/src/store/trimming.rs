@@ -17,6 +17,7 @@ struct BagSizeState {
 #[derive(Default)]
 pub struct TrimmingProcessor {
     bag_size_state: Vec<BagSizeState>,
+    max_depth: Option<usize>,
 }
 
 impl TrimmingProcessor {
@@ -24,6 +25,17 @@ impl TrimmingProcessor {
         Self::default()
     }
 
+    /// Creates a trimming processor that also limits the depth of the entire payload.
+    ///
+    /// Values reaching `max_depth` are trimmed like values reaching the maximum depth of a
+    /// databag: containers are serialized into a JSON string, and deeper values are removed.
+    pub fn with_max_depth(max_depth: usize) -> Self {
+        TrimmingProcessor {
+            max_depth: Some(max_depth),
+            ..Self::default()
+        }
+    }
+
     fn should_remove_container<T: Empty>(&self, value: &T, state: &ProcessingState<'_>) -> bool {
         // Heuristic to avoid trimming a value like `[1, 1, 1, 1, ...]` into `[null, null, null,
         // null, ...]`, making it take up more space.
@@ -43,6 +55,7 @@ impl TrimmingProcessor {
                     .max_depth()
                     .saturating_sub(databag_depth)
             })
+            .chain(self.max_depth.map(|max| max.saturating_sub(state.depth())))
             .min()
     }
 
@@ -515,6 +528,42 @@ fn test_databag_stripping() {
     );
 }
 
+#[test]
+fn test_max_depth() {
+    use crate::protocol::{Event, ExtraValue};
+    use crate::types::{Annotated, Value};
+
+    let mut processor = TrimmingProcessor::with_max_depth(4);
+
+    fn make_nested_object(depth: usize) -> Annotated<Value> {
+        if depth == 0 {
+            return Annotated::new(Value::String("max depth".to_string()));
+        }
+        let mut rv = Object::new();
+        rv.insert(format!("key{}", depth), make_nested_object(depth - 1));
+        Annotated::new(Value::Object(rv))
+    }
+
+    let mut extra = Object::new();
+    extra.insert(
+        "outer".to_string(),
+        make_nested_object(6).map_value(ExtraValue),
+    );
+    let mut event = Annotated::new(Event {
+        extra: Annotated::new(extra),
+        ..Default::default()
+    });
+
+    process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+    let json = event.value().unwrap().extra.to_json().unwrap();
+
+    // `extra` is at depth 1, so the object at depth 3 is serialized into a string.
+    assert_eq_str!(
+        json,
+        r#"{"outer":{"key6":"{\"key5\":{\"key4\":{\"key3\":{\"key2\":{\"key1\":\"max depth\"}}}}}"}}"#
+    );
+}
+
 #[test]
 fn test_databag_array_stripping() {
     use crate::protocol::{Event, ExtraValue};
@@ -1,10 +1,12 @@
+use std::collections::BTreeMap;
 use std::env;
 use std::io::{self, Write};
 use std::path::PathBuf;
 use std::sync::Arc;
 
 use chrono::{DateTime, Utc};
-use log::{Level, LevelFilter};
+use log::kv::{self, Key, Visitor};
+use log::{Level, LevelFilter, Record};
 use sentry::types::Dsn;
 use serde::{Deserialize, Serialize};
 
@@ -103,6 +105,22 @@ impl Default for SentryConfig {
     }
 }
 
+/// Collects the structured key-value pairs of a log record.
+fn record_fields(record: &Record<'_>) -> BTreeMap<String, String> {
+    struct FieldsVisitor(BTreeMap<String, String>);
+
+    impl<'kvs> Visitor<'kvs> for FieldsVisitor {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+            self.0.insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+    }
+
+    let mut visitor = FieldsVisitor(BTreeMap::new());
+    record.key_values().visit(&mut visitor).ok();
+    visitor.0
+}
+
 /// Initialize the logging system and reporting to Sentry.
 ///
 /// # Example
@@ -195,6 +213,8 @@ pub fn init(config: &LogConfig, sentry: &SentryConfig) {
                     module_path: Option<&'a str>,
                     filename: Option<&'a str>,
                     lineno: Option<u32>,
+                    #[serde(flatten)]
+                    fields: BTreeMap<String, String>,
                 }
 
                 let mut builder = env_logger::Builder::new();
@@ -209,6 +229,7 @@ pub fn init(config: &LogConfig, sentry: &SentryConfig) {
                             module_path: record.module_path(),
                             filename: record.file(),
                             lineno: record.line(),
+                            fields: record_fields(record),
                         },
                     )
                     .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
@@ -265,3 +286,21 @@ pub fn init(config: &LogConfig, sentry: &SentryConfig) {
     // Keep the client initialized. The client is flushed manually in `main`.
     std::mem::forget(guard);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_fields() {
+        let fields = [("project_id", "42"), ("error", "timeout")];
+        let record = Record::builder()
+            .args(format_args!("error processing envelope"))
+            .key_values(&fields)
+            .build();
+
+        let fields = record_fields(&record);
+        assert_eq!(fields["project_id"], "42");
+        assert_eq!(fields["error"], "timeout");
+    }
+}
@@ -98,8 +98,10 @@
 )]
 
 mod aggregation;
+mod meta;
 mod protocol;
 mod statsd;
 
 pub use aggregation::*;
+pub use meta::*;
 pub use protocol::*;
@@ -0,0 +1,97 @@
+use failure::Fail;
+use serde::{Deserialize, Serialize};
+
+use relay_common::MetricUnit;
+
+use crate::MetricResourceIdentifier;
+
+/// Error returned when parsing [`MetricMeta`].
+#[derive(Debug, Fail)]
+#[fail(display = "failed to parse metric meta")]
+pub struct ParseMetricMetaError(#[cause] serde_json::Error);
+
+/// Metadata describing a metric, independent of its values.
+///
+/// Clients send metric metadata in envelope items of type `metric_meta`, which contain a JSON list
+/// of entries:
+///
+/// ```json
+/// [
+///   {
+///     "name": "d:custom/endpoint.response_time@millisecond",
+///     "unit": "millisecond"
+///   }
+/// ]
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct MetricMeta {
+    /// The full metric name in MRI format described by this entry.
+    ///
+    /// For more information see [`MetricResourceIdentifier`].
+    pub name: String,
+
+    /// The unit of the metric. Defaults to [`MetricUnit::None`].
+    #[serde(default)]
+    pub unit: MetricUnit,
+
+    /// An optional hint for the number of distinct tag combinations of this metric.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cardinality: Option<u64>,
+}
+
+impl MetricMeta {
+    /// Parses a list of metric metadata from the JSON protocol.
+    ///
+    /// Entries with names that are not a valid MRI are skipped.
+    pub fn parse_all(slice: &[u8]) -> Result<Vec<Self>, ParseMetricMetaError> {
+        let mut meta: Vec<Self> = serde_json::from_slice(slice).map_err(ParseMetricMetaError)?;
+        meta.retain(|entry| MetricResourceIdentifier::parse(&entry.name).is_ok());
+        Ok(meta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use relay_common::DurationUnit;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_metric_meta() {
+        let json = br#"[
+            {"name": "d:custom/response_time@millisecond", "unit": "millisecond"},
+            {"name": "c:custom/hits@none", "cardinality": 10}
+        ]"#;
+
+        let meta = MetricMeta::parse_all(json).unwrap();
+        assert_eq!(
+            meta,
+            vec![
+                MetricMeta {
+                    name: "d:custom/response_time@millisecond".to_owned(),
+                    unit: MetricUnit::Duration(DurationUnit::MilliSecond),
+                    cardinality: None,
+                },
+                MetricMeta {
+                    name: "c:custom/hits@none".to_owned(),
+                    unit: MetricUnit::None,
+                    cardinality: Some(10),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_metric_meta_invalid_name() {
+        let json = br#"[{"name": "response_time"}, {"name": "c:custom/hits@none"}]"#;
+
+        let meta = MetricMeta::parse_all(json).unwrap();
+        assert_eq!(meta.len(), 1);
+        assert_eq!(meta[0].name, "c:custom/hits@none");
+    }
+
+    #[test]
+    fn test_parse_metric_meta_invalid_json() {
+        assert!(MetricMeta::parse_all(br#"{"name": "c:custom/hits@none"}"#).is_err());
+    }
+}
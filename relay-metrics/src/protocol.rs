@@ -180,6 +180,8 @@ pub enum MetricNamespace {
     Sessions,
     /// Metrics extracted from transaction events.
     Transactions,
+    /// Metrics extracted from error events.
+    Errors,
     /// Metrics that relay either doesn't know or recognize the namespace of, will be dropped before
     /// aggregating. For instance, an MRI of `c:something_new/foo@none` has the namespace
     /// `something_new`, but as Relay doesn't support that namespace, it gets deserialized into
@@ -197,6 +199,7 @@ impl std::str::FromStr for MetricNamespace {
         match ns {
             "sessions" => Ok(MetricNamespace::Sessions),
             "transactions" => Ok(MetricNamespace::Transactions),
+            "errors" => Ok(MetricNamespace::Errors),
             _ => Ok(MetricNamespace::Unsupported),
         }
     }
@@ -209,6 +212,7 @@ impl fmt::Display for MetricNamespace {
         match self {
             MetricNamespace::Sessions => write!(f, "sessions"),
             MetricNamespace::Transactions => write!(f, "transactions"),
+            MetricNamespace::Errors => write!(f, "errors"),
             MetricNamespace::Unsupported => write!(f, "unsupported"),
         }
     }
@@ -104,7 +104,8 @@ impl CategoryUnit {
             | DataCategory::Error
             | DataCategory::Transaction
             | DataCategory::Security
-            | DataCategory::Profile => Some(Self::Count),
+            | DataCategory::Profile
+            | DataCategory::Replay => Some(Self::Count),
             DataCategory::Attachment => Some(Self::Bytes),
             DataCategory::Session => Some(Self::Batched),
             DataCategory::Unknown => None,
@@ -823,6 +823,41 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_rate_limits_check_key_scoped() {
+        let limited_key = ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap();
+        let other_key = ProjectKey::parse("deadbeefdeadbeefdeadbeefdeadbeef").unwrap();
+
+        let mut rate_limits = RateLimits::new();
+        rate_limits.add(RateLimit {
+            categories: smallvec![DataCategory::Error],
+            scope: RateLimitScope::Key(limited_key),
+            reason_code: None,
+            retry_after: RetryAfter::from_secs(1),
+        });
+
+        let limited_scoping = Scoping {
+            organization_id: 42,
+            project_id: ProjectId::new(21),
+            project_key: limited_key,
+            key_id: Some(17),
+        };
+
+        let other_scoping = Scoping {
+            project_key: other_key,
+            key_id: Some(18),
+            ..limited_scoping
+        };
+
+        let applied_limits =
+            rate_limits.check_with_quotas(&[], limited_scoping.item(DataCategory::Error));
+        assert!(applied_limits.is_limited());
+
+        let applied_limits =
+            rate_limits.check_with_quotas(&[], other_scoping.item(DataCategory::Error));
+        assert!(applied_limits.is_ok());
+    }
+
     #[test]
     fn test_rate_limits_merge() {
         let mut rate_limits1 = RateLimits::new();
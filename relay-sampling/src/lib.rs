@@ -704,6 +704,27 @@ pub struct TraceContext {
     /// set on transaction start, or via `scope.transaction`
     #[serde(default)]
     pub transaction: Option<String>,
+    /// the sample rate with which the head of the trace was sampled
+    ///
+    /// SDKs send this as a string, but numbers are accepted as well. Invalid values are ignored.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_sample_rate",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub sample_rate: Option<f64>,
+}
+
+/// Deserializes an optional sample rate from either a number or a numeric string.
+fn deserialize_sample_rate<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        Some(Value::Number(number)) => number.as_f64(),
+        Some(Value::String(string)) => string.parse().ok(),
+        _ => None,
+    })
 }
 
 impl TraceContext {
@@ -967,6 +988,7 @@ mod tests {
             }),
             environment: Some("prod".into()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         assert_eq!(Value::String("1.1.1".into()), tc.get_value("trace.release"));
@@ -997,6 +1019,7 @@ mod tests {
             user: None,
             environment: None,
             transaction: None,
+            sample_rate: None,
         };
         assert_eq!(Value::Null, tc.get_value("trace.release"));
         assert_eq!(Value::Null, tc.get_value("trace.environment"));
@@ -1011,6 +1034,7 @@ mod tests {
             user: Some(TraceUserContext::default()),
             environment: None,
             transaction: None,
+            sample_rate: None,
         };
         assert_eq!(Value::Null, tc.get_value("trace.user.id"));
         assert_eq!(Value::Null, tc.get_value("trace.user.segment"));
@@ -1116,6 +1140,7 @@ mod tests {
             }),
             environment: Some("debug".into()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         for (rule_test_name, condition) in conditions.iter() {
@@ -1289,6 +1314,7 @@ mod tests {
             }),
             environment: Some("debug".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         for (rule_test_name, expected, condition) in conditions.iter() {
@@ -1349,6 +1375,7 @@ mod tests {
             }),
             environment: Some("debug".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         for (rule_test_name, expected, condition) in conditions.iter() {
@@ -1386,6 +1413,7 @@ mod tests {
             }),
             environment: Some("debug".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         for (rule_test_name, expected, condition) in conditions.iter() {
@@ -1446,6 +1474,7 @@ mod tests {
             }),
             environment: Some("debug".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         for (rule_test_name, condition) in conditions.iter() {
@@ -1645,6 +1674,29 @@ mod tests {
         assert_eq!(rule.ty, RuleType::Trace);
     }
 
+    #[test]
+    /// Test TraceContext deserialization of the sample rate
+    fn test_trace_context_sample_rate_deserialization() {
+        let parse = |sample_rate: &str| {
+            let json = format!(
+                r#"{{
+                    "trace_id": "89143b0763095bd9c9955e8175d1fb23",
+                    "public_key": "abd0f232775f45feab79864e580d160b",
+                    "sample_rate": {}
+                }}"#,
+                sample_rate
+            );
+            serde_json::from_str::<TraceContext>(&json)
+                .unwrap()
+                .sample_rate
+        };
+
+        assert_eq!(parse(r#""0.5""#), Some(0.5));
+        assert_eq!(parse("0.25"), Some(0.25));
+        assert_eq!(parse(r#""invalid""#), None);
+        assert_eq!(parse("null"), None);
+    }
+
     #[test]
     fn test_partial_trace_matches() {
         let condition = and(vec![
@@ -1661,6 +1713,7 @@ mod tests {
             }),
             environment: Some("debug".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         assert!(
@@ -1679,6 +1732,7 @@ mod tests {
             user: None,
             environment: Some("debug".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         assert!(
@@ -1700,6 +1754,7 @@ mod tests {
             }),
             environment: None,
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         assert!(
@@ -1721,6 +1776,7 @@ mod tests {
             }),
             environment: Some("debug".to_string()),
             transaction: None,
+            sample_rate: None,
         };
 
         assert!(
@@ -1735,6 +1791,7 @@ mod tests {
             user: None,
             environment: None,
             transaction: None,
+            sample_rate: None,
         };
 
         assert!(
@@ -1815,6 +1872,7 @@ mod tests {
             }),
             environment: Some("debug".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         let result = get_matching_trace_rule(&rules, &trace_context, None, RuleType::Trace);
@@ -1835,6 +1893,7 @@ mod tests {
             }),
             environment: Some("debug".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         let result = get_matching_trace_rule(&rules, &trace_context, None, RuleType::Trace);
@@ -1855,6 +1914,7 @@ mod tests {
             }),
             environment: Some("debug".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         let result = get_matching_trace_rule(&rules, &trace_context, None, RuleType::Trace);
@@ -1875,6 +1935,7 @@ mod tests {
             }),
             environment: Some("production".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         let result = get_matching_trace_rule(&rules, &trace_context, None, RuleType::Trace);
@@ -1895,6 +1956,7 @@ mod tests {
             }),
             environment: Some("debug".to_string()),
             transaction: Some("transaction1".into()),
+            sample_rate: None,
         };
 
         let result = get_matching_trace_rule(&rules, &trace_context, None, RuleType::Trace);
@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 use std::cell::RefCell;
-use std::cmp::max;
-use std::collections::BTreeMap;
+use std::cmp::{max, min};
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 use std::io::Write;
 use std::rc::Rc;
@@ -19,54 +19,69 @@ use flate2::Compression;
 use futures::{future, prelude::*, sync::oneshot};
 use lazy_static::lazy_static;
 use serde_json::Value as SerdeValue;
+use uuid::Uuid;
+use zstd::stream::write::Encoder as ZstdEncoder;
 
 use relay_auth::RelayVersion;
 use relay_common::{clone, ProjectId, ProjectKey, UnixTimestamp};
-use relay_config::{Config, HttpEncoding, RelayMode};
+use relay_config::{
+    BufferOverflowPolicy, ClientOutcomeField, Config, HttpEncoding, RelayMode, UpstreamDescriptor,
+};
 use relay_filter::FilterStatKey;
-use relay_general::pii::{PiiAttachmentsProcessor, PiiProcessor};
-use relay_general::processor::{process_value, ProcessingState};
+use relay_general::pii::{CompiledPiiConfig, PiiAttachmentsProcessor, PiiConfig, PiiProcessor};
+use relay_general::processor::{
+    process_value, ProcessValue, ProcessingResult, ProcessingState, Processor,
+};
 use relay_general::protocol::{
-    self, Breadcrumb, ClientReport, Csp, Event, EventId, EventType, ExpectCt, ExpectStaple, Hpkp,
-    IpAddr, LenientString, Metrics, RelayInfo, SecurityReportType, SessionAggregates,
-    SessionAttributes, SessionUpdate, Timestamp, UserReport, Values,
+    self, Breadcrumb, ClientReport, Context, ContextInner, Csp, Event, EventId, EventType,
+    ExpectCt, ExpectStaple, Frame, Hpkp, IpAddr, LenientString, Metrics, NativeImagePath,
+    RelayInfo, SecurityReportType, SessionAggregates, SessionAttributes, SessionUpdate, Span,
+    SpanId, Tags, Timestamp, UserReport, Values,
+};
+use relay_general::store::{trim_breadcrumbs, ClockDriftProcessor};
+use relay_general::types::{
+    Annotated, Array, FromValue, Meta, Object, ProcessingAction, Remark, RemarkType, Value,
 };
-use relay_general::store::ClockDriftProcessor;
-use relay_general::types::{Annotated, Array, FromValue, Object, ProcessingAction, Value};
 use relay_log::LogError;
-use relay_metrics::{Bucket, Metric};
+use relay_metrics::{Bucket, Metric, MetricMeta, MetricNamespace};
 use relay_quotas::{DataCategory, RateLimits, ReasonCode, Scoping};
 use relay_redis::RedisPool;
-use relay_sampling::{RuleId, SamplingResult};
+use relay_sampling::{pseudo_random_from_uuid, RuleId, SamplingResult};
 use relay_statsd::metric;
 
 use crate::actors::outcome::{DiscardReason, Outcome, TrackOutcome};
 use crate::actors::outcome_aggregator::OutcomeAggregator;
 use crate::actors::project::{Feature, ProjectState};
 use crate::actors::project_cache::{
-    CheckEnvelope, GetProjectState, InsertMetrics, MergeBuckets, ProjectCache, ProjectError,
-    UpdateRateLimits,
+    AddMetricMeta, CheckEnvelope, GetProjectState, InsertMetrics, MergeBuckets, ProjectCache,
+    ProjectError, UpdateRateLimits,
 };
 use crate::actors::upstream::{SendRequest, UpstreamRelay, UpstreamRequest, UpstreamRequestError};
 use crate::envelope::{self, AttachmentType, ContentType, Envelope, EnvelopeError, Item, ItemType};
 use crate::extractors::{PartialDsn, RequestMeta};
 use crate::http::{HttpError, Request, RequestBuilder, Response};
 use crate::metrics_extraction::sessions::{extract_session_metrics, SessionMetricsConfig};
-use crate::service::ServerError;
-use crate::statsd::{RelayCounters, RelayHistograms, RelaySets, RelayTimers};
+use crate::service::{ServerError, ServerErrorKind};
+use crate::statsd::{RelayCounters, RelayGauges, RelayHistograms, RelaySets, RelayTimers};
 use crate::utils::{
-    self, ChunkedFormDataAggregator, EnvelopeSummary, FormDataIter, FutureExt, MinimalProfile,
-    ProfileError, SendWithOutcome,
+    self, ChunkedFormDataAggregator, EnvelopeSummary, FormDataIter, FutureExt, LogSampler,
+    MinimalProfile, ProfileError, SendWithOutcome,
 };
 
 #[cfg(feature = "processing")]
 use {
     crate::actors::store::{StoreEnvelope, StoreError, StoreForwarder},
-    crate::metrics_extraction::transactions::extract_transaction_metrics,
-    crate::service::ServerErrorKind,
+    crate::metrics_extraction::cardinality::CardinalityLimiter,
+    crate::metrics_extraction::errors::extract_error_metrics,
+    crate::metrics_extraction::transactions::{
+        extract_transaction_metrics, UnnamedTransactionBehavior,
+    },
+    crate::metrics_extraction::{add_os_name_tag, add_runtime_name_tag},
     crate::utils::{EnvelopeLimiter, ErrorBoundary},
     failure::ResultExt,
-    relay_general::store::{GeoIpLookup, StoreConfig, StoreProcessor},
+    relay_general::store::{
+        normalize_transaction_name, GeoIpLookup, GlobalDebugImages, StoreConfig, StoreProcessor,
+    },
     relay_quotas::{RateLimitingError, RedisRateLimiter},
     symbolic_unreal::{Unreal4Error, Unreal4ErrorKind},
 };
@@ -74,10 +89,129 @@ use {
 /// The minimum clock drift for correction to apply.
 const MINIMUM_CLOCK_DRIFT: Duration = Duration::from_secs(55 * 60);
 
+/// The maximum number of characters of an upstream response body written to logs.
+const MAX_LOGGED_RESPONSE_BODY: usize = 1024;
+
+/// The span operation of processing stages recorded with `self_instrument_transactions`.
+const SELF_INSTRUMENTATION_OP: &str = "relay.processing";
+
+/// Counts the remarks of data scrubbing rules on all values of an event.
+///
+/// Remarks are grouped by the type of the rule that created them. Built-in rules, such as
+/// `@email:replace`, are reported by their name without the `@` prefix and the redaction method.
+/// All other rules are reported as `custom`. Remarks from internal processing, such as trimming,
+/// are not counted.
+#[derive(Debug, Default)]
+struct PiiRemarkCounter {
+    counts: BTreeMap<String, usize>,
+}
+
+impl Processor for PiiRemarkCounter {
+    fn before_process<T: ProcessValue>(
+        &mut self,
+        _value: Option<&T>,
+        meta: &mut Meta,
+        _state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        for remark in meta.iter_remarks() {
+            let rule_id = remark.rule_id();
+            let rule_type = if rule_id.starts_with('!') {
+                continue;
+            } else if let Some(builtin) = rule_id.strip_prefix('@') {
+                builtin.split(':').next().unwrap_or(builtin)
+            } else {
+                "custom"
+            };
+
+            *self.counts.entry(rule_type.to_owned()).or_default() += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// The placeholder for the directory of stack frame paths removed by [`FramePathScrubber`].
+const FRAME_PATH_PLACEHOLDER: &str = "[path]";
+
+/// Replaces the directory portion of absolute stack frame paths with a placeholder.
+///
+/// Paths on developer machines often include user names. The file name is retained, since it is
+/// required for grouping and source context. URLs and relative paths are left untouched, since
+/// they are needed to resolve source maps and do not point into a user's file system.
+struct FramePathScrubber;
+
+impl FramePathScrubber {
+    /// Returns `true` if the path is an absolute Unix or Windows file system path.
+    fn is_absolute_path(path: &str) -> bool {
+        match path.as_bytes() {
+            [b'/', ..] | [b'\\', b'\\', ..] => true,
+            [drive, b':', b'\\', ..] | [drive, b':', b'/', ..] => drive.is_ascii_alphabetic(),
+            _ => false,
+        }
+    }
+
+    fn scrub_path(path: &mut Annotated<NativeImagePath>) {
+        let (path, meta) = match path {
+            Annotated(Some(path), meta) => (path, meta),
+            Annotated(None, _) => return,
+        };
+
+        if !Self::is_absolute_path(path.as_str()) {
+            return;
+        }
+
+        // The separator is retained in front of the file name.
+        let basename_start = match path.as_str().rfind(&['/', '\\'][..]) {
+            Some(index) => index,
+            None => return,
+        };
+
+        path.0 = format!("{}{}", FRAME_PATH_PLACEHOLDER, &path.0[basename_start..]);
+        meta.add_remark(Remark::new(RemarkType::Substituted, "!scrub_frame_paths"));
+    }
+}
+
+impl Processor for FramePathScrubber {
+    fn process_frame(
+        &mut self,
+        frame: &mut Frame,
+        _meta: &mut Meta,
+        _state: &ProcessingState<'_>,
+    ) -> ProcessingResult {
+        Self::scrub_path(&mut frame.filename);
+        Self::scrub_path(&mut frame.abs_path);
+        Ok(())
+    }
+}
+
+/// Records that the payload of an item with the given type could not be deserialized.
+fn track_parsing_failure(ty: &ItemType) {
+    metric!(
+        counter(RelayCounters::ItemParsingFailed) += 1,
+        item_type = &ty.to_string()
+    );
+}
+
+/// Returns the number of data scrubbing remarks on the event grouped by rule type.
+fn count_pii_remarks(
+    event: &mut Annotated<Event>,
+) -> Result<BTreeMap<String, usize>, ProcessingError> {
+    let mut counter = PiiRemarkCounter::default();
+    process_value(event, &mut counter, ProcessingState::root())
+        .map_err(ProcessingError::ProcessingFailed)?;
+    Ok(counter.counts)
+}
+
 #[derive(Debug, Fail)]
 pub enum QueueEnvelopeError {
     #[fail(display = "Too many envelopes (event_buffer_size reached)")]
     TooManyEnvelopes,
+
+    #[fail(display = "Envelope does not contain any items")]
+    EmptyEnvelope,
+
+    #[fail(display = "Relay is in read-only mode for maintenance")]
+    ReadOnly,
 }
 
 #[derive(Debug, Fail)]
@@ -95,6 +229,9 @@ enum ProcessingError {
     #[fail(display = "event payload too large")]
     PayloadTooLarge,
 
+    #[fail(display = "event payload exceeds the soft size limit")]
+    SoftSizeLimit,
+
     #[fail(display = "invalid transaction event")]
     InvalidTransaction,
 
@@ -107,6 +244,9 @@ enum ProcessingError {
     #[fail(display = "failed to extract event payload")]
     NoEventPayload,
 
+    #[fail(display = "missing event timestamp")]
+    MissingTimestamp,
+
     #[fail(display = "could not schedule project fetch")]
     ScheduleFailed,
 
@@ -125,7 +265,6 @@ enum ProcessingError {
     #[fail(display = "submission rejected with reason: {:?}", _0)]
     Rejected(DiscardReason),
 
-    #[cfg(feature = "processing")]
     #[fail(display = "event filtered with reason: {:?}", _0)]
     EventFiltered(FilterStatKey),
 
@@ -155,6 +294,9 @@ enum ProcessingError {
     #[fail(display = "envelope exceeded its configured lifetime")]
     Timeout,
 
+    #[fail(display = "envelope evicted from the full envelope buffer")]
+    Evicted,
+
     #[fail(display = "trace dropped by sampling rule {}", _0)]
     TraceSampled(RuleId),
 
@@ -167,6 +309,7 @@ impl ProcessingError {
         match *self {
             // General outcomes for invalid events
             Self::PayloadTooLarge => Some(Outcome::Invalid(DiscardReason::TooLarge)),
+            Self::SoftSizeLimit => Some(Outcome::Invalid(DiscardReason::SoftSizeLimit)),
             Self::InvalidJson(_) => Some(Outcome::Invalid(DiscardReason::InvalidJson)),
             Self::InvalidMsgpack(_) => Some(Outcome::Invalid(DiscardReason::InvalidMsgpack)),
             Self::InvalidSecurityType => Some(Outcome::Invalid(DiscardReason::SecurityReportType)),
@@ -174,6 +317,8 @@ impl ProcessingError {
             Self::InvalidTransaction => Some(Outcome::Invalid(DiscardReason::InvalidTransaction)),
             Self::DuplicateItem(_) => Some(Outcome::Invalid(DiscardReason::DuplicateItem)),
             Self::NoEventPayload => Some(Outcome::Invalid(DiscardReason::NoEventPayload)),
+            Self::MissingTimestamp => Some(Outcome::Invalid(DiscardReason::Timestamp)),
+            Self::Evicted => Some(Outcome::Invalid(DiscardReason::BufferOverflow)),
 
             // Processing-only outcomes (Sentry-internal Relays)
             #[cfg(feature = "processing")]
@@ -197,7 +342,6 @@ impl ProcessingError {
             // These outcomes are emitted at the source.
             Self::ScheduleFailed => None,
             Self::Rejected(_) => None,
-            #[cfg(feature = "processing")]
             Self::EventFiltered(_) => None,
             Self::TraceSampled(_) => None,
             Self::EventSampled(_) => None,
@@ -215,6 +359,73 @@ impl ProcessingError {
     fn should_keep_metrics(&self) -> bool {
         matches!(self, Self::TraceSampled(_) | Self::EventSampled(_))
     }
+
+    /// Returns the name of the error variant for structured logging.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::InvalidJson(_) => "invalid_json",
+            Self::InvalidMsgpack(_) => "invalid_msgpack",
+            #[cfg(feature = "processing")]
+            Self::InvalidUnrealReport(_) => "invalid_unreal_report",
+            Self::PayloadTooLarge => "payload_too_large",
+            Self::SoftSizeLimit => "soft_size_limit",
+            Self::InvalidTransaction => "invalid_transaction",
+            Self::ProcessingFailed(_) => "processing_failed",
+            Self::DuplicateItem(_) => "duplicate_item",
+            Self::NoEventPayload => "no_event_payload",
+            Self::MissingTimestamp => "missing_timestamp",
+            Self::ScheduleFailed => "schedule_failed",
+            Self::ProjectFailed(_) => "project_failed",
+            Self::MissingProjectId => "missing_project_id",
+            Self::InvalidSecurityType => "invalid_security_type",
+            Self::InvalidSecurityReport(_) => "invalid_security_report",
+            Self::Rejected(_) => "rejected",
+            Self::EventFiltered(_) => "event_filtered",
+            Self::SerializeFailed(_) => "serialize_failed",
+            Self::EnvelopeBuildFailed(_) => "envelope_build_failed",
+            Self::BodyEncodingFailed(_) => "body_encoding_failed",
+            Self::UpstreamRequestFailed(_) => "upstream_request_failed",
+            #[cfg(feature = "processing")]
+            Self::StoreFailed(_) => "store_failed",
+            Self::RateLimited => "rate_limited",
+            #[cfg(feature = "processing")]
+            Self::QuotasFailed(_) => "quotas_failed",
+            Self::Timeout => "timeout",
+            Self::Evicted => "evicted",
+            Self::TraceSampled(_) => "trace_sampled",
+            Self::EventSampled(_) => "event_sampled",
+        }
+    }
+}
+
+/// Logs a failed envelope with structured fields.
+///
+/// The project id, event id, error variant and item types are added as key-value pairs to the log
+/// record. Only the item types are attached to the Sentry error report, since the other fields
+/// would make its tags unbounded in cardinality.
+fn log_processing_error(
+    error: &ProcessingError,
+    envelope_context: &EnvelopeContext,
+    item_types: &str,
+) {
+    let project_id = envelope_context.scoping().project_id.value();
+    let event_id = (envelope_context.event_id())
+        .map(|event_id| event_id.to_string())
+        .unwrap_or_default();
+
+    relay_log::with_scope(
+        |scope| scope.set_extra("item_types", item_types.into()),
+        || {
+            relay_log::error!(
+                project_id = project_id,
+                event_id = event_id.as_str(),
+                error = error.name(),
+                item_types = item_types;
+                "error processing envelope: {}",
+                LogError(error)
+            )
+        },
+    );
 }
 
 #[cfg(feature = "processing")]
@@ -322,13 +533,25 @@ impl EnvelopeContext {
             outcome_aggregator.do_send(TrackOutcome {
                 timestamp: self.received_at,
                 scoping: self.scoping,
-                outcome,
+                outcome: outcome.clone(),
                 event_id: self.event_id,
                 remote_addr: self.remote_addr,
                 category: DataCategory::Profile,
                 quantity: self.summary.profile_quantity as u32,
             })
         }
+
+        if self.summary.replay_quantity > 0 {
+            outcome_aggregator.do_send(TrackOutcome {
+                timestamp: self.received_at,
+                scoping: self.scoping,
+                outcome,
+                event_id: self.event_id,
+                remote_addr: self.remote_addr,
+                category: DataCategory::Replay,
+                quantity: self.summary.replay_quantity as u32,
+            })
+        }
     }
 }
 
@@ -388,6 +611,12 @@ struct ProcessEnvelopeState {
 
     /// The envelope context before processing.
     envelope_context: EnvelopeContext,
+
+    /// Timings of processing stages for self-instrumentation.
+    ///
+    /// These are only recorded for transactions if `self_instrument_transactions` is enabled. They
+    /// are added to the transaction as spans before it is serialized.
+    stage_timings: Vec<StageTiming>,
 }
 
 impl ProcessEnvelopeState {
@@ -396,7 +625,7 @@ impl ProcessEnvelopeState {
     /// This is used to branch into the processing pipeline. If this function returns false, only
     /// rate limits are executed.
     fn creates_event(&self) -> bool {
-        self.envelope.items().any(Item::creates_event)
+        self.envelope.creates_event()
     }
 
     /// Returns true if there is an event in the processing state.
@@ -426,12 +655,22 @@ impl ProcessEnvelopeState {
     }
 
     /// Removes the event payload from this processing state.
-    #[cfg(feature = "processing")]
     fn remove_event(&mut self) {
         self.event = Annotated::empty();
     }
 }
 
+/// Start and end time of a processing stage recorded for self-instrumentation.
+#[derive(Clone, Copy, Debug)]
+struct StageTiming {
+    /// The name of the processing stage.
+    stage: &'static str,
+    /// The time at which the stage started.
+    start: DateTime<Utc>,
+    /// The time at which the stage ended.
+    end: DateTime<Utc>,
+}
+
 /// Fields of client reports that map to specific [`Outcome`]s without content.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 enum ClientReportField {
@@ -448,6 +687,18 @@ enum ClientReportField {
     ClientDiscard,
 }
 
+impl ClientReportField {
+    /// Returns the corresponding field in [`Config::client_outcome_fields`].
+    fn config_field(self) -> ClientOutcomeField {
+        match self {
+            Self::Filtered => ClientOutcomeField::FilteredEvents,
+            Self::FilteredSampling => ClientOutcomeField::FilteredSamplingEvents,
+            Self::RateLimited => ClientOutcomeField::RateLimitedEvents,
+            Self::ClientDiscard => ClientOutcomeField::DiscardedEvents,
+        }
+    }
+}
+
 /// Parse an outcome from an outcome ID and a reason string.
 ///
 /// Currently only used to reconstruct outcomes encoded in client reports.
@@ -474,10 +725,16 @@ fn outcome_from_parts(field: ClientReportField, reason: &str) -> Result<Outcome,
 /// Synchronous service for processing envelopes.
 pub struct EnvelopeProcessor {
     config: Arc<Config>,
+    log_sampler: LogSampler,
+    test_pii_config: Option<PiiConfig>,
     #[cfg(feature = "processing")]
     rate_limiter: Option<RedisRateLimiter>,
     #[cfg(feature = "processing")]
     geoip_lookup: Option<Arc<GeoIpLookup>>,
+    #[cfg(feature = "processing")]
+    cardinality_limiter: Arc<CardinalityLimiter>,
+    #[cfg(feature = "processing")]
+    global_debug_images: GlobalDebugImages,
 }
 
 impl EnvelopeProcessor {
@@ -501,12 +758,21 @@ impl EnvelopeProcessor {
             let rate_limiter =
                 _redis.map(|pool| RedisRateLimiter::new(pool).max_limit(config.max_rate_limit()));
 
+            // Shared across all workers, so that the budget applies to the entire Relay.
+            let cardinality_limiter = Arc::new(CardinalityLimiter::new());
+
+            let global_debug_images: GlobalDebugImages =
+                serde_json::from_value(config.global_debug_images().into())
+                    .context(ServerErrorKind::ConfigError)?;
+
             Ok(SyncArbiter::start(
                 thread_count,
                 clone!(config, || {
                     EnvelopeProcessor::new(config.clone())
                         .with_rate_limiter(rate_limiter.clone())
                         .with_geoip_lookup(geoip_lookup.clone())
+                        .with_cardinality_limiter(cardinality_limiter.clone())
+                        .with_global_debug_images(global_debug_images.clone())
                 }),
             ))
         }
@@ -520,12 +786,29 @@ impl EnvelopeProcessor {
 
     #[inline]
     fn new(config: Arc<Config>) -> Self {
+        let test_pii_config =
+            config.test_pii_config().and_then(|value| {
+                match serde_json::from_value(value.clone()) {
+                    Ok(pii_config) => Some(pii_config),
+                    Err(error) => {
+                        relay_log::error!("invalid test_pii_config: {}", LogError(&error));
+                        None
+                    }
+                }
+            });
+
         Self {
+            log_sampler: LogSampler::new(config.processing_log_sample_rate()),
+            test_pii_config,
             config,
             #[cfg(feature = "processing")]
             rate_limiter: None,
             #[cfg(feature = "processing")]
             geoip_lookup: None,
+            #[cfg(feature = "processing")]
+            cardinality_limiter: Arc::new(CardinalityLimiter::new()),
+            #[cfg(feature = "processing")]
+            global_debug_images: GlobalDebugImages::default(),
         }
     }
 
@@ -543,6 +826,33 @@ impl EnvelopeProcessor {
         self
     }
 
+    #[cfg(feature = "processing")]
+    #[inline]
+    fn with_cardinality_limiter(mut self, cardinality_limiter: Arc<CardinalityLimiter>) -> Self {
+        self.cardinality_limiter = cardinality_limiter;
+        self
+    }
+
+    #[cfg(feature = "processing")]
+    #[inline]
+    fn with_global_debug_images(mut self, global_debug_images: GlobalDebugImages) -> Self {
+        self.global_debug_images = global_debug_images;
+        self
+    }
+
+    /// Clamps the sender's `sent_at` timestamp to the maximum allowed future offset.
+    ///
+    /// A `sent_at` far in the future would otherwise cause the clock drift correction to shift all
+    /// timestamps into the past. The timestamp is limited to `received_at + max_secs_in_future`.
+    fn clamp_sent_at(
+        &self,
+        sent_at: Option<DateTime<Utc>>,
+        received_at: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let max_sent_at = received_at + SignedDuration::seconds(self.config.max_secs_in_future());
+        sent_at.map(|sent_at| min(sent_at, max_sent_at))
+    }
+
     /// Returns Ok(true) if attributes were modified.
     /// Returns Err if the session should be dropped.
     fn validate_attributes(
@@ -558,6 +868,11 @@ impl EnvelopeProcessor {
             return Err(());
         }
 
+        if truncate_release(&mut attributes.release, self.config.max_release_length()).is_some() {
+            relay_log::trace!("truncated session release '{}'", attributes.release);
+            changed = true;
+        }
+
         if let Some(ref env) = attributes.environment {
             if let Err(e) = protocol::validate_environment(env) {
                 relay_log::trace!("removing invalid environment '{}': {}", env, e);
@@ -566,6 +881,13 @@ impl EnvelopeProcessor {
             }
         }
 
+        if attributes.environment.is_none() {
+            if let Some(environment) = self.config.default_environment() {
+                attributes.environment = Some(environment.to_owned());
+                changed = true;
+            }
+        }
+
         if let Some(ref ip_address) = attributes.ip_address {
             if ip_address.is_auto() {
                 attributes.ip_address = client_addr.map(IpAddr::from);
@@ -580,8 +902,9 @@ impl EnvelopeProcessor {
         &self,
         received: DateTime<Utc>,
         timestamp: DateTime<Utc>,
+        max_secs_in_past: i64,
     ) -> bool {
-        let max_age = SignedDuration::seconds(self.config.max_session_secs_in_past());
+        let max_age = SignedDuration::seconds(max_secs_in_past);
         if (received - timestamp) > max_age {
             relay_log::trace!("skipping session older than {} days", max_age.num_days());
             return false;
@@ -605,6 +928,7 @@ impl EnvelopeProcessor {
         &self,
         item: &mut Item,
         received: DateTime<Utc>,
+        max_secs_in_past: i64,
         client: Option<&str>,
         client_addr: Option<net::IpAddr>,
         metrics_config: SessionMetricsConfig,
@@ -618,6 +942,7 @@ impl EnvelopeProcessor {
             Ok(session) => session,
             Err(error) => {
                 relay_log::trace!("skipping invalid session payload: {}", LogError(&error));
+                track_parsing_failure(item.ty());
                 return false;
             }
         };
@@ -627,6 +952,11 @@ impl EnvelopeProcessor {
             return false;
         }
 
+        if session.sequence > self.config.max_session_sequence() {
+            relay_log::trace!("skipping session due to sequence above configured maximum");
+            return false;
+        }
+
         if clock_drift_processor.is_drifted() {
             relay_log::trace!("applying clock drift correction to session");
             clock_drift_processor.process_datetime(&mut session.started);
@@ -651,7 +981,7 @@ impl EnvelopeProcessor {
 
         // Validate timestamps
         for t in [session.timestamp, session.started] {
-            if !self.is_valid_session_timestamp(received, t) {
+            if !self.is_valid_session_timestamp(received, t, max_secs_in_past) {
                 return false;
             }
         }
@@ -695,6 +1025,7 @@ impl EnvelopeProcessor {
         &self,
         item: &mut Item,
         received: DateTime<Utc>,
+        max_secs_in_past: i64,
         client: Option<&str>,
         client_addr: Option<net::IpAddr>,
         metrics_config: SessionMetricsConfig,
@@ -708,10 +1039,19 @@ impl EnvelopeProcessor {
             Ok(session) => session,
             Err(error) => {
                 relay_log::trace!("skipping invalid sessions payload: {}", LogError(&error));
+                track_parsing_failure(item.ty());
                 return false;
             }
         };
 
+        if session.aggregates.len() > self.config.max_session_aggregates() {
+            relay_log::trace!(
+                "skipping sessions payload with {} aggregates",
+                session.aggregates.len()
+            );
+            return false;
+        }
+
         if clock_drift_processor.is_drifted() {
             relay_log::trace!("applying clock drift correction to session");
             for aggregate in &mut session.aggregates {
@@ -721,9 +1061,9 @@ impl EnvelopeProcessor {
         }
 
         // Validate timestamps
-        session
-            .aggregates
-            .retain(|aggregate| self.is_valid_session_timestamp(received, aggregate.started));
+        session.aggregates.retain(|aggregate| {
+            self.is_valid_session_timestamp(received, aggregate.started, max_secs_in_past)
+        });
 
         // Aftter timestamp validation, aggregates could now be empty
         if session.aggregates.is_empty() {
@@ -774,18 +1114,25 @@ impl EnvelopeProcessor {
         let received = state.envelope_context.received_at;
         let extracted_metrics = &mut state.extracted_metrics;
         let metrics_config = state.project_state.config().session_metrics;
+        let max_secs_in_past = state
+            .project_state
+            .config()
+            .max_session_secs_in_past
+            .unwrap_or_else(|| self.config.max_session_secs_in_past());
         let envelope = &mut state.envelope;
         let client = envelope.meta().client().map(|x| x.to_owned());
         let client_addr = envelope.meta().client_addr();
 
+        let sent_at = self.clamp_sent_at(envelope.sent_at(), received);
         let clock_drift_processor =
-            ClockDriftProcessor::new(envelope.sent_at(), received).at_least(MINIMUM_CLOCK_DRIFT);
+            ClockDriftProcessor::new(sent_at, received).at_least(MINIMUM_CLOCK_DRIFT);
 
         envelope.retain_items(|item| {
             match item.ty() {
                 ItemType::Session => self.process_session(
                     item,
                     received,
+                    max_secs_in_past,
                     client.as_deref(),
                     client_addr,
                     metrics_config,
@@ -795,6 +1142,7 @@ impl EnvelopeProcessor {
                 ItemType::Sessions => self.process_session_aggregates(
                     item,
                     received,
+                    max_secs_in_past,
                     client.as_deref(),
                     client_addr,
                     metrics_config,
@@ -821,6 +1169,7 @@ impl EnvelopeProcessor {
                 Ok(session) => session,
                 Err(error) => {
                     relay_log::error!("failed to store user report: {}", LogError(&error));
+                    track_parsing_failure(item.ty());
                     return false;
                 }
             };
@@ -844,6 +1193,11 @@ impl EnvelopeProcessor {
     /// client SDKs.  The outcomes are removed here and sent directly to the outcomes
     /// system.
     fn process_client_reports(&self, state: &mut ProcessEnvelopeState) {
+        // if raw forwarding is configured, client reports are passed on without conversion.
+        if self.config.forward_client_reports_raw() {
+            return;
+        }
+
         // if client outcomes are disabled we leave the the client reports unprocessed
         // and pass them on.
         if !self.config.emit_outcomes().any() || !self.config.emit_client_outcomes() {
@@ -860,8 +1214,9 @@ impl EnvelopeProcessor {
         let mut output_events = BTreeMap::new();
         let received = state.envelope_context.received_at;
 
-        let clock_drift_processor = ClockDriftProcessor::new(state.envelope.sent_at(), received)
-            .at_least(MINIMUM_CLOCK_DRIFT);
+        let sent_at = self.clamp_sent_at(state.envelope.sent_at(), received);
+        let clock_drift_processor =
+            ClockDriftProcessor::new(sent_at, received).at_least(MINIMUM_CLOCK_DRIFT);
 
         // we're going through all client reports but we're effectively just merging
         // them into the first one.
@@ -894,6 +1249,9 @@ impl EnvelopeProcessor {
                         }));
 
                     for (outcome_type, discarded_event) in input_events {
+                        if !self.emits_client_outcome(outcome_type) {
+                            continue;
+                        }
                         if discarded_event.reason.len() > 200 {
                             relay_log::trace!("ignored client outcome with an overlong reason");
                             continue;
@@ -910,7 +1268,10 @@ impl EnvelopeProcessor {
                         timestamp.get_or_insert(ts);
                     }
                 }
-                Err(err) => relay_log::trace!("invalid client report received: {}", LogError(&err)),
+                Err(err) => {
+                    relay_log::trace!("invalid client report received: {}", LogError(&err));
+                    track_parsing_failure(item.ty());
+                }
             }
             false
         });
@@ -971,18 +1332,247 @@ impl EnvelopeProcessor {
         }
     }
 
+    /// Returns `true` if outcomes of the given client report field are emitted.
+    fn emits_client_outcome(&self, field: ClientReportField) -> bool {
+        match self.config.client_outcome_fields() {
+            Some(fields) => fields.contains(&field.config_field()),
+            None => true,
+        }
+    }
+
+    /// Drops envelopes from SDKs older than the minimum version in [`Config::min_sdk_versions`].
+    ///
+    /// The SDK name and version are parsed from the client string of the request. Envelopes are
+    /// accepted if the client version is not valid semver.
+    fn filter_sdk_version(&self, state: &mut ProcessEnvelopeState) -> Result<(), ProcessingError> {
+        let min_versions = self.config.min_sdk_versions();
+        if min_versions.is_empty() {
+            return Ok(());
+        }
+
+        let client = match state.envelope.meta().client() {
+            Some(client) => client,
+            None => return Ok(()),
+        };
+
+        let (name, version) = match client.find('/') {
+            Some(index) => (&client[..index], &client[index + 1..]),
+            None => return Ok(()),
+        };
+
+        let is_outdated = match (semver::Version::parse(version), min_versions.get(name)) {
+            (Ok(version), Some(min_version)) => version < *min_version,
+            _ => false,
+        };
+
+        if !is_outdated {
+            return Ok(());
+        }
+
+        relay_log::trace!("dropping envelope from outdated sdk {}", client);
+        let reason = FilterStatKey::ReleaseVersion;
+        state
+            .envelope_context
+            .send_outcomes(Outcome::Filtered(reason));
+        Err(ProcessingError::EventFiltered(reason))
+    }
+
+    /// Removes items of types that are disabled in the project config.
+    fn remove_disabled_items(&self, state: &mut ProcessEnvelopeState) {
+        let project_state = state.project_state.clone();
+        let disabled_item_types = &project_state.config.disabled_item_types;
+        if disabled_item_types.is_empty() {
+            return;
+        }
+
+        let context = state.envelope_context;
+        state.envelope.retain_items(|item| {
+            if !disabled_item_types.contains(item.ty()) {
+                return true;
+            }
+
+            relay_log::trace!("dropping disabled item of type {}", item.ty());
+
+            let outcome = match item.ty() {
+                ItemType::Attachment => Some((DataCategory::Attachment, item.len().max(1))),
+                ItemType::Session => Some((DataCategory::Session, 1)),
+                ItemType::Profile | ItemType::ProfileChunk => Some((DataCategory::Profile, 1)),
+                _ => utils::infer_event_category(item).map(|category| (category, 1)),
+            };
+
+            if let Some((category, quantity)) = outcome {
+                OutcomeAggregator::from_registry().do_send(TrackOutcome {
+                    timestamp: context.received_at,
+                    scoping: context.scoping,
+                    outcome: Outcome::Invalid(DiscardReason::FeatureDisabled),
+                    event_id: context.event_id,
+                    remote_addr: context.remote_addr,
+                    category,
+                    quantity: quantity as u32,
+                });
+            }
+
+            false
+        });
+
+        // Outcomes of the remaining items must no longer include the removed ones.
+        state.envelope_context.update(&state.envelope);
+    }
+
+    /// Removes attachments with a filename that already occurred earlier in the envelope.
+    fn dedupe_attachments(&self, state: &mut ProcessEnvelopeState) {
+        if !self.config.dedupe_attachments_by_filename() {
+            return;
+        }
+
+        let context = state.envelope_context;
+        let mut filenames = BTreeSet::new();
+        let mut removed = false;
+
+        state.envelope.retain_items(|item| {
+            if item.ty() != &ItemType::Attachment {
+                return true;
+            }
+
+            let filename = match item.filename() {
+                Some(filename) => filename,
+                None => return true,
+            };
+
+            if filenames.insert(filename.to_owned()) {
+                return true;
+            }
+
+            relay_log::trace!("dropping duplicate attachment {}", filename);
+            OutcomeAggregator::from_registry().do_send(TrackOutcome {
+                timestamp: context.received_at,
+                scoping: context.scoping,
+                outcome: Outcome::Invalid(DiscardReason::DuplicateItem),
+                event_id: context.event_id,
+                remote_addr: context.remote_addr,
+                category: DataCategory::Attachment,
+                quantity: item.len().max(1) as u32,
+            });
+
+            removed = true;
+            false
+        });
+
+        if removed {
+            // Outcomes of the remaining items must no longer include the removed ones.
+            state.envelope_context.update(&state.envelope);
+        }
+    }
+
+    /// Drops attachments until the combined size fits into
+    /// [`Config::max_total_attachment_bytes`].
+    ///
+    /// Attachments that create the event, such as minidumps, are never dropped. Plain attachments
+    /// are dropped before other attachment types, and larger attachments before smaller ones.
+    fn limit_attachments_size(&self, state: &mut ProcessEnvelopeState) {
+        let max_size = match self.config.max_total_attachment_bytes() {
+            Some(max_size) => max_size,
+            None => return,
+        };
+
+        let mut total_size = 0;
+        let mut candidates = Vec::new();
+
+        let attachments = state
+            .envelope
+            .items()
+            .filter(|item| item.ty() == &ItemType::Attachment);
+
+        for (index, item) in attachments.enumerate() {
+            total_size += item.len();
+            if !item.creates_event() {
+                let plain =
+                    item.attachment_type().unwrap_or_default() == AttachmentType::Attachment;
+                candidates.push((index, item.len(), plain));
+            }
+        }
+
+        if total_size <= max_size {
+            return;
+        }
+
+        // Sort plain attachments first, then by descending size. The sort is stable, so earlier
+        // attachments are dropped first among attachments of equal size.
+        candidates.sort_by(|(_, size_a, plain_a), (_, size_b, plain_b)| {
+            plain_b.cmp(plain_a).then(size_b.cmp(size_a))
+        });
+
+        let mut dropped = BTreeSet::new();
+        for (index, size, _) in candidates {
+            if total_size <= max_size {
+                break;
+            }
+
+            dropped.insert(index);
+            total_size -= size;
+        }
+
+        let context = state.envelope_context;
+        let mut index = 0;
+
+        state.envelope.retain_items(|item| {
+            if item.ty() != &ItemType::Attachment {
+                return true;
+            }
+
+            let keep = !dropped.contains(&index);
+            index += 1;
+            if keep {
+                return true;
+            }
+
+            relay_log::trace!("dropping attachment exceeding the total size limit");
+            OutcomeAggregator::from_registry().do_send(TrackOutcome {
+                timestamp: context.received_at,
+                scoping: context.scoping,
+                outcome: Outcome::Invalid(DiscardReason::TooLarge),
+                event_id: context.event_id,
+                remote_addr: context.remote_addr,
+                category: DataCategory::Attachment,
+                quantity: item.len().max(1) as u32,
+            });
+
+            false
+        });
+
+        // Outcomes of the remaining items must no longer include the removed ones.
+        state.envelope_context.update(&state.envelope);
+    }
+
     /// Remove profiles if the feature flag is not enabled
     fn process_profiles(&self, state: &mut ProcessEnvelopeState) {
         let profiling_enabled = state.project_state.has_feature(Feature::Profiling);
         let context = state.envelope_context;
+        let mut removed = false;
         state.envelope.retain_items(|item| {
             match item.ty() {
-                ItemType::Profile => {
+                ItemType::Profile | ItemType::ProfileChunk => {
                     if !profiling_enabled {
+                        OutcomeAggregator::from_registry().do_send(TrackOutcome {
+                            timestamp: context.received_at,
+                            scoping: context.scoping,
+                            outcome: Outcome::Invalid(DiscardReason::FeatureDisabled),
+                            event_id: context.event_id,
+                            remote_addr: context.remote_addr,
+                            category: DataCategory::Profile,
+                            quantity: 1,
+                        });
+
+                        removed = true;
                         return false;
                     }
                     if self.config.processing_enabled() {
-                        if self.parse_profile(item).is_err() {
+                        let result = match item.ty() {
+                            ItemType::ProfileChunk => utils::validate_profile_chunk(item),
+                            _ => self.parse_profile(item),
+                        };
+
+                        if result.is_err() {
                             let outcome_aggregator = OutcomeAggregator::from_registry();
 
                             outcome_aggregator.do_send(TrackOutcome {
@@ -995,6 +1585,7 @@ impl EnvelopeProcessor {
                                 quantity: 1,
                             });
 
+                            removed = true;
                             return false;
                         }
                         return true;
@@ -1004,17 +1595,88 @@ impl EnvelopeProcessor {
                 _ => true, // Keep all other item types
             }
         });
+
+        if removed {
+            // Outcomes of the remaining items must no longer include the removed ones.
+            state.envelope_context.update(&state.envelope);
+        }
     }
 
-    fn process_replay_recordings(&self, state: &mut ProcessEnvelopeState) {
-        let replays_enabled = state.project_state.has_feature(Feature::Replays);
-        state.envelope.retain_items(|item| match item.ty() {
-            ItemType::ReplayRecording => replays_enabled,
-            _ => true,
+    /// Removes view hierarchy attachments that cannot be parsed in processing mode.
+    fn process_view_hierarchies(&self, state: &mut ProcessEnvelopeState) {
+        if !self.config.processing_enabled() {
+            return;
+        }
+
+        let context = state.envelope_context;
+        let mut removed = false;
+
+        state.envelope.retain_items(|item| {
+            if item.attachment_type() != Some(AttachmentType::ViewHierarchy) {
+                return true;
+            }
+
+            let error = match utils::validate_view_hierarchy(&item.payload()) {
+                Ok(()) => return true,
+                Err(error) => error,
+            };
+
+            relay_log::trace!("dropping invalid view hierarchy: {}", LogError(&error));
+            OutcomeAggregator::from_registry().do_send(TrackOutcome {
+                timestamp: context.received_at,
+                scoping: context.scoping,
+                outcome: Outcome::Invalid(DiscardReason::InvalidViewHierarchy),
+                event_id: context.event_id,
+                remote_addr: context.remote_addr,
+                category: DataCategory::Attachment,
+                quantity: item.len().max(1) as u32,
+            });
+
+            removed = true;
+            false
         });
+
+        if removed {
+            // Outcomes of the remaining items must no longer include the removed ones.
+            state.envelope_context.update(&state.envelope);
+        }
     }
 
-    /// Creates and initializes the processing state.
+    fn process_replay_recordings(&self, state: &mut ProcessEnvelopeState) {
+        let replays_enabled = state.project_state.has_feature(Feature::Replays);
+        if replays_enabled {
+            return;
+        }
+
+        let context = state.envelope_context;
+        let mut removed = false;
+
+        state.envelope.retain_items(|item| {
+            if item.ty() != &ItemType::ReplayRecording {
+                return true;
+            }
+
+            OutcomeAggregator::from_registry().do_send(TrackOutcome {
+                timestamp: context.received_at,
+                scoping: context.scoping,
+                outcome: Outcome::Invalid(DiscardReason::FeatureDisabled),
+                event_id: context.event_id,
+                remote_addr: context.remote_addr,
+                category: DataCategory::Replay,
+                quantity: 1,
+            });
+
+            removed = true;
+            false
+        });
+
+        if removed {
+            // Outcomes of the remaining items must no longer include the removed ones.
+            state.envelope_context.update(&state.envelope);
+        }
+    }
+
+    /// Creates and initializes the processing state.
     ///
     /// This applies defaults to the envelope and initializes empty rate limits.
     fn prepare_state(
@@ -1063,6 +1725,7 @@ impl EnvelopeProcessor {
             project_state,
             project_id,
             envelope_context,
+            stage_timings: Vec::new(),
         })
     }
 
@@ -1096,6 +1759,7 @@ impl EnvelopeProcessor {
             "cocoa" => utils::parse_cocoa_profile(item),
             "typescript" => utils::parse_typescript_profile(item),
             "rust" => utils::parse_rust_profile(item),
+            "php" => utils::parse_php_profile(item),
             _ => Err(ProfileError::PlatformNotSupported),
         }
     }
@@ -1327,8 +1991,10 @@ impl EnvelopeProcessor {
             ItemType::Sessions => false,
             ItemType::Metrics => false,
             ItemType::MetricBuckets => false,
+            ItemType::MetricMeta => false,
             ItemType::ClientReport => false,
             ItemType::Profile => false,
+            ItemType::ProfileChunk => false,
             ItemType::ReplayRecording => false,
             // Without knowing more, `Unknown` items are allowed to be repeated
             ItemType::Unknown(_) => false,
@@ -1367,26 +2033,51 @@ impl EnvelopeProcessor {
         }
 
         let (event, event_len) = if let Some(mut item) = event_item.or(security_item) {
-            relay_log::trace!("processing json event");
+            if self.log_sampler.sample() {
+                relay_log::trace!("processing json event");
+            }
             state.sample_rates = item.take_sample_rates();
-            metric!(timer(RelayTimers::EventProcessingDeserialize), {
+            let ty = item.ty().clone();
+            let (event, event_len) = metric!(timer(RelayTimers::EventProcessingDeserialize), {
                 // Event items can never include transactions, so retain the event type and let
                 // inference deal with this during store normalization.
-                self.event_from_json_payload(item, None)?
-            })
+                self.event_from_json_payload(item, None).map_err(|error| {
+                    track_parsing_failure(&ty);
+                    error
+                })?
+            });
+
+            // Only events sent by clients are checked. Relay creates events without a timestamp
+            // for security reports and attachments, which receive one during normalization.
+            if ty == ItemType::Event
+                && self.config.require_event_timestamp()
+                && is_missing_timestamp(&event)
+            {
+                relay_log::trace!("dropping event without timestamp");
+                return Err(ProcessingError::MissingTimestamp);
+            }
+
+            (event, event_len)
         } else if let Some(mut item) = transaction_item {
-            relay_log::trace!("processing json transaction");
+            if self.log_sampler.sample() {
+                relay_log::trace!("processing json transaction");
+            }
             state.sample_rates = item.take_sample_rates();
             metric!(timer(RelayTimers::EventProcessingDeserialize), {
                 // Transaction items can only contain transaction events. Force the event type to
                 // hint to normalization that we're dealing with a transaction now.
-                self.event_from_json_payload(item, Some(EventType::Transaction))?
+                self.event_from_json_payload(item, Some(EventType::Transaction))
+                    .map_err(|error| {
+                        track_parsing_failure(&ItemType::Transaction);
+                        error
+                    })?
             })
         } else if let Some(mut item) = raw_security_item {
             relay_log::trace!("processing security report");
             state.sample_rates = item.take_sample_rates();
             self.event_from_security_report(item).map_err(|error| {
                 relay_log::error!("failed to extract security report: {}", LogError(&error));
+                track_parsing_failure(&ItemType::RawSecurity);
                 error
             })?
         } else if attachment_item.is_some() || breadcrumbs1.is_some() || breadcrumbs2.is_some() {
@@ -1406,6 +2097,15 @@ impl EnvelopeProcessor {
             (Annotated::empty(), 0)
         };
 
+        if let Some(soft_limit) = self.config.soft_max_event_size() {
+            let event_id = envelope.event_id().unwrap_or_else(EventId::new);
+            let sample_rate = self.config.soft_max_event_sample_rate();
+            if event_len > soft_limit && pseudo_random_from_uuid(event_id.0) >= sample_rate {
+                relay_log::trace!("dropping event exceeding the soft size limit");
+                return Err(ProcessingError::SoftSizeLimit);
+            }
+        }
+
         state.event = event;
         state.metrics.bytes_ingested_event = Annotated::new(event_len as u64);
 
@@ -1458,6 +2158,15 @@ impl EnvelopeProcessor {
             None => return Err(ProcessingError::NoEventPayload),
         };
 
+        if is_transaction && self.config.validate_trace_ids() && !has_valid_trace_id(event) {
+            relay_log::trace!("dropping transaction with malformed trace id");
+            return Err(ProcessingError::InvalidTransaction);
+        }
+
+        // Breadcrumbs are trimmed again during store normalization. Trimming them here avoids
+        // the cost of processing all breadcrumbs of events that exceed the limit by far.
+        trim_breadcrumbs(event, self.config.max_breadcrumbs());
+
         if !self.config.processing_enabled() {
             lazy_static! {
                 static ref MY_VERSION_STRING: String = format!("{}", RelayVersion::current());
@@ -1475,6 +2184,31 @@ impl EnvelopeProcessor {
                 }));
         }
 
+        if event.environment.value().is_none() {
+            if let Some(environment) = self.config.default_environment() {
+                event.environment = Annotated::new(environment.to_owned());
+            }
+        }
+
+        if let Annotated(Some(LenientString(ref mut release)), ref mut meta) = event.release {
+            let max_chars = self.config.max_release_length();
+            if let Some(original_length) = truncate_release(release, max_chars) {
+                relay_log::trace!("truncated event release '{}'", release);
+                meta.set_original_length(Some(original_length));
+                meta.add_remark(Remark::new(RemarkType::Substituted, "!limit"));
+            }
+        }
+
+        let static_tags = self.config.static_event_tags();
+        if !static_tags.is_empty() {
+            let tags = event.tags.get_or_insert_with(Tags::default);
+            for (key, value) in static_tags {
+                if !tags.0.contains(key) {
+                    tags.0.insert(key.clone(), Annotated::new(value.clone()));
+                }
+            }
+        }
+
         // Event id is set statically in the ingest path.
         let event_id = envelope.event_id().unwrap_or_default();
         debug_assert!(!event_id.is_nil());
@@ -1523,11 +2257,28 @@ impl EnvelopeProcessor {
             None => None,
         };
 
-        let mut processor = ClockDriftProcessor::new(sent_at, state.envelope_context.received_at)
-            .at_least(MINIMUM_CLOCK_DRIFT);
+        let received_at = state.envelope_context.received_at;
+        let sent_at = self.clamp_sent_at(sent_at, received_at);
+        let mut processor =
+            ClockDriftProcessor::new(sent_at, received_at).at_least(MINIMUM_CLOCK_DRIFT);
         process_value(&mut state.event, &mut processor, ProcessingState::root())
             .map_err(|_| ProcessingError::InvalidTransaction)?;
 
+        if self.config.clamp_future_timestamps() {
+            let max_secs = SignedDuration::seconds(self.config.max_secs_in_future());
+            if let Some(event) = state.event.value_mut() {
+                let timestamp = event.timestamp.value().map(|ts| ts.into_inner());
+                if timestamp.map_or(false, |ts| ts > received_at + max_secs) {
+                    relay_log::trace!("clamping future event timestamp to received time");
+                    event.timestamp.set_value(Some(received_at.into()));
+                    event
+                        .timestamp
+                        .meta_mut()
+                        .add_remark(Remark::new(RemarkType::Substituted, "!future_timestamp"));
+                }
+            }
+        }
+
         // Log timestamp delays for all events after clock drift correction. This happens before
         // store processing, which could modify the timestamp if it exceeds a threshold. We are
         // interested in the actual delay before this correction.
@@ -1545,6 +2296,75 @@ impl EnvelopeProcessor {
         Ok(())
     }
 
+    /// Extracts a count metric from error events if enabled for the project.
+    #[cfg(feature = "processing")]
+    fn extract_error_metrics(&self, state: &mut ProcessEnvelopeState) {
+        let config = match state.project_state.config.error_metrics {
+            Some(ErrorBoundary::Ok(ref config)) => config,
+            _ => return,
+        };
+
+        if !state.project_state.has_feature(Feature::ErrorMetrics)
+            || state.event_category() != Some(DataCategory::Error)
+        {
+            return;
+        }
+
+        let received_at = state.envelope_context.received_at;
+        let received_at = UnixTimestamp::from_secs(received_at.timestamp() as u64);
+
+        if let Some(event) = state.event.value() {
+            extract_error_metrics(config, event, received_at, &mut state.extracted_metrics);
+        }
+    }
+
+    /// Adds tags from normalized contexts to metrics extracted from the event.
+    ///
+    /// The `os.name` and `runtime.name` tags are added to metrics if enabled for the project. This
+    /// runs after [`store_process_event`](Self::store_process_event), since normalization infers
+    /// the OS context from the user agent and the runtime name from the raw runtime description.
+    /// Metrics of events dropped by dynamic sampling are not tagged, since these events are not
+    /// normalized.
+    #[cfg(feature = "processing")]
+    fn tag_normalized_contexts(&self, state: &mut ProcessEnvelopeState) {
+        let config = &state.project_state.config;
+
+        let namespaces = |tag: &str| {
+            let mut namespaces = Vec::new();
+
+            if let Some(ErrorBoundary::Ok(ref config)) = config.transaction_metrics {
+                if config.has_custom_tag(tag) {
+                    namespaces.push(MetricNamespace::Transactions);
+                }
+            }
+
+            if let Some(ErrorBoundary::Ok(ref config)) = config.error_metrics {
+                if config.has_custom_tag(tag) {
+                    namespaces.push(MetricNamespace::Errors);
+                }
+            }
+
+            namespaces
+        };
+
+        let os_name_namespaces = namespaces("os.name");
+        let runtime_name_namespaces = namespaces("runtime.name");
+
+        if let Some(event) = state.event.value() {
+            if !os_name_namespaces.is_empty() {
+                add_os_name_tag(&mut state.extracted_metrics, event, &os_name_namespaces);
+            }
+
+            if !runtime_name_namespaces.is_empty() {
+                add_runtime_name_tag(
+                    &mut state.extracted_metrics,
+                    event,
+                    &runtime_name_namespaces,
+                );
+            }
+        }
+    }
+
     #[cfg(feature = "processing")]
     fn store_process_event(&self, state: &mut ProcessEnvelopeState) -> Result<(), ProcessingError> {
         let ProcessEnvelopeState {
@@ -1566,6 +2386,11 @@ impl EnvelopeProcessor {
             );
         }
 
+        let priority_measurements = match project_state.config.transaction_metrics {
+            Some(ErrorBoundary::Ok(ref config)) => config.priority_measurements().clone(),
+            _ => Default::default(),
+        };
+
         let store_config = StoreConfig {
             project_id: Some(state.project_id.value()),
             client_ip: envelope.meta().client_addr().map(IpAddr::from),
@@ -1580,10 +2405,28 @@ impl EnvelopeProcessor {
             is_renormalize: Some(false),
             remove_other: Some(true),
             normalize_user_agent: Some(true),
+            canonicalize_ip_addresses: Some(self.config.canonicalize_ip_addresses()),
+            normalize_levels: Some(self.config.normalize_levels()),
+            dedupe_breadcrumbs: Some(self.config.dedupe_breadcrumbs()),
+            max_breadcrumbs: Some(self.config.max_breadcrumbs()),
+            max_exceptions: Some(self.config.max_exceptions()),
+            max_threads: Some(self.config.max_event_threads()),
+            scrub_query_strings: Some(self.config.scrub_query_strings()),
+            derive_device_class: Some(self.config.derive_device_class()),
+            max_json_depth: self.config.max_json_depth(),
+            max_fingerprint_entries: Some(self.config.max_fingerprint_entries()),
             sent_at: envelope.sent_at(),
             received_at: Some(envelope_context.received_at),
             breakdowns: project_state.config.breakdowns_v2.clone(),
             span_attributes: project_state.config.span_attributes.clone(),
+            max_tags: Some(self.config.max_event_tags()),
+            max_measurements: Some(self.config.max_measurements()),
+            priority_measurements,
+            max_request_body_bytes: Some(self.config.max_request_body_size()),
+            max_message_length: Some(self.config.max_message_length()),
+            global_debug_images: self.global_debug_images.clone(),
+            // Transaction names are canonicalized before metrics extraction.
+            transaction_name_config: None,
         };
 
         let mut store_processor = StoreProcessor::new(store_config, self.geoip_lookup.as_deref());
@@ -1664,6 +2507,21 @@ impl EnvelopeProcessor {
         Ok(())
     }
 
+    /// Replaces identifiers in the transaction name with placeholders.
+    ///
+    /// This runs before metrics extraction so that metrics are tagged with the canonical name.
+    #[cfg(feature = "processing")]
+    fn normalize_transaction_name(&self, state: &mut ProcessEnvelopeState) {
+        let config = match state.project_state.config.transaction_name_config {
+            Some(ref config) => config,
+            None => return,
+        };
+
+        if let Some(event) = state.event.value_mut() {
+            normalize_transaction_name(event, config);
+        }
+    }
+
     /// Extract metrics for transaction events with breakdowns and measurements.
     #[cfg(feature = "processing")]
     fn extract_transaction_metrics(
@@ -1683,6 +2541,15 @@ impl EnvelopeProcessor {
             .as_slice();
 
         if let Some(event) = state.event.value() {
+            if config.unnamed_transactions() == UnnamedTransactionBehavior::Drop
+                && event.ty.value() == Some(&EventType::Transaction)
+                && event.transaction.value().is_none()
+            {
+                relay_log::trace!("dropping transaction without transaction name");
+                return Err(ProcessingError::InvalidTransaction);
+            }
+
+            let before_len = state.extracted_metrics.len();
             let extracted_anything;
 
             metric!(
@@ -1695,31 +2562,155 @@ impl EnvelopeProcessor {
                         breakdowns_config,
                         conditional_tagging_config,
                         event,
+                        state.envelope.trace_context(),
                         &mut state.extracted_metrics,
                     );
                 }
             );
+
+            if let Some(limit) = config.cardinality_limit() {
+                let mut extracted = state.extracted_metrics.split_off(before_len);
+                self.cardinality_limiter.retain(
+                    state.envelope_context.scoping.project_key,
+                    limit,
+                    &mut extracted,
+                    |metric| config.is_priority_metric(&metric.name),
+                );
+                state.extracted_metrics.extend(extracted);
+            }
+
             Ok(())
         } else {
             Err(ProcessingError::NoEventPayload)
         }
     }
 
+    /// Returns `true` if the envelope was sent by Relay's own Sentry SDK.
+    ///
+    /// This compares the envelope's public key with the DSN configured for Relay's error reporting.
+    fn is_self_reported(&self, state: &ProcessEnvelopeState) -> bool {
+        match self.config.sentry().enabled_dsn() {
+            Some(dsn) => dsn.public_key() == state.envelope.meta().public_key().as_str(),
+            None => false,
+        }
+    }
+
+    /// Records the timing of a processing stage that started at `start` and ends now.
+    ///
+    /// Timings are only recorded if `self_instrument_transactions` is enabled, and only for
+    /// Relay's own transactions. Customer transactions are never modified.
+    fn record_stage(
+        &self,
+        state: &mut ProcessEnvelopeState,
+        stage: &'static str,
+        start: DateTime<Utc>,
+    ) {
+        if !self.config.self_instrument_transactions()
+            || state.event_type() != Some(EventType::Transaction)
+            || !self.is_self_reported(state)
+        {
+            return;
+        }
+
+        state.stage_timings.push(StageTiming {
+            stage,
+            start,
+            end: Utc::now(),
+        });
+    }
+
+    /// Adds the recorded processing stage timings as child spans of the transaction.
+    ///
+    /// Transactions that already carry processing spans, for instance because they passed through
+    /// another instrumented Relay or were emitted by Relay itself, are skipped so that spans are
+    /// not added repeatedly.
+    fn add_stage_spans(&self, state: &mut ProcessEnvelopeState) {
+        let timings = std::mem::take(&mut state.stage_timings);
+        if timings.is_empty() {
+            return;
+        }
+
+        let event = match state.event.value_mut() {
+            Some(event) => event,
+            None => return,
+        };
+
+        let trace_context = event
+            .contexts
+            .value()
+            .and_then(|contexts| contexts.get("trace"))
+            .and_then(Annotated::value);
+
+        let (trace_id, parent_span_id) = match trace_context {
+            Some(ContextInner(Context::Trace(trace_context))) => (
+                trace_context.trace_id.clone(),
+                trace_context.span_id.clone(),
+            ),
+            _ => return,
+        };
+
+        let spans = event.spans.get_or_insert_with(Vec::new);
+        let is_instrumented = spans.iter().any(|span| {
+            span.value().and_then(|span| span.op.as_str()) == Some(SELF_INSTRUMENTATION_OP)
+        });
+
+        if is_instrumented {
+            return;
+        }
+
+        for timing in timings {
+            let span_id = Uuid::new_v4().to_simple().to_string()[..16].to_owned();
+            spans.push(Annotated::new(Span {
+                timestamp: Annotated::new(timing.end.into()),
+                start_timestamp: Annotated::new(timing.start.into()),
+                description: Annotated::new(timing.stage.to_owned()),
+                op: Annotated::new(SELF_INSTRUMENTATION_OP.to_owned()),
+                span_id: Annotated::new(SpanId(span_id)),
+                parent_span_id: parent_span_id.clone(),
+                trace_id: trace_id.clone(),
+                ..Span::default()
+            }));
+        }
+    }
+
+    /// Returns `true` if the envelope's project key is exempt from data scrubbing.
+    fn is_scrubbing_exempt(&self, state: &ProcessEnvelopeState) -> bool {
+        let project_key = state.envelope_context.scoping.project_key;
+        let exempt = self.config.scrubbing_exempt_keys().contains(&project_key);
+        if exempt {
+            relay_log::debug!("skipping data scrubbing for exempt project {}", project_key);
+        }
+        exempt
+    }
+
     /// Apply data privacy rules to the event payload.
     ///
     /// This uses both the general `datascrubbing_settings`, as well as the the PII rules.
     fn scrub_event(&self, state: &mut ProcessEnvelopeState) -> Result<(), ProcessingError> {
+        if self.is_scrubbing_exempt(state) {
+            return Ok(());
+        }
+
         let event = &mut state.event;
         let config = &state.project_state.config;
 
+        let count_hits = self.config.metrics_pii_rule_hits();
+        let remarks_before = if count_hits {
+            count_pii_remarks(event)?
+        } else {
+            BTreeMap::new()
+        };
+
+        let datascrubbing_config = config.datascrubbing_settings.pii_config();
+        let project_configs = if config.datascrubbing_before_pii_config {
+            [(*datascrubbing_config).as_ref(), config.pii_config.as_ref()]
+        } else {
+            [config.pii_config.as_ref(), (*datascrubbing_config).as_ref()]
+        };
+
         metric!(timer(RelayTimers::EventProcessingPii), {
-            if let Some(ref config) = config.pii_config {
-                let compiled = config.compiled();
-                let mut processor = PiiProcessor::new(&compiled);
-                process_value(event, &mut processor, ProcessingState::root())
-                    .map_err(ProcessingError::ProcessingFailed)?;
-            }
-            if let Some(ref config) = *config.datascrubbing_settings.pii_config() {
+            let pii_configs = project_configs.iter().flatten().copied();
+            for config in pii_configs.chain(self.test_pii_config.as_ref()) {
                 let compiled = config.compiled();
                 let mut processor = PiiProcessor::new(&compiled);
                 process_value(event, &mut processor, ProcessingState::root())
@@ -1727,6 +2718,35 @@ impl EnvelopeProcessor {
             }
         });
 
+        if config.scrub_server_name {
+            if let Some(event) = event.value_mut() {
+                event.server_name.set_value(None);
+
+                // In processing mode, normalization has already moved the server name into tags.
+                if let Some(tags) = event.tags.value_mut() {
+                    tags.0.remove("server_name");
+                }
+            }
+        }
+
+        if config.scrub_frame_paths {
+            process_value(event, &mut FramePathScrubber, ProcessingState::root())
+                .map_err(ProcessingError::ProcessingFailed)?;
+        }
+
+        if count_hits {
+            for (rule_type, count) in count_pii_remarks(event)? {
+                let before = remarks_before.get(&rule_type).copied().unwrap_or(0);
+                let hits = count.saturating_sub(before);
+                if hits > 0 {
+                    metric!(
+                        counter(RelayCounters::PiiRuleHits) += hits as i64,
+                        rule_type = &rule_type
+                    );
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -1734,8 +2754,13 @@ impl EnvelopeProcessor {
     ///
     /// This only applies the new PII rules that explicitly select `ValueType::Binary` or one of the
     /// attachment types. When special attachments are detected, these are scrubbed with custom
-    /// logic; otherwise the entire attachment is treated as a single binary blob.
+    /// logic; otherwise the entire attachment is treated as a single binary blob. View hierarchies
+    /// are scrubbed like JSON event payloads.
     fn scrub_attachments(&self, state: &mut ProcessEnvelopeState) {
+        if self.is_scrubbing_exempt(state) {
+            return;
+        }
+
         let envelope = &mut state.envelope;
         if let Some(ref config) = state.project_state.config.pii_config {
             let minidump = envelope
@@ -1778,6 +2803,13 @@ impl EnvelopeProcessor {
 
                 item.set_payload(content_type, payload);
             }
+
+            let compiled = config.compiled();
+            for item in envelope.items_mut() {
+                if item.attachment_type() == Some(AttachmentType::ViewHierarchy) {
+                    scrub_view_hierarchy(item, &compiled);
+                }
+            }
         }
     }
 
@@ -1795,8 +2827,11 @@ impl EnvelopeProcessor {
 
         // If there are sample rates, write them back to the envelope. In processing mode, sample
         // rates have been removed from the state and burnt into the event via `finalize_event`.
+        // When configured, they are dropped to force the upstream to re-sample.
         if let Some(sample_rates) = state.sample_rates.take() {
-            event_item.set_sample_rates(sample_rates);
+            if !self.config.strip_sample_rates_on_forward() {
+                event_item.set_sample_rates(sample_rates);
+            }
         }
 
         state.envelope.add_item(event_item);
@@ -1804,18 +2839,64 @@ impl EnvelopeProcessor {
         Ok(())
     }
 
+    /// Removes an event that failed normalization while keeping the remaining items.
+    ///
+    /// This only applies to [`ProcessingError::InvalidTransaction`] if
+    /// [`Config::keep_items_of_invalid_events`] is enabled. All other errors are returned as-is.
+    fn discard_invalid_event(
+        &self,
+        state: &mut ProcessEnvelopeState,
+        error: ProcessingError,
+    ) -> Result<(), ProcessingError> {
+        if !matches!(error, ProcessingError::InvalidTransaction)
+            || !self.config.keep_items_of_invalid_events()
+        {
+            return Err(error);
+        }
+
+        relay_log::trace!("dropping invalid event, keeping remaining items");
+
+        if let Some(category) = state.event_category() {
+            let context = state.envelope_context;
+            OutcomeAggregator::from_registry().do_send(TrackOutcome {
+                timestamp: context.received_at,
+                scoping: context.scoping,
+                outcome: Outcome::Invalid(DiscardReason::InvalidTransaction),
+                event_id: context.event_id,
+                remote_addr: context.remote_addr,
+                category,
+                quantity: 1,
+            });
+        }
+
+        state.remove_event();
+
+        // Outcomes of the remaining items must no longer include the event.
+        state.envelope_context.update(&state.envelope);
+
+        Ok(())
+    }
+
     /// Run dynamic sampling rules to see if we keep the event or remove it.
     fn sample_event(&self, state: &mut ProcessEnvelopeState) -> Result<(), ProcessingError> {
         let event = match &state.event.0 {
             None => return Ok(()), // can't process without an event
             Some(event) => event,
         };
+
+        let project_key = state.envelope_context.scoping.project_key;
+        if self.config.force_keep_projects().contains(&project_key) {
+            relay_log::debug!("keeping event of pinned project {}", project_key);
+            return Ok(());
+        }
+
         let client_ip = state.envelope.meta().client_addr();
         match utils::should_keep_event(
             event,
             client_ip,
             &state.project_state,
             self.config.processing_enabled(),
+            self.config.min_effective_sample_rate(),
         ) {
             SamplingResult::Drop(rule_id) => {
                 state
@@ -1839,11 +2920,25 @@ impl EnvelopeProcessor {
             };
         }
 
+        macro_rules! instrument {
+            ($stage:literal, $stage_expr:expr) => {{
+                let start = Utc::now();
+                let result = $stage_expr;
+                self.record_stage(state, $stage, start);
+                result
+            }};
+        }
+
+        self.filter_sdk_version(state)?;
+        self.remove_disabled_items(state);
+        self.dedupe_attachments(state);
+        self.limit_attachments_size(state);
         self.process_sessions(state);
         self.process_client_reports(state);
         self.process_user_reports(state);
         self.process_profiles(state);
         self.process_replay_recordings(state);
+        self.process_view_hierarchies(state);
 
         if state.creates_event() {
             if_processing!({
@@ -1857,18 +2952,36 @@ impl EnvelopeProcessor {
                 self.create_placeholders(state);
             });
 
-            self.finalize_event(state)?;
-
-            if_processing!({
-                self.extract_transaction_metrics(state)?;
+            instrument!("finalize_event", {
+                self.finalize_event(state)
+                    .or_else(|error| self.discard_invalid_event(state, error))?
             });
 
-            self.sample_event(state)?;
+            if state.has_event() {
+                if_processing!({
+                    instrument!("normalize_transaction_name", {
+                        self.normalize_transaction_name(state)
+                    });
+                    instrument!("extract_transaction_metrics", {
+                        self.extract_transaction_metrics(state)?
+                    });
+                    self.extract_error_metrics(state);
+                });
+
+                instrument!("sample_event", self.sample_event(state)?);
 
-            if_processing!({
-                self.store_process_event(state)?;
-                self.filter_event(state)?;
-            });
+                if_processing!({
+                    instrument!("store_process_event", {
+                        self.store_process_event(state)
+                            .or_else(|error| self.discard_invalid_event(state, error))?
+                    });
+
+                    if state.has_event() {
+                        self.filter_event(state)?;
+                        self.tag_normalized_contexts(state);
+                    }
+                });
+            }
         }
 
         if_processing!({
@@ -1876,7 +2989,8 @@ impl EnvelopeProcessor {
         });
 
         if state.has_event() {
-            self.scrub_event(state)?;
+            instrument!("scrub_event", self.scrub_event(state)?);
+            self.add_stage_spans(state);
             self.serialize_event(state)?;
         }
 
@@ -1946,6 +3060,7 @@ impl EnvelopeProcessor {
     fn encode_envelope_body(
         body: Vec<u8>,
         http_encoding: HttpEncoding,
+        zstd_level: i32,
     ) -> Result<Vec<u8>, std::io::Error> {
         let envelope_body = match http_encoding {
             HttpEncoding::Identity => body,
@@ -1964,6 +3079,11 @@ impl EnvelopeProcessor {
                 encoder.write_all(body.as_ref())?;
                 encoder.finish()?
             }
+            HttpEncoding::Zstd => {
+                let mut encoder = ZstdEncoder::new(Vec::new(), zstd_level)?;
+                encoder.write_all(body.as_ref())?;
+                encoder.finish()?
+            }
         };
         Ok(envelope_body)
     }
@@ -2053,6 +3173,7 @@ impl Handler<ProcessMetrics> for EnvelopeProcessor {
         let received_timestamp = UnixTimestamp::from_secs(received.timestamp() as u64);
 
         let project_cache = ProjectCache::from_registry();
+        let sent_at = self.clamp_sent_at(sent_at, received);
         let clock_drift_processor =
             ClockDriftProcessor::new(sent_at, received).at_least(MINIMUM_CLOCK_DRIFT);
 
@@ -2100,6 +3221,75 @@ impl Handler<ProcessMetrics> for EnvelopeProcessor {
     }
 }
 
+/// Parses metric metadata items and passes them to the project cache.
+///
+/// Each item is parsed and validated separately. Items with invalid JSON are dropped, other item
+/// types are ignored with an error message. The project cache stores the metadata and forwards new
+/// entries via [`SendMetricMeta`].
+struct ProcessMetricMeta {
+    /// A list of metric meta items.
+    pub items: Vec<Item>,
+
+    /// The target project.
+    pub project_key: ProjectKey,
+}
+
+impl Message for ProcessMetricMeta {
+    type Result = ();
+}
+
+impl Handler<ProcessMetricMeta> for EnvelopeProcessor {
+    type Result = ();
+
+    fn handle(&mut self, message: ProcessMetricMeta, _context: &mut Self::Context) -> Self::Result {
+        let ProcessMetricMeta { items, project_key } = message;
+        let project_cache = ProjectCache::from_registry();
+
+        for item in items {
+            if item.ty() != &ItemType::MetricMeta {
+                relay_log::error!(
+                    "invalid item of type {} passed to ProcessMetricMeta",
+                    item.ty()
+                );
+                continue;
+            }
+
+            match MetricMeta::parse_all(&item.payload()) {
+                Ok(meta) => {
+                    relay_log::trace!("adding metric meta to project cache");
+                    project_cache.do_send(AddMetricMeta::new(project_key, meta));
+                }
+                Err(error) => {
+                    relay_log::debug!("failed to parse metric meta: {}", LogError(&error));
+                    metric!(counter(RelayCounters::MetricMetaParsingFailed) += 1);
+                }
+            }
+        }
+    }
+}
+
+/// Removes all metric metadata items from the envelope.
+fn take_metric_meta(envelope: &mut Envelope) -> Vec<Item> {
+    let mut items = Vec::new();
+    while let Some(item) = envelope.take_item_by(|i| i.ty() == &ItemType::MetricMeta) {
+        items.push(item);
+    }
+    items
+}
+
+/// Returns a copy of the envelope with only the items of the given types.
+///
+/// Returns `None` if the envelope does not contain any such items.
+fn mirror_envelope(envelope: &Envelope, item_types: &[ItemType]) -> Option<Envelope> {
+    if !envelope.items().any(|item| item_types.contains(item.ty())) {
+        return None;
+    }
+
+    let mut mirror = envelope.clone();
+    mirror.retain_items(|item| item_types.contains(item.ty()));
+    Some(mirror)
+}
+
 /// Error returned from [`EnvelopeManager::send_envelope`].
 #[derive(Debug)]
 #[allow(clippy::enum_variant_names)]
@@ -2116,14 +3306,71 @@ enum SendEnvelopeError {
 /// Either a captured envelope or an error that occured during processing.
 pub type CapturedEnvelope = Result<Envelope, String>;
 
+/// Bounded storage for envelopes in capture mode.
+///
+/// Captures that have not been accessed within the TTL are evicted. If the store exceeds its
+/// maximum size, the least recently used captures are evicted first.
 #[derive(Debug)]
-struct EncodeEnvelope {
-    envelope_body: Vec<u8>,
-    envelope_meta: RequestMeta,
-    scoping: Scoping,
-    http_encoding: HttpEncoding,
-    response_sender: Option<oneshot::Sender<Result<(), SendEnvelopeError>>>,
-    project_key: ProjectKey,
+struct CaptureStore {
+    max_captures: usize,
+    ttl: Duration,
+    captures: BTreeMap<EventId, (Instant, CapturedEnvelope)>,
+}
+
+impl CaptureStore {
+    fn new(max_captures: usize, ttl: Duration) -> Self {
+        Self {
+            max_captures,
+            ttl,
+            captures: BTreeMap::new(),
+        }
+    }
+
+    fn insert(&mut self, event_id: EventId, capture: CapturedEnvelope) {
+        self.captures.insert(event_id, (Instant::now(), capture));
+        self.evict();
+    }
+
+    fn get(&mut self, event_id: EventId) -> Option<CapturedEnvelope> {
+        self.evict();
+
+        let (last_used, capture) = self.captures.get_mut(&event_id)?;
+        *last_used = Instant::now();
+        Some(capture.clone())
+    }
+
+    fn evict(&mut self) {
+        let ttl = self.ttl;
+        self.captures
+            .retain(|_, (last_used, _)| last_used.elapsed() < ttl);
+
+        while self.captures.len() > self.max_captures {
+            let oldest = self
+                .captures
+                .iter()
+                .min_by_key(|(_, (last_used, _))| *last_used)
+                .map(|(event_id, _)| *event_id);
+
+            match oldest {
+                Some(event_id) => self.captures.remove(&event_id),
+                None => break,
+            };
+        }
+
+        metric!(gauge(RelayGauges::CapturedEnvelopes) = self.captures.len() as u64);
+    }
+}
+
+#[derive(Debug)]
+struct EncodeEnvelope {
+    envelope_body: Vec<u8>,
+    envelope_meta: RequestMeta,
+    signature: Option<String>,
+    upstream: Option<UpstreamDescriptor<'static>>,
+    scoping: Scoping,
+    http_encoding: HttpEncoding,
+    response_sender: Option<oneshot::Sender<Result<(), SendEnvelopeError>>>,
+    project_key: ProjectKey,
 }
 
 impl Message for EncodeEnvelope {
@@ -2137,12 +3384,15 @@ impl Handler<EncodeEnvelope> for EnvelopeProcessor {
         let EncodeEnvelope {
             envelope_body,
             envelope_meta,
+            signature,
+            upstream,
             scoping,
             http_encoding,
             response_sender,
             project_key,
         } = message;
-        match Self::encode_envelope_body(envelope_body, http_encoding) {
+        let zstd_level = self.config.http_zstd_level();
+        match Self::encode_envelope_body(envelope_body, http_encoding, zstd_level) {
             Err(e) => {
                 response_sender.map(|sender| {
                     sender
@@ -2154,10 +3404,13 @@ impl Handler<EncodeEnvelope> for EnvelopeProcessor {
                 let request = SendEnvelope {
                     envelope_body,
                     envelope_meta,
+                    signature,
+                    upstream,
                     scoping,
                     http_encoding,
                     response_sender,
                     project_key,
+                    client_ip_header: self.config.client_ip_header_name().map(str::to_owned),
                 };
                 UpstreamRelay::from_registry().do_send(SendRequest(request));
             }
@@ -2169,10 +3422,13 @@ impl Handler<EncodeEnvelope> for EnvelopeProcessor {
 struct SendEnvelope {
     envelope_body: Vec<u8>,
     envelope_meta: RequestMeta,
+    signature: Option<String>,
+    upstream: Option<UpstreamDescriptor<'static>>,
     scoping: Scoping,
     http_encoding: HttpEncoding,
     response_sender: Option<oneshot::Sender<Result<(), SendEnvelopeError>>>,
     project_key: ProjectKey,
+    client_ip_header: Option<String>,
 }
 
 impl UpstreamRequest for SendEnvelope {
@@ -2192,13 +3448,22 @@ impl UpstreamRequest for SendEnvelope {
             .header_opt("User-Agent", meta.user_agent())
             .header("X-Sentry-Auth", meta.auth_header())
             .header("X-Forwarded-For", meta.forwarded_for())
+            .header_opt("X-Sentry-Relay-Signature", self.signature.as_deref())
             .header("Content-Type", envelope::CONTENT_TYPE);
 
+        if let Some(ref header_name) = self.client_ip_header {
+            builder.header_opt(header_name, meta.client_addr().map(|ip| ip.to_string()));
+        }
+
         let envelope_body = self.envelope_body.clone();
         metric!(histogram(RelayHistograms::UpstreamEnvelopeBodySize) = envelope_body.len() as u64);
         builder.body(envelope_body)
     }
 
+    fn upstream(&self) -> Option<&UpstreamDescriptor<'_>> {
+        self.upstream.as_ref()
+    }
+
     fn respond(
         &mut self,
         result: Result<Response, UpstreamRequestError>,
@@ -2222,6 +3487,11 @@ impl UpstreamRequest for SendEnvelope {
 
                 Box::new(future)
             }
+            Err(error) if self.upstream.is_some() => {
+                // Mirroring is best-effort, and rate limits of the mirror must not be applied.
+                relay_log::warn!("failed to mirror envelope: {}", LogError(&error));
+                Box::new(future::err(()))
+            }
             Err(error) => {
                 match error {
                     UpstreamRequestError::RateLimited(upstream_limits) => {
@@ -2238,6 +3508,18 @@ impl UpstreamRequest for SendEnvelope {
                         }
                     }
                     error => {
+                        if let Some(body) = error.response_body() {
+                            let end = body
+                                .char_indices()
+                                .nth(MAX_LOGGED_RESPONSE_BODY)
+                                .map_or(body.len(), |(index, _)| index);
+                            relay_log::debug!(
+                                "upstream rejected envelope: {}: {}",
+                                LogError(&error),
+                                &body[..end]
+                            );
+                        }
+
                         if let Some(sender) = sender {
                             sender
                                 .send(Err(SendEnvelopeError::UpstreamRequestFailed(error)))
@@ -2254,8 +3536,13 @@ impl UpstreamRequest for SendEnvelope {
 pub struct EnvelopeManager {
     config: Arc<Config>,
     active_envelopes: u32,
-    captures: BTreeMap<EventId, CapturedEnvelope>,
+    next_envelope_id: u64,
+    queued_envelopes: BTreeMap<u64, oneshot::Sender<()>>,
+    evicted_envelopes: BTreeSet<u64>,
+    captures: CaptureStore,
     processor: Addr<EnvelopeProcessor>,
+    log_sampler: LogSampler,
+    mirror_item_types: Vec<ItemType>,
     #[cfg(feature = "processing")]
     store_forwarder: Option<Addr<StoreForwarder>>,
 }
@@ -2273,16 +3560,125 @@ impl EnvelopeManager {
             None
         };
 
+        let captures = CaptureStore::new(
+            config.max_captured_envelopes(),
+            config.captured_envelope_ttl(),
+        );
+
+        let mut mirror_item_types = Vec::new();
+        for name in config.mirror_item_types() {
+            match name.parse() {
+                Ok(ItemType::Unknown(_)) | Err(_) => {
+                    relay_log::error!("unknown item type `{}` in mirror_item_types", name);
+                    return Err(ServerErrorKind::ConfigError.into());
+                }
+                Ok(ty) => mirror_item_types.push(ty),
+            }
+        }
+
         Ok(EnvelopeManager {
+            log_sampler: LogSampler::new(config.processing_log_sample_rate()),
+            mirror_item_types,
             config,
             active_envelopes: 0,
-            captures: BTreeMap::new(),
+            next_envelope_id: 0,
+            queued_envelopes: BTreeMap::new(),
+            evicted_envelopes: BTreeSet::new(),
+            captures,
             processor,
             #[cfg(feature = "processing")]
             store_forwarder,
         })
     }
 
+    /// Ensures that there is space for another envelope in the envelope buffer.
+    ///
+    /// If the buffer is full, this either rejects the new envelope or evicts the oldest queued
+    /// envelope, depending on [`Config::buffer_overflow_policy`].
+    fn reserve_envelope_slot(&mut self) -> Result<(), QueueEnvelopeError> {
+        if self.active_envelopes < self.config.envelope_buffer_size() {
+            return Ok(());
+        }
+
+        let policy = self.config.buffer_overflow_policy();
+        if policy == BufferOverflowPolicy::DropOldest && self.evict_oldest_envelope() {
+            return Ok(());
+        }
+
+        Err(QueueEnvelopeError::TooManyEnvelopes)
+    }
+
+    /// Rejects envelopes without items if [`Config::reject_empty_envelopes`] is enabled.
+    fn check_empty_envelope(&self, envelope: &Envelope) -> Result<(), QueueEnvelopeError> {
+        if self.config.reject_empty_envelopes() && envelope.is_empty() {
+            return Err(QueueEnvelopeError::EmptyEnvelope);
+        }
+
+        Ok(())
+    }
+
+    /// Rejects all envelopes if [`Config::read_only_mode`] is enabled.
+    fn check_read_only(&self) -> Result<(), QueueEnvelopeError> {
+        if self.config.read_only_mode() {
+            return Err(QueueEnvelopeError::ReadOnly);
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether a new envelope can be queued and records its receipt.
+    ///
+    /// The received counter is emitted for all envelopes, including those rejected here.
+    fn accept_envelope(&mut self, envelope: &Envelope) -> Result<(), QueueEnvelopeError> {
+        let result = self
+            .check_read_only()
+            .and_then(|()| self.check_empty_envelope(envelope))
+            .and_then(|()| self.reserve_envelope_slot());
+
+        if self.config.metrics_envelopes_received() {
+            metric!(
+                counter(RelayCounters::EnvelopeReceived) += 1,
+                accepted = if result.is_ok() { "true" } else { "false" },
+            );
+        }
+
+        result
+    }
+
+    /// Evicts the oldest queued envelope that has not been sent yet.
+    ///
+    /// The buffer slot of the evicted envelope is released immediately, so that the new envelope
+    /// can take its place. The evicted envelope fails with [`ProcessingError::Evicted`] and emits
+    /// outcomes. Returns `false` if there is no envelope that can be evicted.
+    fn evict_oldest_envelope(&mut self) -> bool {
+        let envelope_id = match self.queued_envelopes.keys().next() {
+            Some(&envelope_id) => envelope_id,
+            None => return false,
+        };
+
+        if let Some(sender) = self.queued_envelopes.remove(&envelope_id) {
+            sender.send(()).ok();
+        }
+
+        metric!(counter(RelayCounters::EnvelopeEvicted) += 1);
+        self.evicted_envelopes.insert(envelope_id);
+        self.active_envelopes -= 1;
+        true
+    }
+
+    /// Takes a slot in the envelope buffer and registers the envelope in queue order.
+    ///
+    /// The envelope can be evicted through the returned receiver until it is being sent.
+    fn register_envelope(&mut self) -> (u64, oneshot::Receiver<()>) {
+        let envelope_id = self.next_envelope_id;
+        self.next_envelope_id += 1;
+        self.active_envelopes += 1;
+
+        let (evict_tx, evict_rx) = oneshot::channel();
+        self.queued_envelopes.insert(envelope_id, evict_tx);
+        (envelope_id, evict_rx)
+    }
+
     /// Sends an envelope to the upstream or Kafka and handles returned rate limits.
     fn send_envelope(
         &mut self,
@@ -2294,7 +3690,9 @@ impl EnvelopeManager {
         #[cfg(feature = "processing")]
         {
             if let Some(ref store_forwarder) = self.store_forwarder {
-                relay_log::trace!("sending envelope to kafka");
+                if self.log_sampler.sample() {
+                    relay_log::trace!("sending envelope to kafka");
+                }
                 let future = store_forwarder
                     .send(StoreEnvelope {
                         envelope,
@@ -2322,7 +3720,9 @@ impl EnvelopeManager {
             return Box::new(future::ok(()));
         }
 
-        relay_log::trace!("sending envelope to sentry endpoint");
+        if self.log_sampler.sample() {
+            relay_log::trace!("sending envelope to sentry endpoint");
+        }
 
         // Override the `sent_at` timestamp. Since the envelope went through basic
         // normalization, all timestamps have been corrected. We propagate the new
@@ -2330,46 +3730,85 @@ impl EnvelopeManager {
         // potentially apply correction again. This is done as close to sending as
         // possible so that we avoid internal delays.
         envelope.set_sent_at(Utc::now());
-        let envelope_meta = envelope.meta();
 
-        let original_body = match envelope.to_vec() {
-            Ok(v) => v,
-            Err(e) => return Box::new(future::err(SendEnvelopeError::EnvelopeBuildFailed(e))),
-        };
-        let http_encoding = self.config.http_encoding();
+        if let Some(upstream) = self.config.mirror_upstream_descriptor() {
+            if let Some(mirror) = mirror_envelope(&envelope, &self.mirror_item_types) {
+                let upstream = Some(upstream.clone());
+                let result = self.submit_envelope(mirror, scoping, project_key, upstream, None);
+                if let Err(error) = result {
+                    relay_log::warn!("failed to mirror envelope: {}", LogError(&error));
+                }
+            }
+        }
 
         let (tx, rx) = oneshot::channel();
+        if let Err(error) = self.submit_envelope(envelope, scoping, project_key, None, Some(tx)) {
+            return Box::new(future::err(SendEnvelopeError::EnvelopeBuildFailed(error)));
+        }
+
+        Box::new(
+            rx.map_err(|_| {
+                SendEnvelopeError::UpstreamRequestFailed(UpstreamRequestError::ChannelClosed)
+            })
+            .flatten(),
+        )
+    }
+
+    /// Serializes the envelope and sends it to the given upstream, or the primary upstream.
+    fn submit_envelope(
+        &self,
+        mut envelope: Envelope,
+        scoping: Scoping,
+        project_key: ProjectKey,
+        upstream: Option<UpstreamDescriptor<'static>>,
+        response_sender: Option<oneshot::Sender<Result<(), SendEnvelopeError>>>,
+    ) -> Result<(), EnvelopeError> {
+        if self.config.attach_payload_hash() {
+            let hash = envelope.payload_hash()?;
+            envelope.set_header("payload_hash", hash);
+        }
+
+        let envelope_meta = envelope.meta().clone();
+        let original_body = envelope.to_vec()?;
+        let http_encoding = self.config.http_encoding();
+
+        // Sign the body before encoding, since the next Relay verifies the decoded envelope.
+        let signature = self
+            .config
+            .credentials()
+            .map(|credentials| credentials.secret_key.sign(&original_body));
+
         match http_encoding {
             HttpEncoding::Identity => {
                 let request = SendEnvelope {
                     envelope_body: original_body,
-                    envelope_meta: envelope_meta.to_owned(),
+                    envelope_meta,
+                    signature,
+                    upstream,
                     scoping,
                     http_encoding,
-                    response_sender: Some(tx),
+                    response_sender,
                     project_key,
+                    client_ip_header: self.config.client_ip_header_name().map(str::to_owned),
                 };
                 UpstreamRelay::from_registry().do_send(SendRequest(request));
             }
             _ => {
                 let request = EncodeEnvelope {
                     envelope_body: original_body,
-                    envelope_meta: envelope_meta.to_owned(),
+                    envelope_meta,
+                    signature,
+                    upstream,
                     scoping,
                     http_encoding,
-                    response_sender: Some(tx),
+                    response_sender,
                     project_key,
                 };
                 self.processor.do_send(request);
             }
         };
 
-        Box::new(
-            rx.map_err(|_| {
-                SendEnvelopeError::UpstreamRequestFailed(UpstreamRequestError::ChannelClosed)
-            })
-            .flatten(),
-        )
+        Ok(())
     }
 }
 
@@ -2411,9 +3850,13 @@ impl Default for EnvelopeManager {
 ///   as an event, they are split off.
 /// - Metrics are directly sent to the `EnvelopeProcessor`, bypassing the manager's queue and going
 ///   straight into metrics aggregation. See [`ProcessMetrics`] for a full description.
+/// - Metric metadata is sent to the `EnvelopeProcessor` the same way and forwarded through the
+///   project cache. See [`ProcessMetricMeta`].
 ///
 /// Queueing can fail if the queue exceeds [`Config::envelope_buffer_size`]. In this case, `Err` is
-/// returned and the envelope is not queued. Otherwise, this message responds with `Ok`. If it
+/// returned and the envelope is not queued, unless [`Config::buffer_overflow_policy`] allows to
+/// evict the oldest queued envelope instead. Envelopes without items are rejected if
+/// [`Config::reject_empty_envelopes`] is enabled. Otherwise, this message responds with `Ok`. If it
 /// contained an event-related item, such as an event payload or an attachment, this contains
 /// `Some(EventId)`.
 pub struct QueueEnvelope {
@@ -2446,9 +3889,7 @@ impl Handler<QueueEnvelope> for EnvelopeManager {
             start_time,
         } = message;
 
-        if self.config.envelope_buffer_size() <= self.active_envelopes {
-            return Err(QueueEnvelopeError::TooManyEnvelopes);
-        }
+        self.accept_envelope(&envelope)?;
 
         let event_id = envelope.event_id();
 
@@ -2460,7 +3901,9 @@ impl Handler<QueueEnvelope> for EnvelopeManager {
         }
 
         if !metric_items.is_empty() {
-            relay_log::trace!("sending metrics into processing queue");
+            if self.log_sampler.sample() {
+                relay_log::trace!("sending metrics into processing queue");
+            }
             self.processor.do_send(ProcessMetrics {
                 items: metric_items,
                 project_key,
@@ -2469,27 +3912,47 @@ impl Handler<QueueEnvelope> for EnvelopeManager {
             });
         }
 
+        // Remove metric metadata from the envelope and pass it directly to the project cache.
+        let meta_items = take_metric_meta(&mut envelope);
+        if !meta_items.is_empty() {
+            if self.log_sampler.sample() {
+                relay_log::trace!("sending metric meta into processing queue");
+            }
+            self.processor.do_send(ProcessMetricMeta {
+                items: meta_items,
+                project_key,
+            });
+        }
+
         // Split the envelope into event-related items and other items. This allows to fast-track:
         //  1. Envelopes with only session items. They only require rate limiting.
         //  2. Event envelope processing can bail out if the event is filtered or rate limited,
         //     since all items depend on this event.
         if let Some(event_envelope) = envelope.split_by(Item::requires_event) {
-            relay_log::trace!("queueing separate envelope for non-event items");
-            self.active_envelopes += 1;
+            if self.log_sampler.sample() {
+                relay_log::trace!("queueing separate envelope for non-event items");
+            }
+            let (envelope_id, evict_rx) = self.register_envelope();
             context.notify(HandleEnvelope {
                 envelope: event_envelope,
                 project_key,
                 start_time,
+                envelope_id,
+                evict_rx,
             });
         }
 
         if !envelope.is_empty() {
-            relay_log::trace!("queueing envelope");
-            self.active_envelopes += 1;
+            if self.log_sampler.sample() {
+                relay_log::trace!("queueing envelope");
+            }
+            let (envelope_id, evict_rx) = self.register_envelope();
             context.notify(HandleEnvelope {
                 envelope,
                 project_key,
                 start_time,
+                envelope_id,
+                evict_rx,
             });
         }
 
@@ -2518,6 +3981,10 @@ struct HandleEnvelope {
     pub envelope: Envelope,
     pub project_key: ProjectKey,
     pub start_time: Instant,
+    /// The position of the envelope in the envelope buffer.
+    pub envelope_id: u64,
+    /// Resolves when the envelope is evicted from the envelope buffer.
+    pub evict_rx: oneshot::Receiver<()>,
 }
 
 impl Message for HandleEnvelope {
@@ -2552,13 +4019,18 @@ impl Handler<HandleEnvelope> for EnvelopeManager {
             envelope,
             project_key,
             start_time,
+            envelope_id,
+            evict_rx,
         } = message;
 
         let sampling_project_key = envelope.trace_context().map(|tc| tc.public_key);
 
         let event_id = envelope.event_id();
+        let item_types = envelope.item_types();
         let envelope_context = Rc::new(RefCell::new(EnvelopeContext::from_envelope(&envelope)));
 
+        let evicted = evict_rx.then(|_| Err::<Envelope, _>(ProcessingError::Evicted));
+
         let future = ProjectCache::from_registry()
             .send_tracked(
                 CheckEnvelope::fetched(project_key, envelope),
@@ -2659,8 +4131,14 @@ impl Handler<HandleEnvelope> for EnvelopeManager {
                     None => Err(ProcessingError::RateLimited),
                 }
             }))
+            .select(evicted)
+            .map(|(envelope, _)| envelope)
+            .map_err(|(error, _)| error)
             .into_actor(self)
             .and_then(clone!(envelope_context, |envelope, slf, _| {
+                // Once sending starts, the envelope can no longer be evicted.
+                slf.queued_envelopes.remove(&envelope_id);
+
                 let scoping = envelope_context.borrow().scoping();
                 slf.send_envelope(project_key, envelope, scoping, start_time)
                     .then(clone!(envelope_context, |result| {
@@ -2727,13 +4205,13 @@ impl Handler<HandleEnvelope> for EnvelopeManager {
                     // Errors are only logged for what we consider an internal discard reason. These
                     // indicate errors in the infrastructure or implementation bugs. In other cases,
                     // we "expect" errors and log them as debug level.
-                    relay_log::error!("error processing envelope: {}", LogError(&error));
+                    log_processing_error(&error, &envelope_context.borrow(), &item_types);
                 } else {
                     relay_log::debug!("dropped envelope: {}", LogError(&error));
                 }
 
-                if let ProcessingError::Timeout = error {
-                    // handle the last failure (the timeout)
+                if let ProcessingError::Timeout | ProcessingError::Evicted = error {
+                    // handle the last failure (the timeout or eviction)
                     if let Some(outcome) = outcome {
                         envelope_context.borrow().send_outcomes(outcome);
                     }
@@ -2741,7 +4219,11 @@ impl Handler<HandleEnvelope> for EnvelopeManager {
             })
             .then(move |x, slf, _| {
                 metric!(timer(RelayTimers::EnvelopeTotalTime) = start_time.elapsed());
-                slf.active_envelopes -= 1;
+                slf.queued_envelopes.remove(&envelope_id);
+                // Evicted envelopes have already released their slot in the buffer.
+                if !slf.evicted_envelopes.remove(&envelope_id) {
+                    slf.active_envelopes -= 1;
+                }
                 fut::result(x)
             })
             .drop_guard("process_envelope");
@@ -2810,6 +4292,65 @@ impl Handler<SendMetrics> for EnvelopeManager {
     }
 }
 
+/// Sends metric metadata to the upstream or Kafka.
+///
+/// Only metadata that is new or changed since the last submission for this project should be sent.
+#[derive(Debug)]
+pub struct SendMetricMeta {
+    /// The metadata entries to forward.
+    pub meta: Vec<MetricMeta>,
+    /// Scoping information for the metadata.
+    pub scoping: Scoping,
+    /// The project of the metadata.
+    pub project_key: ProjectKey,
+}
+
+impl Message for SendMetricMeta {
+    type Result = ();
+}
+
+impl Handler<SendMetricMeta> for EnvelopeManager {
+    type Result = ();
+
+    fn handle(&mut self, message: SendMetricMeta, context: &mut Self::Context) -> Self::Result {
+        let SendMetricMeta {
+            meta,
+            scoping,
+            project_key,
+        } = message;
+
+        let upstream = self.config.upstream_descriptor();
+        let dsn = PartialDsn {
+            scheme: upstream.scheme(),
+            public_key: scoping.project_key,
+            host: upstream.host().to_owned(),
+            port: upstream.port(),
+            path: "".to_owned(),
+            project_id: Some(scoping.project_id),
+        };
+
+        let payload = match serde_json::to_vec(&meta) {
+            Ok(payload) => payload,
+            Err(error) => {
+                relay_log::error!("failed to serialize metric meta: {}", LogError(&error));
+                return;
+            }
+        };
+
+        let mut item = Item::new(ItemType::MetricMeta);
+        item.set_payload(ContentType::Json, payload);
+        let mut envelope = Envelope::from_request(None, RequestMeta::outbound(dsn));
+        envelope.add_item(item);
+
+        self.send_envelope(project_key, envelope, scoping, Instant::now())
+            .map_err(|error| {
+                relay_log::debug!("failed to send metric meta: {:?}", error);
+            })
+            .into_actor(self)
+            .spawn(context);
+    }
+}
+
 /// Sends a client report to the upstream
 pub struct SendClientReports {
     /// The client report to be sent.
@@ -2874,8 +4415,78 @@ impl Handler<GetCapturedEnvelope> for EnvelopeManager {
         message: GetCapturedEnvelope,
         _context: &mut Self::Context,
     ) -> Self::Result {
-        self.captures.get(&message.event_id).cloned()
+        self.captures.get(message.event_id)
+    }
+}
+
+/// Returns `true` if the event is not a transaction and has no timestamp.
+///
+/// Transactions are not checked, since they are always validated for start and end timestamps.
+fn is_missing_timestamp(event: &Annotated<Event>) -> bool {
+    match event.value() {
+        Some(event) => {
+            event.ty.value() != Some(&EventType::Transaction) && event.timestamp.value().is_none()
+        }
+        None => false,
+    }
+}
+
+/// Returns `true` if the event's trace context contains a valid trace id.
+///
+/// Malformed trace ids are removed during deserialization, so only valid ids remain.
+fn has_valid_trace_id(event: &Event) -> bool {
+    let trace = event
+        .contexts
+        .value()
+        .and_then(|contexts| contexts.get("trace"))
+        .and_then(Annotated::value);
+
+    match trace {
+        Some(ContextInner(Context::Trace(trace_context))) => {
+            trace_context.trace_id.value().is_some()
+        }
+        _ => false,
+    }
+}
+
+/// Applies PII rules to a view hierarchy attachment by scrubbing it as JSON.
+///
+/// View hierarchies that cannot be parsed are scrubbed as plain binary attachments instead.
+fn scrub_view_hierarchy(item: &mut Item, compiled: &CompiledPiiConfig) {
+    let payload = item.payload();
+    let mut view_hierarchy = match Annotated::<Value>::from_json_bytes(&payload) {
+        Ok(view_hierarchy) => view_hierarchy,
+        Err(_) => {
+            let filename = item.filename().unwrap_or_default();
+            let mut payload = payload.to_vec();
+            let processor = PiiAttachmentsProcessor::new(compiled);
+            if processor.scrub_attachment(filename, &mut payload) {
+                let content_type = item.content_type().unwrap_or(&ContentType::Json).clone();
+                item.set_payload(content_type, payload);
+            }
+            return;
+        }
+    };
+
+    let mut processor = PiiProcessor::new(compiled);
+    if process_value(&mut view_hierarchy, &mut processor, ProcessingState::root()).is_err() {
+        return;
     }
+
+    match view_hierarchy.payload_to_json() {
+        Ok(json) => item.set_payload(ContentType::Json, json),
+        Err(error) => relay_log::error!("failed to serialize view hierarchy: {}", LogError(&error)),
+    }
+}
+
+/// Truncates a release to at most `max_chars` characters.
+///
+/// Returns the original length in characters if the release was truncated.
+fn truncate_release(release: &mut String, max_chars: usize) -> Option<usize> {
+    let (index, _) = release.char_indices().nth(max_chars)?;
+    let original_length = release.chars().count();
+    release.truncate(index);
+    Some(original_length)
 }
 
 /// Checks if the Event includes unprintable fields.
@@ -2900,11 +4511,38 @@ fn has_unprintable_fields(event: &Annotated<Event>) -> bool {
 #[cfg(test)]
 mod tests {
     use chrono::{DateTime, TimeZone, Utc};
+    use tokio_timer::Timeout;
+
+    use relay_general::pii::PiiConfig;
 
     use crate::extractors::RequestMeta;
 
     use super::*;
 
+    /// Creates an envelope for the test DSN without any items.
+    fn create_test_envelope(event_id: Option<EventId>) -> Envelope {
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        Envelope::from_request(event_id, RequestMeta::new(dsn))
+    }
+
+    /// Creates a [`ProcessEnvelope`] message for the test project.
+    fn create_process_message(envelope: Envelope, project_state: ProjectState) -> ProcessEnvelope {
+        ProcessEnvelope {
+            envelope,
+            project_state: Arc::new(project_state),
+            start_time: Instant::now(),
+            scoping: Scoping {
+                project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                organization_id: 1,
+                project_id: ProjectId::new(1),
+                key_id: None,
+            },
+        }
+    }
+
     fn create_breadcrumbs_item(breadcrumbs: &[(Option<DateTime<Utc>>, &str)]) -> Item {
         let mut data = Vec::new();
 
@@ -3092,67 +4730,652 @@ mod tests {
     }
 
     #[test]
-    fn test_client_report_removal() {
-        relay_test::setup();
-
+    fn test_scrub_event_test_pii_config() {
         let config = Config::from_json_value(serde_json::json!({
-            "outcomes": {
-                "emit_outcomes": true,
-                "emit_client_outcomes": true
+            "relay": {
+                "mode": "capture",
+                "test_pii_config": {
+                    "applications": {"$string": ["@email:replace"]}
+                }
             }
         }))
         .unwrap();
 
         let processor = EnvelopeProcessor::new(Arc::new(config));
+        let event_id = EventId::new();
 
         let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
             .parse()
             .unwrap();
 
         let request_meta = RequestMeta::new(dsn);
-        let mut envelope = Envelope::from_request(None, request_meta);
+        let mut envelope = Envelope::from_request(Some(event_id), request_meta);
 
         envelope.add_item({
-            let mut item = Item::new(ItemType::ClientReport);
+            let mut item = Item::new(ItemType::Event);
             item.set_payload(
                 ContentType::Json,
-                r###"
-                    {
-                        "discarded_events": [
-                            ["queue_full", "error", 42]
-                        ]
-                    }
-                "###,
+                r###"{"extra": {"contact": "foo@example.com"}}"###,
             );
             item
         });
 
-        let envelope_response = relay_test::with_system(move || {
-            processor
-                .process(ProcessEnvelope {
-                    envelope,
-                    project_state: Arc::new(ProjectState::allowed()),
-                    start_time: Instant::now(),
-                    scoping: Scoping {
-                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
-                        organization_id: 1,
-                        project_id: ProjectId::new(1),
-                        key_id: None,
-                    },
-                })
-                .unwrap()
-        });
+        let envelope_response = processor
+            .process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(ProjectState::allowed()),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+            .unwrap();
 
-        assert!(envelope_response.envelope.is_none());
+        let new_envelope = envelope_response.envelope.unwrap();
+        let event_item = new_envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&event_item.payload()).unwrap();
+        let extra = &event.value().unwrap().extra;
+        let contact = extra.value().unwrap().get("contact").unwrap().value();
+
+        assert_eq!(contact.and_then(|v| v.0.as_str()), Some("[email]"));
     }
 
     #[test]
-    fn test_client_report_forwarding() {
-        relay_test::setup();
-
+    fn test_scrubbing_exempt_keys() {
         let config = Config::from_json_value(serde_json::json!({
-            "outcomes": {
-                "emit_outcomes": false,
+            "relay": {
+                "scrubbing_exempt_keys": ["a94ae32be2584e0bbd7a4cbb95971fee"],
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.pii_config = Some(
+            PiiConfig::from_json(r###"{"applications": {"$string": ["@email:replace"]}}"###)
+                .unwrap(),
+        );
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(
+                ContentType::Json,
+                r###"{"extra": {"contact": "foo@example.com"}}"###,
+            );
+            item
+        });
+
+        let envelope_response = processor
+            .process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(project_state),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+            .unwrap();
+
+        let new_envelope = envelope_response.envelope.unwrap();
+        let event_item = new_envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&event_item.payload()).unwrap();
+        let extra = &event.value().unwrap().extra;
+        let contact = extra.value().unwrap().get("contact").unwrap().value();
+
+        assert_eq!(contact.and_then(|v| v.0.as_str()), Some("foo@example.com"));
+    }
+
+    #[test]
+    fn test_count_pii_remarks() {
+        let mut event = Annotated::<Event>::from_json(
+            r###"{
+                "extra": {
+                    "contact": "foo@example.com",
+                    "reply_to": "bar@example.com",
+                    "subject": "hello"
+                }
+            }"###,
+        )
+        .unwrap();
+
+        let config =
+            PiiConfig::from_json(r###"{"applications": {"$string": ["@email:replace"]}}"###)
+                .unwrap();
+
+        let before = count_pii_remarks(&mut event).unwrap();
+        assert!(before.is_empty());
+
+        let compiled = config.compiled();
+        let mut processor = PiiProcessor::new(&compiled);
+        process_value(&mut event, &mut processor, ProcessingState::root()).unwrap();
+
+        let after = count_pii_remarks(&mut event).unwrap();
+        assert_eq!(after.len(), 1);
+        assert_eq!(after.get("email"), Some(&2));
+    }
+
+    #[test]
+    fn test_pii_rule_hits_metric() {
+        let config = Config::from_json_value(serde_json::json!({
+            "metrics": {
+                "pii_rule_hits": true,
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.pii_config = Some(
+            PiiConfig::from_json(r###"{"applications": {"$string": ["@email:replace"]}}"###)
+                .unwrap(),
+        );
+
+        let mut envelope = create_test_envelope(Some(EventId::new()));
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(
+                ContentType::Json,
+                r###"{"extra": {"contact": "foo@example.com", "reply_to": "bar@example.com"}}"###,
+            );
+            item
+        });
+
+        let captures = relay_statsd::with_capturing_test_client(|| {
+            processor
+                .process(create_process_message(envelope, project_state))
+                .unwrap();
+        });
+
+        let hits: Vec<_> = captures
+            .iter()
+            .filter(|metric| metric.starts_with("pii.rule_hits:"))
+            .collect();
+        assert_eq!(hits, vec!["pii.rule_hits:2|c|#rule_type:email"]);
+    }
+
+    #[test]
+    fn test_scrub_view_hierarchy() {
+        let config =
+            PiiConfig::from_json(r###"{"applications": {"$string": ["@email:replace"]}}"###)
+                .unwrap();
+
+        let mut item = Item::new(ItemType::Attachment);
+        item.set_attachment_type(AttachmentType::ViewHierarchy);
+        item.set_payload(
+            ContentType::Json,
+            r#"{"rendering_system":"UIKIT","windows":[{"text":"foo@example.com"}]}"#,
+        );
+
+        scrub_view_hierarchy(&mut item, &config.compiled());
+        assert_eq!(
+            &*item.payload(),
+            br#"{"rendering_system":"UIKIT","windows":[{"text":"[email]"}]}"#
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "processing")]
+    fn test_validate_view_hierarchy() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "processing": {
+                "enabled": true,
+                "kafka_config": [],
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        let payloads = [
+            r#"{"rendering_system": "UIKIT", "windows": []}"#,
+            r#"{"windows": []}"#,
+            "not json",
+        ];
+
+        for payload in &payloads {
+            envelope.add_item({
+                let mut item = Item::new(ItemType::Attachment);
+                item.set_attachment_type(AttachmentType::ViewHierarchy);
+                item.set_payload(ContentType::Json, *payload);
+                item
+            });
+        }
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        assert_eq!(envelope.len(), 1);
+
+        let item = envelope.items().next().unwrap();
+        assert_eq!(&*item.payload(), payloads[0].as_bytes());
+    }
+
+    fn scrub_with_order(datascrubbing_before_pii_config: bool) -> Option<String> {
+        let processor = EnvelopeProcessor::new(Arc::new(Config::default()));
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.datascrubbing_before_pii_config = datascrubbing_before_pii_config;
+        project_state.config.datascrubbing_settings = serde_json::from_value(serde_json::json!({
+            "scrubData": true,
+            "sensitiveFields": ["contact"]
+        }))
+        .unwrap();
+        project_state.config.pii_config = Some(
+            PiiConfig::from_json(
+                r###"{
+                    "rules": {
+                        "hide_filtered": {
+                            "type": "pattern",
+                            "pattern": "\\[Filtered\\]",
+                            "redaction": {"method": "replace", "text": "[hidden]"}
+                        }
+                    },
+                    "applications": {"$string": ["hide_filtered"]}
+                }"###,
+            )
+            .unwrap(),
+        );
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(ContentType::Json, r###"{"extra": {"contact": "secret"}}"###);
+            item
+        });
+
+        let envelope_response = processor
+            .process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(project_state),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+            .unwrap();
+
+        let new_envelope = envelope_response.envelope.unwrap();
+        let event_item = new_envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&event_item.payload()).unwrap();
+        let extra = event.value().unwrap().extra.value().unwrap();
+        let contact = extra.get("contact").unwrap().value();
+
+        contact.and_then(|v| v.0.as_str()).map(str::to_owned)
+    }
+
+    #[test]
+    fn test_scrub_event_pii_config_first() {
+        // The PII config runs first and does not match, then data scrubbing filters the value.
+        assert_eq!(scrub_with_order(false).as_deref(), Some("[Filtered]"));
+    }
+
+    #[test]
+    fn test_scrub_event_datascrubbing_first() {
+        // Data scrubbing filters the value, which then matches the rule in the PII config.
+        assert_eq!(scrub_with_order(true).as_deref(), Some("[hidden]"));
+    }
+
+    #[test]
+    fn test_scrub_server_name() {
+        let processor = EnvelopeProcessor::new(Arc::new(Config::default()));
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.scrub_server_name = true;
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(
+                ContentType::Json,
+                r###"{"message": "hello", "server_name": "db-1.internal"}"###,
+            );
+            item
+        });
+
+        let envelope_response = processor
+            .process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(project_state),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+            .unwrap();
+
+        let new_envelope = envelope_response.envelope.unwrap();
+        let event_item = new_envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&event_item.payload()).unwrap();
+
+        assert!(event.value().unwrap().server_name.value().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "processing")]
+    fn test_scrub_server_name_processing() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "processing": {
+                "enabled": true,
+                "kafka_config": [],
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.scrub_server_name = true;
+
+        let mut envelope = create_test_envelope(Some(EventId::new()));
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(
+                ContentType::Json,
+                r###"{"message": "hello", "server_name": "db-1.internal"}"###,
+            );
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(create_process_message(envelope, project_state))
+                .unwrap()
+        });
+
+        let new_envelope = envelope_response.envelope.unwrap();
+        let event_item = new_envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&event_item.payload()).unwrap();
+        let event = event.value().unwrap();
+
+        assert!(event.server_name.value().is_none());
+        assert!(event.tags.value().unwrap().get("server_name").is_none());
+    }
+
+    #[test]
+    fn test_scrub_frame_paths() {
+        let processor = EnvelopeProcessor::new(Arc::new(Config::default()));
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.scrub_frame_paths = true;
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(
+                ContentType::Json,
+                r###"{
+                    "message": "hello",
+                    "stacktrace": {
+                        "frames": [
+                            {
+                                "filename": "app.py",
+                                "abs_path": "/home/jane/project/app.py"
+                            },
+                            {
+                                "filename": "src\\Program.cs",
+                                "abs_path": "C:\\Users\\jane\\src\\Program.cs"
+                            },
+                            {
+                                "filename": "/home/jane/lib/util.rb"
+                            },
+                            {
+                                "filename": "app.js",
+                                "abs_path": "https://cdn.example.com/app.js"
+                            }
+                        ]
+                    }
+                }"###,
+            );
+            item
+        });
+
+        let envelope_response = processor
+            .process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(project_state),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+            .unwrap();
+
+        let new_envelope = envelope_response.envelope.unwrap();
+        let event_item = new_envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&event_item.payload()).unwrap();
+
+        let stacktrace = event.value().unwrap().stacktrace.value().unwrap();
+        let frames = stacktrace.frames.value().unwrap();
+        let paths: Vec<_> = frames
+            .iter()
+            .map(|frame| {
+                let frame = frame.value().unwrap();
+                (
+                    frame.filename.value().map(NativeImagePath::as_str),
+                    frame.abs_path.value().map(NativeImagePath::as_str),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            paths,
+            vec![
+                (Some("app.py"), Some("[path]/app.py")),
+                (Some("src\\Program.cs"), Some("[path]\\Program.cs")),
+                (Some("[path]/util.rb"), None),
+                (Some("app.js"), Some("https://cdn.example.com/app.js")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_max_breadcrumbs() {
+        let config = Config::from_json_value(serde_json::json!({
+            "limits": {"max_breadcrumbs": 2}
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(
+                ContentType::Json,
+                r###"{"message": "hello", "breadcrumbs": [
+                    {"message": "1"}, {"message": "2"}, {"message": "3"}, {"message": "4"}
+                ]}"###,
+            );
+            item
+        });
+
+        let envelope_response = processor
+            .process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(ProjectState::allowed()),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+            .unwrap();
+
+        let new_envelope = envelope_response.envelope.unwrap();
+        let event_item = new_envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&event_item.payload()).unwrap();
+
+        let values = &event.value().unwrap().breadcrumbs.value().unwrap().values;
+        let messages: Vec<_> = values
+            .value()
+            .unwrap()
+            .iter()
+            .map(|b| b.value().unwrap().message.as_str().unwrap())
+            .collect();
+
+        assert_eq!(messages, vec!["3", "4"]);
+        assert_eq!(values.meta().original_length(), Some(4));
+    }
+
+    #[test]
+    fn test_client_report_removal() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {
+                "emit_outcomes": true,
+                "emit_client_outcomes": true
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::ClientReport);
+            item.set_payload(
+                ContentType::Json,
+                r###"
+                    {
+                        "discarded_events": [
+                            ["queue_full", "error", 42]
+                        ]
+                    }
+                "###,
+            );
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        assert!(envelope_response.envelope.is_none());
+    }
+
+    #[test]
+    fn test_client_outcome_fields() {
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {
+                "emit_outcomes": true,
+                "emit_client_outcomes": true,
+                "client_outcome_fields": ["discarded_events"]
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+        assert!(processor.emits_client_outcome(ClientReportField::ClientDiscard));
+        assert!(!processor.emits_client_outcome(ClientReportField::RateLimited));
+        assert!(!processor.emits_client_outcome(ClientReportField::Filtered));
+
+        let processor = EnvelopeProcessor::new(Arc::new(Config::default()));
+        assert!(processor.emits_client_outcome(ClientReportField::RateLimited));
+    }
+
+    #[test]
+    fn test_client_report_forwarding() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {
+                "emit_outcomes": false,
                 // a relay need to emit outcomes at all to not process.
                 "emit_client_outcomes": true
             }
@@ -3169,16 +5392,2052 @@ mod tests {
         let mut envelope = Envelope::from_request(None, request_meta);
 
         envelope.add_item({
-            let mut item = Item::new(ItemType::ClientReport);
+            let mut item = Item::new(ItemType::ClientReport);
+            item.set_payload(
+                ContentType::Json,
+                r###"
+                    {
+                        "discarded_events": [
+                            ["queue_full", "error", 42]
+                        ]
+                    }
+                "###,
+            );
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        let item = envelope.items().next().unwrap();
+        assert_eq!(item.ty(), &ItemType::ClientReport);
+    }
+
+    #[test]
+    #[cfg(feature = "processing")]
+    fn test_client_report_removal_in_processing() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {
+                "emit_outcomes": true,
+                "emit_client_outcomes": false,
+            },
+            "processing": {
+                "enabled": true,
+                "kafka_config": [],
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::ClientReport);
+            item.set_payload(
+                ContentType::Json,
+                r###"
+                    {
+                        "discarded_events": [
+                            ["queue_full", "error", 42]
+                        ]
+                    }
+                "###,
+            );
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        assert!(envelope_response.envelope.is_none());
+    }
+
+    #[test]
+    fn test_client_report_forwarding_raw() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {
+                "emit_outcomes": true,
+                "emit_client_outcomes": true,
+                "forward_client_reports_raw": true,
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        let payload = r###"
+            {
+                "discarded_events": [
+                    ["queue_full", "error", 42]
+                ]
+            }
+        "###;
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::ClientReport);
+            item.set_payload(ContentType::Json, payload);
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        assert_eq!(envelope.len(), 1);
+        let item = envelope.items().next().unwrap();
+        assert_eq!(item.ty(), &ItemType::ClientReport);
+        assert_eq!(item.payload(), payload.as_bytes());
+    }
+
+    fn process_with_sample_rates(config: Config) -> Envelope {
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(ContentType::Json, r#"{"message": "hello world"}"#);
+            item.set_sample_rates(Value::Array(vec![Annotated::new(Value::F64(0.5))]));
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        envelope_response.envelope.unwrap()
+    }
+
+    #[test]
+    fn test_sample_rates_forwarded() {
+        relay_test::setup();
+
+        let mut envelope = process_with_sample_rates(Config::default());
+        let item = envelope.items_mut().next().unwrap();
+        assert!(item.take_sample_rates().is_some());
+    }
+
+    #[test]
+    fn test_strip_sample_rates_on_forward() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {
+                "strip_sample_rates_on_forward": true,
+            }
+        }))
+        .unwrap();
+
+        let mut envelope = process_with_sample_rates(config);
+        let item = envelope.items_mut().next().unwrap();
+        assert_eq!(item.ty(), &ItemType::Event);
+        assert!(item.take_sample_rates().is_none());
+    }
+
+    fn process_with_sampling(config: Config) -> Result<ProcessEnvelopeResponse, ProcessingError> {
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(
+                ContentType::Json,
+                r#"{"event_id": "52df9022835246eeb317dbd739ccd059", "message": "hello world"}"#,
+            );
+            item
+        });
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.dynamic_sampling = Some(
+            serde_json::from_value(serde_json::json!({
+                "rules": [{
+                    "condition": {"op": "and", "inner": []},
+                    "sampleRate": 0.0,
+                    "type": "error",
+                    "id": 1
+                }]
+            }))
+            .unwrap(),
+        );
+
+        relay_test::with_system(move || {
+            processor.process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(project_state),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+        })
+    }
+
+    #[test]
+    fn test_sample_event() {
+        relay_test::setup();
+
+        let result = process_with_sampling(Config::default());
+        assert!(matches!(result, Err(ProcessingError::EventSampled(_))));
+    }
+
+    #[test]
+    fn test_force_keep_projects() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {
+                "force_keep_projects": ["a94ae32be2584e0bbd7a4cbb95971fee"],
+            }
+        }))
+        .unwrap();
+
+        let envelope = process_with_sampling(config).unwrap().envelope.unwrap();
+        let item = envelope.items().next().unwrap();
+        assert_eq!(item.ty(), &ItemType::Event);
+    }
+
+    #[test]
+    fn test_disabled_item_types() {
+        relay_test::setup();
+
+        let processor = EnvelopeProcessor::new(Arc::new(Config::default()));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Profile);
+            item.set_payload(ContentType::Json, r#"{"platform": "cocoa"}"#);
+            item
+        });
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Attachment);
+            item.set_payload(ContentType::OctetStream, "data");
+            item
+        });
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.features.insert(Feature::Profiling);
+        project_state
+            .config
+            .disabled_item_types
+            .insert(ItemType::Profile);
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(project_state),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        assert_eq!(envelope.len(), 1);
+        assert_eq!(envelope.items().next().unwrap().ty(), &ItemType::Attachment);
+    }
+
+    #[test]
+    fn test_capture_store_max_captures() {
+        let mut store = CaptureStore::new(2, Duration::from_secs(3600));
+
+        let first = EventId::new();
+        let second = EventId::new();
+        let third = EventId::new();
+
+        store.insert(first, Err("first".to_owned()));
+        store.insert(second, Err("second".to_owned()));
+
+        // Accessing the first capture makes the second one the least recently used.
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(store.get(first).is_some());
+        store.insert(third, Err("third".to_owned()));
+
+        assert!(store.get(first).is_some());
+        assert!(store.get(second).is_none());
+        assert!(store.get(third).is_some());
+    }
+
+    #[test]
+    fn test_capture_store_ttl() {
+        let mut store = CaptureStore::new(2, Duration::from_secs(0));
+
+        let event_id = EventId::new();
+        store.insert(event_id, Err("expired".to_owned()));
+        assert!(store.get(event_id).is_none());
+    }
+
+    #[cfg(feature = "processing")]
+    fn process_invalid_transaction(
+        keep_items: bool,
+    ) -> Result<ProcessEnvelopeResponse, ProcessingError> {
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {
+                "keep_items_of_invalid_events": keep_items,
+            },
+            "processing": {
+                "enabled": true,
+                "kafka_config": [],
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        // Transactions without a start timestamp fail normalization.
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Transaction);
+            item.set_payload(
+                ContentType::Json,
+                r#"{"type": "transaction", "timestamp": 1619420402.0, "transaction": "/"}"#,
+            );
+            item
+        });
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Attachment);
+            item.set_payload(ContentType::OctetStream, "attachment content");
+            item.set_attachment_type(AttachmentType::Attachment);
+            item
+        });
+
+        relay_test::with_system(move || {
+            processor.process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(ProjectState::allowed()),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+        })
+    }
+
+    fn process_transaction_with_trace_id(
+        trace_id: &str,
+    ) -> Result<ProcessEnvelopeResponse, ProcessingError> {
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"validate_trace_ids": true}
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Transaction);
+            item.set_payload(
+                ContentType::Json,
+                serde_json::json!({
+                    "type": "transaction",
+                    "transaction": "/",
+                    "start_timestamp": 1619420400.0,
+                    "timestamp": 1619420402.0,
+                    "contexts": {
+                        "trace": {
+                            "trace_id": trace_id,
+                            "span_id": "fa90fdead5f74052"
+                        }
+                    }
+                })
+                .to_string(),
+            );
+            item
+        });
+
+        relay_test::with_system(move || {
+            processor.process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(ProjectState::allowed()),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+        })
+    }
+
+    #[test]
+    fn test_malformed_trace_id() {
+        relay_test::setup();
+
+        let result = process_transaction_with_trace_id("not-a-trace-id");
+        let outcome = result.err().and_then(|error| error.to_outcome());
+        assert!(matches!(
+            outcome,
+            Some(Outcome::Invalid(DiscardReason::InvalidTransaction))
+        ));
+
+        let result = process_transaction_with_trace_id("4c79f60c11214eb38604f4ae0781bfb2");
+        assert!(result.unwrap().envelope.is_some());
+    }
+
+    fn process_event_with_payload(
+        payload: serde_json::Value,
+    ) -> Result<ProcessEnvelopeResponse, ProcessingError> {
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"require_event_timestamp": true}
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(ContentType::Json, payload.to_string());
+            item
+        });
+
+        relay_test::with_system(move || {
+            processor.process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(ProjectState::allowed()),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+        })
+    }
+
+    #[test]
+    fn test_require_event_timestamp() {
+        relay_test::setup();
+
+        let result = process_event_with_payload(serde_json::json!({"message": "hello"}));
+        let outcome = result.err().and_then(|error| error.to_outcome());
+        assert!(matches!(
+            outcome,
+            Some(Outcome::Invalid(DiscardReason::Timestamp))
+        ));
+
+        let result = process_event_with_payload(serde_json::json!({
+            "message": "hello",
+            "timestamp": 1619420400.0
+        }));
+        assert!(result.unwrap().envelope.is_some());
+    }
+
+    #[test]
+    fn test_require_event_timestamp_security_report() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"require_event_timestamp": true}
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut envelope = create_test_envelope(Some(EventId::new()));
+
+        // Events created by Relay, such as security reports, have no timestamp until normalization.
+        envelope.add_item({
+            let mut item = Item::new(ItemType::RawSecurity);
+            item.set_payload(
+                ContentType::Json,
+                r#"{
+                    "csp-report": {
+                        "document-uri": "https://example.com/",
+                        "violated-directive": "script-src"
+                    }
+                }"#,
+            );
+            item
+        });
+
+        let result = relay_test::with_system(move || {
+            processor.process(create_process_message(envelope, ProjectState::allowed()))
+        });
+
+        assert!(result.unwrap().envelope.is_some());
+    }
+
+    fn process_event_with_soft_limit(
+        sample_rate: f64,
+    ) -> Result<ProcessEnvelopeResponse, ProcessingError> {
+        let config = Config::from_json_value(serde_json::json!({
+            "limits": {
+                "soft_max_event_size": 10,
+                "soft_max_event_sample_rate": sample_rate
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(ContentType::Json, r#"{"message": "hello world"}"#);
+            item
+        });
+
+        relay_test::with_system(move || {
+            processor.process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(ProjectState::allowed()),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+        })
+    }
+
+    #[test]
+    fn test_soft_max_event_size() {
+        relay_test::setup();
+
+        let result = process_event_with_soft_limit(0.0);
+        let outcome = result.err().and_then(|error| error.to_outcome());
+        assert!(matches!(
+            outcome,
+            Some(Outcome::Invalid(DiscardReason::SoftSizeLimit))
+        ));
+
+        let result = process_event_with_soft_limit(1.0);
+        assert!(result.unwrap().envelope.is_some());
+
+        let accepted = (0..50)
+            .filter(|_| process_event_with_soft_limit(0.5).is_ok())
+            .count();
+        assert!(accepted > 0 && accepted < 50, "accepted {} of 50", accepted);
+    }
+
+    #[test]
+    fn test_dedupe_attachments_by_filename() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"dedupe_attachments_by_filename": true}
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        for payload in &["first", "second"] {
+            envelope.add_item({
+                let mut item = Item::new(ItemType::Attachment);
+                item.set_filename("minidump.dmp");
+                item.set_payload(ContentType::OctetStream, *payload);
+                item
+            });
+        }
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Attachment);
+            item.set_filename("log.txt");
+            item.set_payload(ContentType::Text, "log");
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        assert_eq!(envelope.len(), 2);
+
+        let items: Vec<_> = envelope.items().collect();
+        assert_eq!(items[0].filename(), Some("minidump.dmp"));
+        assert_eq!(&*items[0].payload(), b"first");
+        assert_eq!(items[1].filename(), Some("log.txt"));
+    }
+
+    #[test]
+    fn test_min_sdk_versions() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"min_sdk_versions": {"sentry.python": "1.5.0"}}
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let process_with_client = |client: &str| {
+            let request_meta: RequestMeta = serde_json::from_value(serde_json::json!({
+                "dsn": "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42",
+                "client": client,
+            }))
+            .unwrap();
+
+            let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+            envelope.add_item({
+                let mut item = Item::new(ItemType::Attachment);
+                item.set_filename("log.txt");
+                item.set_payload(ContentType::Text, "log");
+                item
+            });
+
+            relay_test::with_system(|| {
+                processor.process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+            })
+        };
+
+        assert!(matches!(
+            process_with_client("sentry.python/1.4.2"),
+            Err(ProcessingError::EventFiltered(
+                FilterStatKey::ReleaseVersion
+            ))
+        ));
+        assert!(process_with_client("sentry.python/1.5.0").is_ok());
+        assert!(process_with_client("sentry.python/unknown").is_ok());
+        assert!(process_with_client("sentry.javascript/1.0.0").is_ok());
+    }
+
+    #[test]
+    fn test_max_total_attachment_bytes() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "limits": {"max_total_attachment_bytes": 10}
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        for (filename, payload) in &[
+            ("a.txt", "aaaa"),
+            ("b.txt", "bbbbbbbb"),
+            ("c.txt", "cccccc"),
+        ] {
+            envelope.add_item({
+                let mut item = Item::new(ItemType::Attachment);
+                item.set_filename(*filename);
+                item.set_payload(ContentType::Text, *payload);
+                item
+            });
+        }
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        let filenames: Vec<_> = envelope.items().map(Item::filename).collect();
+        assert_eq!(filenames, [Some("a.txt"), Some("c.txt")]);
+    }
+
+    #[test]
+    fn test_max_total_attachment_bytes_keeps_crash_reports() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "limits": {"max_total_attachment_bytes": 20}
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut envelope = create_test_envelope(Some(EventId::new()));
+
+        for (filename, attachment_type, payload) in &[
+            ("crash.dmp", AttachmentType::Minidump, "mmmmmmmmmmmmmmmm"),
+            ("a.txt", AttachmentType::Attachment, "aaaaaaaa"),
+            ("logs.txt", AttachmentType::UnrealLogs, "llll"),
+        ] {
+            envelope.add_item({
+                let mut item = Item::new(ItemType::Attachment);
+                item.set_filename(*filename);
+                item.set_attachment_type(*attachment_type);
+                item.set_payload(ContentType::OctetStream, *payload);
+                item
+            });
+        }
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(create_process_message(envelope, ProjectState::allowed()))
+                .unwrap()
+        });
+
+        // The minidump is the largest attachment, but it creates the event.
+        let envelope = envelope_response.envelope.unwrap();
+        let filenames: Vec<_> = envelope.items().map(Item::filename).collect();
+        assert_eq!(filenames, [Some("crash.dmp"), Some("logs.txt")]);
+    }
+
+    #[test]
+    #[cfg(feature = "processing")]
+    fn test_process_php_profile() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "processing": {
+                "enabled": true,
+                "kafka_config": [],
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.features.insert(Feature::Profiling);
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Profile);
+            item.set_payload(
+                ContentType::Json,
+                &include_bytes!("../../tests/fixtures/profiles/php.json")[..],
+            );
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(project_state),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        assert_eq!(envelope.len(), 1);
+        assert_eq!(envelope.items().next().unwrap().ty(), &ItemType::Profile);
+    }
+
+    /// Forwards all received outcomes into a channel.
+    struct TestOutcomeProducer(futures::sync::mpsc::UnboundedSender<TrackOutcome>);
+
+    impl Actor for TestOutcomeProducer {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<TrackOutcome> for TestOutcomeProducer {
+        type Result = Result<(), crate::actors::outcome::OutcomeError>;
+
+        fn handle(&mut self, msg: TrackOutcome, _ctx: &mut Self::Context) -> Self::Result {
+            self.0.unbounded_send(msg).ok();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_profile_feature_disabled_outcome() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {
+                "aggregator": {
+                    "flush_interval": 0,
+                }
+            }
+        }))
+        .unwrap();
+
+        let config = Arc::new(config);
+        let processor = EnvelopeProcessor::new(config.clone());
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Profile);
+            item.set_payload(
+                ContentType::Json,
+                &include_bytes!("../../tests/fixtures/profiles/php.json")[..],
+            );
+            item
+        });
+
+        let (envelope_response, outcomes) = relay_test::block_fn(move || {
+            let (tx, rx) = futures::sync::mpsc::unbounded();
+            let producer = TestOutcomeProducer(tx).start();
+            let aggregator = OutcomeAggregator::new(&config, producer.recipient()).start();
+            System::current().registry().set(aggregator);
+
+            let response = processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap();
+
+            Timeout::new(rx.take(1).collect(), Duration::from_secs(1))
+                .map(move |outcomes| (response, outcomes))
+                .map_err(|_| panic!("no outcome was emitted for the dropped profile"))
+        })
+        .unwrap();
+
+        assert!(envelope_response.envelope.is_none());
+
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].category, DataCategory::Profile);
+        assert_eq!(outcomes[0].quantity, 1);
+        assert!(matches!(
+            outcomes[0].outcome,
+            Outcome::Invalid(DiscardReason::FeatureDisabled)
+        ));
+    }
+
+    #[test]
+    fn test_replay_feature_disabled_outcome() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {
+                "aggregator": {
+                    "flush_interval": 0,
+                }
+            }
+        }))
+        .unwrap();
+
+        let config = Arc::new(config);
+        let processor = EnvelopeProcessor::new(config.clone());
+
+        let mut envelope = create_test_envelope(Some(EventId::new()));
+
+        for _ in 0..2 {
+            envelope.add_item({
+                let mut item = Item::new(ItemType::ReplayRecording);
+                item.set_payload(ContentType::OctetStream, &b"recording"[..]);
+                item
+            });
+        }
+
+        let (envelope_response, outcomes) = relay_test::block_fn(move || {
+            let (tx, rx) = futures::sync::mpsc::unbounded();
+            let producer = TestOutcomeProducer(tx).start();
+            let aggregator = OutcomeAggregator::new(&config, producer.recipient()).start();
+            System::current().registry().set(aggregator);
+
+            let response = processor
+                .process(create_process_message(envelope, ProjectState::allowed()))
+                .unwrap();
+
+            Timeout::new(rx.take(2).collect(), Duration::from_secs(1))
+                .map(move |outcomes| (response, outcomes))
+                .map_err(|_| panic!("no outcomes were emitted for the dropped replays"))
+        })
+        .unwrap();
+
+        assert!(envelope_response.envelope.is_none());
+
+        assert_eq!(outcomes.len(), 2);
+        for outcome in &outcomes {
+            assert_eq!(outcome.category, DataCategory::Replay);
+            assert_eq!(outcome.quantity, 1);
+            assert!(matches!(
+                outcome.outcome,
+                Outcome::Invalid(DiscardReason::FeatureDisabled)
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "processing")]
+    fn test_os_name_tag_from_user_agent() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "processing": {
+                "enabled": true,
+                "kafka_config": [],
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.features.insert(Feature::ErrorMetrics);
+        project_state.config.error_metrics = Some(ErrorBoundary::Ok(
+            serde_json::from_value(serde_json::json!({
+                "extractCustomTags": ["os.name"],
+            }))
+            .unwrap(),
+        ));
+
+        let mut envelope = create_test_envelope(Some(EventId::new()));
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(
+                ContentType::Json,
+                r#"{
+                    "message": "hello",
+                    "platform": "javascript",
+                    "request": {
+                        "headers": [
+                            ["User-Agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/96.0.4664.110 Safari/537.36"]
+                        ]
+                    }
+                }"#,
+            );
+            item
+        });
+
+        // The OS context is only inferred from the user agent during normalization.
+        let metrics = relay_test::with_system(move || {
+            let mut state = processor
+                .prepare_state(create_process_message(envelope, project_state))
+                .unwrap();
+            processor.process_state(&mut state).unwrap();
+            state.extracted_metrics
+        });
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].tags["os.name"], "Windows");
+    }
+
+    #[test]
+    #[cfg(feature = "processing")]
+    fn test_runtime_name_tag_from_raw_description() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "processing": {
+                "enabled": true,
+                "kafka_config": [],
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.transaction_metrics = Some(ErrorBoundary::Ok(
+            serde_json::from_value(serde_json::json!({
+                "extractMetrics": ["d:transactions/duration@millisecond"],
+                "extractCustomTags": ["runtime.name"],
+            }))
+            .unwrap(),
+        ));
+
+        let mut envelope = create_test_envelope(Some(EventId::new()));
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Transaction);
+            item.set_payload(
+                ContentType::Json,
+                r#"{
+                    "type": "transaction",
+                    "transaction": "/api/users",
+                    "timestamp": "2021-04-26T08:00:00+0100",
+                    "start_timestamp": "2021-04-26T07:59:01+0100",
+                    "contexts": {
+                        "runtime": {
+                            "raw_description": ".NET Framework 4.8.4250.0"
+                        }
+                    }
+                }"#,
+            );
+            item
+        });
+
+        // The runtime name is only derived from the raw description during normalization.
+        let metrics = relay_test::with_system(move || {
+            let mut state = processor
+                .prepare_state(create_process_message(envelope, project_state))
+                .unwrap();
+            processor.process_state(&mut state).unwrap();
+            state.extracted_metrics
+        });
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].tags["runtime.name"], ".NET Framework");
+    }
+
+    #[test]
+    #[cfg(feature = "processing")]
+    fn test_drop_unnamed_transaction() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "processing": {
+                "enabled": true,
+                "kafka_config": [],
+            }
+        }))
+        .unwrap();
+
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.transaction_metrics = Some(ErrorBoundary::Ok(
+            serde_json::from_value(serde_json::json!({
+                "extractMetrics": ["d:transactions/duration@millisecond"],
+                "unnamedTransactions": "drop",
+            }))
+            .unwrap(),
+        ));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Transaction);
+            item.set_payload(
+                ContentType::Json,
+                r#"{
+                    "type": "transaction",
+                    "start_timestamp": 1619420400.0,
+                    "timestamp": 1619420402.0
+                }"#,
+            );
+            item
+        });
+
+        let result = relay_test::with_system(move || {
+            processor.process(ProcessEnvelope {
+                envelope,
+                project_state: Arc::new(project_state),
+                start_time: Instant::now(),
+                scoping: Scoping {
+                    project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                    organization_id: 1,
+                    project_id: ProjectId::new(1),
+                    key_id: None,
+                },
+            })
+        });
+
+        assert!(matches!(result, Err(ProcessingError::InvalidTransaction)));
+    }
+
+    #[test]
+    #[cfg(feature = "processing")]
+    fn test_invalid_transaction_fails_envelope() {
+        relay_test::setup();
+
+        let result = process_invalid_transaction(false);
+        assert!(matches!(result, Err(ProcessingError::InvalidTransaction)));
+    }
+
+    #[test]
+    #[cfg(feature = "processing")]
+    fn test_invalid_transaction_keeps_items() {
+        relay_test::setup();
+
+        let envelope = process_invalid_transaction(true).unwrap().envelope.unwrap();
+        assert_eq!(envelope.len(), 1);
+
+        let item = envelope.items().next().unwrap();
+        assert_eq!(item.ty(), &ItemType::Attachment);
+    }
+
+    #[test]
+    fn test_take_metric_meta() {
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::MetricMeta);
+            item.set_payload(
+                ContentType::Json,
+                r#"[{"name": "d:custom/response_time@millisecond", "unit": "millisecond"}]"#,
+            );
+            item
+        });
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(ContentType::Json, "{}");
+            item
+        });
+
+        let items = take_metric_meta(&mut envelope);
+        assert_eq!(items.len(), 1);
+        assert!(!items[0].creates_event());
+        assert!(!items[0].requires_event());
+
+        let meta = MetricMeta::parse_all(&items[0].payload()).unwrap();
+        assert_eq!(meta[0].name, "d:custom/response_time@millisecond");
+
+        // Only the event remains for regular envelope processing.
+        assert_eq!(envelope.len(), 1);
+        assert_eq!(envelope.items().next().unwrap().ty(), &ItemType::Event);
+    }
+
+    #[test]
+    fn test_mirror_envelope() {
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(ContentType::Json, "{}");
+            item
+        });
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Attachment);
+            item.set_payload(ContentType::OctetStream, "0123456789");
+            item
+        });
+
+        let mirror = mirror_envelope(&envelope, &[ItemType::Attachment]).unwrap();
+        let mirrored: Vec<_> = mirror.items().map(Item::ty).collect();
+        assert_eq!(mirrored, vec![&ItemType::Attachment]);
+        assert_eq!(mirror.event_id(), envelope.event_id());
+
+        // The primary envelope retains all items, including the mirrored ones.
+        let primary: Vec<_> = envelope.items().map(Item::ty).collect();
+        assert_eq!(primary, vec![&ItemType::Event, &ItemType::Attachment]);
+
+        assert!(mirror_envelope(&envelope, &[ItemType::Session]).is_none());
+    }
+
+    #[test]
+    fn test_client_ip_header() {
+        let envelope_meta: RequestMeta = serde_json::from_value(serde_json::json!({
+            "dsn": "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42",
+            "forwarded_for": "203.0.113.7, 10.0.0.1",
+        }))
+        .unwrap();
+
+        let mut request = SendEnvelope {
+            envelope_body: Vec::new(),
+            envelope_meta,
+            signature: None,
+            upstream: None,
+            scoping: Scoping {
+                project_key: ProjectKey::parse("e12d836b15bb49d7bbf99e64295d995b").unwrap(),
+                organization_id: 1,
+                project_id: ProjectId::new(42),
+                key_id: None,
+            },
+            http_encoding: HttpEncoding::Identity,
+            response_sender: None,
+            project_key: ProjectKey::parse("e12d836b15bb49d7bbf99e64295d995b").unwrap(),
+            client_ip_header: Some("X-Real-IP".to_owned()),
+        };
+
+        let builder = reqwest::Client::new().post("http://localhost/api/42/envelope/");
+        let built = request.build(RequestBuilder::reqwest(builder)).unwrap();
+        let headers = built.0.headers();
+
+        assert_eq!(headers["X-Real-IP"], "203.0.113.7");
+        assert_eq!(headers["X-Forwarded-For"], "203.0.113.7, 10.0.0.1");
+    }
+
+    /// Queues envelopes with an event on a running [`EnvelopeManager`].
+    ///
+    /// The config should use static mode, so that no upstream is required. All envelopes are sent before the manager handles the first one, so they compete for slots
+    /// in the envelope buffer. Returns the responses to the [`QueueEnvelope`] messages, the first
+    /// `evictions` outcomes for evicted envelopes, and all emitted metrics.
+    #[allow(clippy::type_complexity)]
+    fn queue_test_envelopes(
+        config: serde_json::Value,
+        event_ids: Vec<EventId>,
+        evictions: u64,
+    ) -> (
+        Vec<Result<Option<EventId>, QueueEnvelopeError>>,
+        Vec<TrackOutcome>,
+        Vec<String>,
+    ) {
+        relay_test::setup();
+
+        let config = Arc::new(Config::from_json_value(config).unwrap());
+
+        let mut result = None;
+        let metrics = relay_statsd::with_capturing_test_client(|| {
+            let block_result = relay_test::block_fn(move || {
+                let (tx, rx) = futures::sync::mpsc::unbounded();
+                let producer = TestOutcomeProducer(tx).start();
+                let aggregator = OutcomeAggregator::new(&config, producer.recipient()).start();
+                System::current().registry().set(aggregator);
+
+                let project_cache = ProjectCache::new(config.clone(), None).start();
+                System::current().registry().set(project_cache);
+
+                let processor = EnvelopeProcessor::start(config.clone(), None).unwrap();
+                let manager = EnvelopeManager::create(config, processor).unwrap().start();
+
+                let responses: Vec<_> = event_ids
+                    .into_iter()
+                    .map(|event_id| {
+                        let mut envelope = create_test_envelope(Some(event_id));
+                        envelope.add_item({
+                            let mut item = Item::new(ItemType::Event);
+                            item.set_payload(ContentType::Json, "{}");
+                            item
+                        });
+
+                        manager.send(QueueEnvelope {
+                            project_key: envelope.meta().public_key(),
+                            envelope,
+                            start_time: Instant::now(),
+                        })
+                    })
+                    .collect();
+
+                let evicted = rx
+                    .filter(|outcome| {
+                        matches!(
+                            outcome.outcome,
+                            Outcome::Invalid(DiscardReason::BufferOverflow)
+                        )
+                    })
+                    .take(evictions)
+                    .collect();
+
+                let responses = future::join_all(responses).map_err(|_| ());
+                Timeout::new(responses.join(evicted), Duration::from_secs(1))
+                    .map_err(|_| panic!("envelopes were not handled in time"))
+            });
+
+            result = Some(block_result.unwrap());
+        });
+
+        let (responses, evicted) = result.unwrap();
+        (responses, evicted, metrics)
+    }
+
+    #[test]
+    fn test_buffer_overflow_drop_oldest() {
+        let event_ids: Vec<_> = (0..4).map(|_| EventId::new()).collect();
+
+        let config = serde_json::json!({
+            "relay": {
+                "mode": "static",
+            },
+            "cache": {
+                "envelope_buffer_size": 2,
+                "buffer_overflow_policy": "drop_oldest",
+            }
+        });
+
+        let (responses, evicted, metrics) = queue_test_envelopes(config, event_ids.clone(), 2);
+
+        // All envelopes are accepted, since the oldest queued envelopes make room for them.
+        for (response, event_id) in responses.iter().zip(&event_ids) {
+            assert_eq!(response.as_ref().ok(), Some(&Some(*event_id)));
+        }
+
+        let evicted_ids: Vec<_> = evicted.iter().map(|outcome| outcome.event_id).collect();
+        assert_eq!(evicted_ids, vec![Some(event_ids[0]), Some(event_ids[1])]);
+        assert!(evicted
+            .iter()
+            .all(|outcome| outcome.category == DataCategory::Error));
+
+        // Evicted envelopes release their slot right away, so the buffer never exceeds its size.
+        let queue_sizes: Vec<_> = metrics
+            .iter()
+            .filter(|metric| metric.starts_with("event.queue_size:"))
+            .collect();
+        assert_eq!(
+            queue_sizes,
+            vec![
+                "event.queue_size:0|h",
+                "event.queue_size:1|h",
+                "event.queue_size:2|h",
+                "event.queue_size:2|h",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_buffer_overflow_reject_new() {
+        let event_ids: Vec<_> = (0..2).map(|_| EventId::new()).collect();
+
+        let config = serde_json::json!({
+            "relay": {
+                "mode": "static",
+            },
+            "cache": {
+                "envelope_buffer_size": 1,
+            }
+        });
+
+        let (responses, evicted, _) = queue_test_envelopes(config, event_ids.clone(), 0);
+
+        assert_eq!(responses[0].as_ref().ok(), Some(&Some(event_ids[0])));
+        assert!(matches!(
+            responses[1],
+            Err(QueueEnvelopeError::TooManyEnvelopes)
+        ));
+        assert!(evicted.is_empty());
+    }
+
+    #[test]
+    fn test_received_metric_buffer_full() {
+        let event_ids: Vec<_> = (0..2).map(|_| EventId::new()).collect();
+
+        let config = serde_json::json!({
+            "relay": {
+                "mode": "static",
+            },
+            "cache": {
+                "envelope_buffer_size": 1,
+            },
+            "metrics": {
+                "envelopes_received": true,
+            }
+        });
+
+        let (_, _, metrics) = queue_test_envelopes(config, event_ids, 0);
+
+        let received: Vec<_> = metrics
+            .iter()
+            .filter(|metric| metric.starts_with("event.received:"))
+            .collect();
+        assert_eq!(
+            received,
+            vec![
+                "event.received:1|c|#accepted:true",
+                "event.received:1|c|#accepted:false",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_accept_empty_envelope() {
+        relay_test::with_system(move || {
+            let config = Arc::new(Config::default());
+            let processor = EnvelopeProcessor::start(config.clone(), None).unwrap();
+            let manager = EnvelopeManager::create(config, processor).unwrap();
+
+            let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+                .parse()
+                .unwrap();
+            let envelope = Envelope::from_request(None, RequestMeta::new(dsn));
+
+            assert!(manager.check_empty_envelope(&envelope).is_ok());
+        });
+    }
+
+    #[test]
+    fn test_reject_empty_envelope() {
+        let config = Config::from_json_value(serde_json::json!({
+            "routing": {
+                "reject_empty_envelopes": true,
+            }
+        }))
+        .unwrap();
+
+        relay_test::with_system(move || {
+            let config = Arc::new(config);
+            let processor = EnvelopeProcessor::start(config.clone(), None).unwrap();
+            let manager = EnvelopeManager::create(config, processor).unwrap();
+
+            let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+                .parse()
+                .unwrap();
+            let mut envelope = Envelope::from_request(None, RequestMeta::new(dsn));
+
+            assert!(matches!(
+                manager.check_empty_envelope(&envelope),
+                Err(QueueEnvelopeError::EmptyEnvelope)
+            ));
+
+            envelope.add_item(Item::new(ItemType::Session));
+            assert!(manager.check_empty_envelope(&envelope).is_ok());
+        });
+    }
+
+    #[test]
+    fn test_read_only_mode() {
+        let config = Config::from_json_value(serde_json::json!({
+            "routing": {
+                "read_only_mode": true,
+            }
+        }))
+        .unwrap();
+
+        relay_test::with_system(move || {
+            let config = Arc::new(config);
+            let processor = EnvelopeProcessor::start(config.clone(), None).unwrap();
+            let mut manager = EnvelopeManager::create(config, processor).unwrap();
+
+            let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+                .parse()
+                .unwrap();
+            let mut envelope = Envelope::from_request(None, RequestMeta::new(dsn));
+            envelope.add_item(Item::new(ItemType::Session));
+
+            assert!(matches!(
+                manager.accept_envelope(&envelope),
+                Err(QueueEnvelopeError::ReadOnly)
+            ));
+        });
+    }
+
+    #[test]
+    fn test_clamp_sent_at() {
+        let processor = EnvelopeProcessor::new(Arc::new(Config::default()));
+        let received_at = Utc.ymd(2021, 1, 1).and_hms(0, 0, 0);
+
+        let sent_at = received_at + SignedDuration::days(30);
+        let clamped = processor.clamp_sent_at(Some(sent_at), received_at);
+        assert_eq!(clamped, Some(received_at + SignedDuration::seconds(60)));
+
+        let sent_at = received_at - SignedDuration::days(30);
+        let clamped = processor.clamp_sent_at(Some(sent_at), received_at);
+        assert_eq!(clamped, Some(sent_at));
+
+        assert_eq!(processor.clamp_sent_at(None, received_at), None);
+    }
+
+    #[test]
+    fn test_session_far_future_sent_at() {
+        relay_test::setup();
+
+        let processor = EnvelopeProcessor::new(Arc::new(Config::default()));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let received_at = relay_common::instant_to_date_time(request_meta.start_time());
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        // Without clamping, this would shift the session 30 days into the past and drop it.
+        envelope.set_sent_at(received_at + SignedDuration::days(30));
+
+        let timestamp = received_at.to_rfc3339();
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Session);
+            item.set_payload(
+                ContentType::Json,
+                serde_json::json!({
+                    "sid": "8333339f-5675-4f89-a9a0-1c935255ab58",
+                    "timestamp": timestamp,
+                    "started": timestamp,
+                    "attrs": {"release": "1.0"}
+                })
+                .to_string(),
+            );
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        let item = envelope.items().next().unwrap();
+        assert_eq!(item.ty(), &ItemType::Session);
+
+        let session = SessionUpdate::parse(&item.payload()).unwrap();
+        assert_eq!(session.timestamp.timestamp(), received_at.timestamp());
+    }
+
+    #[test]
+    fn test_default_environment_session() {
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"default_environment": "production"}
+        }))
+        .unwrap();
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut attributes: SessionAttributes =
+            serde_json::from_value(serde_json::json!({"release": "1.0"})).unwrap();
+        assert_eq!(
+            processor.validate_attributes(&None, &mut attributes),
+            Ok(true)
+        );
+        assert_eq!(attributes.environment.as_deref(), Some("production"));
+
+        let mut attributes: SessionAttributes =
+            serde_json::from_value(serde_json::json!({"release": "1.0", "environment": "dev"}))
+                .unwrap();
+        assert_eq!(
+            processor.validate_attributes(&None, &mut attributes),
+            Ok(false)
+        );
+        assert_eq!(attributes.environment.as_deref(), Some("dev"));
+    }
+
+    #[test]
+    fn test_default_environment_event() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"default_environment": "production"}
+        }))
+        .unwrap();
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(ContentType::Json, r#"{"message": "hello world"}"#);
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        let item = envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&item.payload()).unwrap();
+        assert_eq!(
+            event.value().unwrap().environment.as_str(),
+            Some("production")
+        );
+    }
+
+    #[test]
+    fn test_truncate_event_release() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "limits": {"max_release_length": 10}
+        }))
+        .unwrap();
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(
+                ContentType::Json,
+                r#"{"message": "hello world", "release": "my-app@1.0.0-beta.1"}"#,
+            );
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        let item = envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&item.payload()).unwrap();
+        let release = &event.value().unwrap().release;
+        assert_eq!(release.as_str(), Some("my-app@1.0"));
+        assert_eq!(release.meta().original_length(), Some(19));
+        assert_eq!(release.meta().iter_remarks().count(), 1);
+    }
+
+    #[test]
+    fn test_truncate_session_release() {
+        let config = Config::from_json_value(serde_json::json!({
+            "limits": {"max_release_length": 10}
+        }))
+        .unwrap();
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let mut attributes: SessionAttributes =
+            serde_json::from_value(serde_json::json!({"release": "my-app@äöü-beta.1"})).unwrap();
+        assert_eq!(
+            processor.validate_attributes(&None, &mut attributes),
+            Ok(true)
+        );
+        assert_eq!(attributes.release, "my-app@äöü");
+
+        let mut attributes: SessionAttributes =
+            serde_json::from_value(serde_json::json!({"release": "my-app@1.0"})).unwrap();
+        assert_eq!(
+            processor.validate_attributes(&None, &mut attributes),
+            Ok(false)
+        );
+        assert_eq!(attributes.release, "my-app@1.0");
+    }
+
+    #[test]
+    fn test_static_event_tags() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {
+                "static_event_tags": {
+                    "relay.cluster": "us-east",
+                    "region": "relay"
+                }
+            }
+        }))
+        .unwrap();
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(
+                ContentType::Json,
+                r#"{"message": "hello world", "tags": {"region": "client"}}"#,
+            );
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        let item = envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&item.payload()).unwrap();
+        let tags = event.value().unwrap().tags.value().unwrap();
+        assert_eq!(tags.get("relay.cluster"), Some("us-east"));
+        assert_eq!(tags.get("region"), Some("client"));
+        assert_eq!(tags.len(), 2);
+    }
+
+    fn process_self_instrumented(sentry_dsn: &str) -> Vec<Span> {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"self_instrument_transactions": true},
+            "sentry": {"enabled": true, "dsn": sentry_dsn}
+        }))
+        .unwrap();
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Transaction);
+            item.set_payload(
+                ContentType::Json,
+                serde_json::json!({
+                    "type": "transaction",
+                    "transaction": "/",
+                    "start_timestamp": 1619420400.0,
+                    "timestamp": 1619420402.0,
+                    "contexts": {
+                        "trace": {
+                            "trace_id": "4c79f60c11214eb38604f4ae0781bfb2",
+                            "span_id": "fa90fdead5f74052"
+                        }
+                    },
+                    "spans": []
+                })
+                .to_string(),
+            );
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        let item = envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&item.payload()).unwrap();
+        let spans = event.value().unwrap().spans.value().unwrap();
+        spans.iter().filter_map(Annotated::value).cloned().collect()
+    }
+
+    #[test]
+    fn test_self_instrument_transactions() {
+        // Matches the public key of the test envelope.
+        let spans =
+            process_self_instrumented("https://e12d836b15bb49d7bbf99e64295d995b@sentry.io/42");
+
+        let stages: Vec<_> = spans
+            .iter()
+            .filter(|span| span.op.as_str() == Some(SELF_INSTRUMENTATION_OP))
+            .filter_map(|span| span.description.as_str())
+            .collect();
+        assert!(stages.contains(&"finalize_event"));
+        assert!(stages.contains(&"scrub_event"));
+
+        let span = &spans[0];
+        assert_eq!(span.parent_span_id.value().unwrap().0, "fa90fdead5f74052");
+        assert_eq!(
+            span.trace_id.value().unwrap().0,
+            "4c79f60c11214eb38604f4ae0781bfb2"
+        );
+    }
+
+    #[test]
+    fn test_self_instrument_customer_transactions() {
+        let spans =
+            process_self_instrumented("https://0cc4a37e5aab4da58366266a87a95740@sentry.io/1");
+        assert!(spans.is_empty());
+    }
+
+    fn process_old_session(project_state: ProjectState) -> Option<Envelope> {
+        let processor = EnvelopeProcessor::new(Arc::new(Config::default()));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let received_at = relay_common::instant_to_date_time(request_meta.start_time());
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        // Older than the default of 5 days.
+        let timestamp = (received_at - SignedDuration::days(10)).to_rfc3339();
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Session);
+            item.set_payload(
+                ContentType::Json,
+                serde_json::json!({
+                    "sid": "8333339f-5675-4f89-a9a0-1c935255ab58",
+                    "timestamp": timestamp,
+                    "started": timestamp,
+                    "attrs": {"release": "1.0"}
+                })
+                .to_string(),
+            );
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(project_state),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        envelope_response.envelope
+    }
+
+    #[test]
+    fn test_session_max_secs_in_past() {
+        relay_test::setup();
+
+        let envelope = process_old_session(ProjectState::allowed());
+        assert!(envelope.is_none());
+    }
+
+    #[test]
+    fn test_session_max_secs_in_past_project_override() {
+        relay_test::setup();
+
+        let mut project_state = ProjectState::allowed();
+        project_state.config.max_session_secs_in_past = Some(30 * 24 * 3600);
+
+        let envelope = process_old_session(project_state).unwrap();
+        let item = envelope.items().next().unwrap();
+        assert_eq!(item.ty(), &ItemType::Session);
+    }
+
+    fn process_session_aggregates(config: Config, count: usize) -> Option<Envelope> {
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let received_at = relay_common::instant_to_date_time(request_meta.start_time());
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        let aggregate = serde_json::json!({
+            "started": received_at.to_rfc3339(),
+            "exited": 1
+        });
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Sessions);
             item.set_payload(
                 ContentType::Json,
-                r###"
-                    {
-                        "discarded_events": [
-                            ["queue_full", "error", 42]
-                        ]
-                    }
-                "###,
+                serde_json::json!({
+                    "aggregates": vec![aggregate; count],
+                    "attrs": {"release": "1.0"}
+                })
+                .to_string(),
             );
             item
         });
@@ -3199,24 +7458,92 @@ mod tests {
                 .unwrap()
         });
 
-        let envelope = envelope_response.envelope.unwrap();
+        envelope_response.envelope
+    }
+
+    #[test]
+    fn test_max_session_aggregates() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "limits": {
+                "max_session_aggregates": 2,
+            }
+        }))
+        .unwrap();
+
+        let envelope = process_session_aggregates(config, 3);
+        assert!(envelope.is_none());
+    }
+
+    #[test]
+    fn test_max_session_aggregates_within_limit() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "limits": {
+                "max_session_aggregates": 2,
+            }
+        }))
+        .unwrap();
+
+        let envelope = process_session_aggregates(config, 2).unwrap();
         let item = envelope.items().next().unwrap();
-        assert_eq!(item.ty(), &ItemType::ClientReport);
+        assert_eq!(item.ty(), &ItemType::Sessions);
     }
 
     #[test]
-    #[cfg(feature = "processing")]
-    fn test_client_report_removal_in_processing() {
+    fn test_forward_custom_envelope_header() {
+        relay_test::setup();
+
+        let processor = EnvelopeProcessor::new(Arc::new(Config::default()));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
+        envelope.set_header("x-tenant-id", "acme");
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Event);
+            item.set_payload(ContentType::Json, r###"{"message": "hello"}"###);
+            item
+        });
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        let body = String::from_utf8(envelope.to_vec().unwrap()).unwrap();
+        let headers = body.lines().next().unwrap();
+
+        assert!(headers.contains(r#""x-tenant-id":"acme""#));
+    }
+
+    #[test]
+    fn test_clamp_future_timestamps() {
         relay_test::setup();
 
         let config = Config::from_json_value(serde_json::json!({
-            "outcomes": {
-                "emit_outcomes": true,
-                "emit_client_outcomes": false,
-            },
             "processing": {
-                "enabled": true,
+                "enabled": false,
                 "kafka_config": [],
+                "clamp_future_timestamps": true
             }
         }))
         .unwrap();
@@ -3228,19 +7555,15 @@ mod tests {
             .unwrap();
 
         let request_meta = RequestMeta::new(dsn);
-        let mut envelope = Envelope::from_request(None, request_meta);
+        let received_at = relay_common::instant_to_date_time(request_meta.start_time());
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta);
 
+        let timestamp = received_at + SignedDuration::days(30);
         envelope.add_item({
-            let mut item = Item::new(ItemType::ClientReport);
+            let mut item = Item::new(ItemType::Event);
             item.set_payload(
                 ContentType::Json,
-                r###"
-                    {
-                        "discarded_events": [
-                            ["queue_full", "error", 42]
-                        ]
-                    }
-                "###,
+                serde_json::json!({ "timestamp": timestamp.timestamp() }).to_string(),
             );
             item
         });
@@ -3261,7 +7584,120 @@ mod tests {
                 .unwrap()
         });
 
-        assert!(envelope_response.envelope.is_none());
+        let envelope = envelope_response.envelope.unwrap();
+        let item = envelope.items().next().unwrap();
+        let event = Annotated::<Event>::from_json_bytes(&item.payload()).unwrap();
+        let timestamp = &event.value().unwrap().timestamp;
+
+        assert_eq!(
+            timestamp.value().unwrap().into_inner().timestamp(),
+            received_at.timestamp()
+        );
+        assert_eq!(
+            timestamp.meta().iter_remarks().next(),
+            Some(&Remark::new(RemarkType::Substituted, "!future_timestamp"))
+        );
+    }
+
+    #[test]
+    fn test_max_session_sequence() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "limits": {"max_session_sequence": 1000}
+        }))
+        .unwrap();
+        let processor = EnvelopeProcessor::new(Arc::new(config));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let timestamp = relay_common::instant_to_date_time(request_meta.start_time()).to_rfc3339();
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        for seq in &[1000, 1001] {
+            envelope.add_item({
+                let mut item = Item::new(ItemType::Session);
+                item.set_payload(
+                    ContentType::Json,
+                    serde_json::json!({
+                        "sid": "8333339f-5675-4f89-a9a0-1c935255ab58",
+                        "seq": seq,
+                        "timestamp": timestamp,
+                        "started": timestamp,
+                        "attrs": {"release": "1.0"}
+                    })
+                    .to_string(),
+                );
+                item
+            });
+        }
+
+        let envelope_response = relay_test::with_system(move || {
+            processor
+                .process(ProcessEnvelope {
+                    envelope,
+                    project_state: Arc::new(ProjectState::allowed()),
+                    start_time: Instant::now(),
+                    scoping: Scoping {
+                        project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                        organization_id: 1,
+                        project_id: ProjectId::new(1),
+                        key_id: None,
+                    },
+                })
+                .unwrap()
+        });
+
+        let envelope = envelope_response.envelope.unwrap();
+        assert_eq!(envelope.len(), 1);
+
+        let item = envelope.items().next().unwrap();
+        let session = SessionUpdate::parse(&item.payload()).unwrap();
+        assert_eq!(session.sequence, 1000);
+    }
+
+    #[test]
+    fn test_session_parsing_failure_metric() {
+        relay_test::setup();
+
+        let processor = EnvelopeProcessor::new(Arc::new(Config::default()));
+
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let request_meta = RequestMeta::new(dsn);
+        let mut envelope = Envelope::from_request(None, request_meta);
+
+        envelope.add_item({
+            let mut item = Item::new(ItemType::Session);
+            item.set_payload(ContentType::Json, r###"{"sid": 42"###);
+            item
+        });
+
+        let captures = relay_test::with_system(move || {
+            relay_statsd::with_capturing_test_client(|| {
+                processor
+                    .process(ProcessEnvelope {
+                        envelope,
+                        project_state: Arc::new(ProjectState::allowed()),
+                        start_time: Instant::now(),
+                        scoping: Scoping {
+                            project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee")
+                                .unwrap(),
+                            organization_id: 1,
+                            project_id: ProjectId::new(1),
+                            key_id: None,
+                        },
+                    })
+                    .ok();
+            })
+        });
+
+        assert!(captures.contains(&"item.parsing_failed:1|c|#item_type:session".to_owned()));
     }
 
     #[test]
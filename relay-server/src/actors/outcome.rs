@@ -229,6 +229,9 @@ pub enum DiscardReason {
     /// (Relay) The event payload exceeds the maximum size limit for the respective endpoint.
     TooLarge,
 
+    /// (Relay) The event payload exceeds the soft size limit and was not sampled.
+    SoftSizeLimit,
+
     /// (Legacy) A store request was received with an invalid method.
     ///
     /// This outcome is no longer emitted by Relay, as HTTP method validation occurs before an event
@@ -307,6 +310,22 @@ pub enum DiscardReason {
 
     /// (Relay) We failed to parse the profile so we discard the profile.
     ProcessProfile,
+
+    /// (Relay) The item type is disabled for the project.
+    FeatureDisabled,
+
+    /// (Relay) An envelope from another Relay was not signed by a trusted Relay.
+    RelaySignature,
+
+    /// (Relay) An error event was submitted without a timestamp.
+    Timestamp,
+
+    /// (Relay) A view hierarchy attachment could not be parsed.
+    InvalidViewHierarchy,
+
+    /// (Relay) The envelope was evicted from the full envelope buffer to make room for a newer
+    /// envelope.
+    BufferOverflow,
 }
 
 impl DiscardReason {
@@ -339,6 +358,12 @@ impl DiscardReason {
             DiscardReason::InvalidCompression => "invalid_compression",
             DiscardReason::ProjectState => "project_state",
             DiscardReason::DuplicateItem => "duplicate_item",
+            DiscardReason::FeatureDisabled => "feature_disabled",
+            DiscardReason::SoftSizeLimit => "soft_size_limit",
+            DiscardReason::RelaySignature => "relay_signature",
+            DiscardReason::Timestamp => "timestamp",
+            DiscardReason::InvalidViewHierarchy => "invalid_view_hierarchy",
+            DiscardReason::BufferOverflow => "buffer_overflow",
             DiscardReason::NoEventPayload => "no_event_payload",
             DiscardReason::Internal => "internal",
             DiscardReason::TransactionSampled => "transaction_sampled",
@@ -375,6 +400,9 @@ pub struct TrackRawOutcome {
     /// The source of the outcome (which Relay sent it)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     source: Option<String>,
+    /// The region of the Relay that emitted the outcome.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    region: Option<String>,
     /// The event's data category.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub category: Option<u8>,
@@ -399,6 +427,7 @@ impl TrackRawOutcome {
         // since TrackOutcome objects come only from this Relay (and not any downstream
         // Relays), set the source to whatever our current outcome source is.
         let source = config.outcome_source().map(str::to_owned);
+        let region = config.relay_region().map(str::to_owned);
 
         TrackRawOutcome {
             timestamp,
@@ -410,6 +439,7 @@ impl TrackRawOutcome {
             event_id: msg.event_id,
             remote_addr: msg.remote_addr.map(|addr| addr.to_string()),
             source,
+            region,
             category: msg.category.value(),
             quantity: Some(msg.quantity),
         }
@@ -830,3 +860,48 @@ impl Handler<TrackRawOutcome> for OutcomeProducer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use relay_common::ProjectKey;
+
+    use super::*;
+
+    fn track_outcome() -> TrackOutcome {
+        TrackOutcome {
+            timestamp: Utc::now(),
+            scoping: Scoping {
+                organization_id: 1,
+                project_id: ProjectId::new(42),
+                project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                key_id: Some(17),
+            },
+            outcome: Outcome::Invalid(DiscardReason::Payload),
+            event_id: None,
+            remote_addr: None,
+            category: DataCategory::Error,
+            quantity: 1,
+        }
+    }
+
+    #[test]
+    fn test_outcome_region() {
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {
+                "region": "us-east-1"
+            }
+        }))
+        .unwrap();
+
+        let outcome = TrackRawOutcome::from_outcome(track_outcome(), &config);
+        let json = serde_json::to_value(&outcome).unwrap();
+        assert_eq!(json["region"], "us-east-1");
+    }
+
+    #[test]
+    fn test_outcome_no_region() {
+        let outcome = TrackRawOutcome::from_outcome(track_outcome(), &Config::default());
+        let json = serde_json::to_value(&outcome).unwrap();
+        assert!(json.get("region").is_none());
+    }
+}
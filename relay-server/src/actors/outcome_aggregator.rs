@@ -49,6 +49,8 @@ pub struct OutcomeAggregator {
     bucket_interval: u64,
     /// The number of seconds between flushes of all buckets
     flush_interval: u64,
+    /// The number of buckets after which all buckets are flushed before the interval elapses
+    max_batch_size: usize,
     /// Mapping from bucket key to quantity.
     buckets: HashMap<BucketKey, u32>,
     /// The recipient of the aggregated outcomes
@@ -67,6 +69,7 @@ impl OutcomeAggregator {
             mode,
             bucket_interval: config.outcome_aggregator().bucket_interval,
             flush_interval: config.outcome_aggregator().flush_interval,
+            max_batch_size: config.outcome_aggregator().max_batch_size,
             buckets: HashMap::new(),
             outcome_producer,
         }
@@ -162,7 +165,7 @@ impl Default for OutcomeAggregator {
 impl Handler<TrackOutcome> for OutcomeAggregator {
     type Result = Result<(), OutcomeError>;
 
-    fn handle(&mut self, msg: TrackOutcome, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: TrackOutcome, ctx: &mut Self::Context) -> Self::Result {
         relay_log::trace!("Outcome aggregation requested: {:?}", msg);
 
         if self.mode == AggregationMode::DropEverything {
@@ -198,8 +201,85 @@ impl Handler<TrackOutcome> for OutcomeAggregator {
         if self.flush_interval == 0 {
             // Flush immediately. This is useful for integration tests.
             self.do_flush();
+        } else if self.buckets.len() >= self.max_batch_size {
+            relay_log::trace!("Flushing outcomes early, batch size reached");
+            self.flush(ctx);
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use futures::future::Future;
+    use futures::stream::Stream;
+    use futures::sync::mpsc;
+    use tokio_timer::Timeout;
+
+    use relay_common::{ProjectId, ProjectKey};
+
+    use super::*;
+
+    /// Forwards all received outcomes into a channel.
+    struct TestProducer(mpsc::UnboundedSender<TrackOutcome>);
+
+    impl Actor for TestProducer {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<TrackOutcome> for TestProducer {
+        type Result = Result<(), OutcomeError>;
+
+        fn handle(&mut self, msg: TrackOutcome, _ctx: &mut Self::Context) -> Self::Result {
+            self.0.unbounded_send(msg).ok();
+            Ok(())
+        }
+    }
+
+    fn track_outcome(category: DataCategory) -> TrackOutcome {
+        TrackOutcome {
+            timestamp: Utc::now(),
+            scoping: Scoping {
+                organization_id: 1,
+                project_id: ProjectId::new(42),
+                project_key: ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap(),
+                key_id: Some(17),
+            },
+            outcome: Outcome::Invalid(DiscardReason::Payload),
+            event_id: None,
+            remote_addr: None,
+            category,
+            quantity: 1,
+        }
+    }
+
+    #[test]
+    fn test_flush_at_max_batch_size() {
+        let config = Config::from_json_value(serde_json::json!({
+            "outcomes": {
+                "aggregator": {
+                    "flush_interval": 3600,
+                    "max_batch_size": 2,
+                }
+            }
+        }))
+        .unwrap();
+
+        let outcomes = relay_test::block_fn(move || {
+            let (tx, rx) = mpsc::unbounded();
+            let producer = TestProducer(tx).start();
+            let aggregator = OutcomeAggregator::new(&config, producer.recipient()).start();
+
+            aggregator.do_send(track_outcome(DataCategory::Error));
+            aggregator.do_send(track_outcome(DataCategory::Transaction));
+
+            Timeout::new(rx.take(2).collect(), Duration::from_millis(500))
+                .map_err(|_| panic!("outcomes were not flushed at the batch size cap"))
+        })
+        .unwrap();
+
+        assert_eq!(outcomes.len(), 2);
+    }
+}
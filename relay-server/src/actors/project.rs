@@ -1,9 +1,14 @@
-use std::collections::BTreeSet;
-use std::sync::Arc;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, Write};
+use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 
 use actix::prelude::*;
 use chrono::{DateTime, Utc};
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
 use futures::{future::Shared, sync::oneshot, Future};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -15,10 +20,11 @@ use relay_common::{ProjectId, ProjectKey};
 use relay_config::Config;
 use relay_filter::{matches_any_origin, FiltersConfig};
 use relay_general::pii::{DataScrubbingConfig, PiiConfig};
-use relay_general::store::BreakdownsConfig;
+use relay_general::store::{BreakdownsConfig, TransactionNameConfig};
 use relay_general::types::SpanAttribute;
-use relay_metrics::{self, Aggregator, Bucket, Metric};
-use relay_quotas::{Quota, RateLimits, Scoping};
+use relay_log::LogError;
+use relay_metrics::{self, Aggregator, Bucket, Metric, MetricMeta};
+use relay_quotas::{DataCategories, Quota, RateLimit, RateLimitScope, RateLimits, Scoping};
 use relay_sampling::SamplingConfig;
 use relay_statsd::metric;
 
@@ -27,14 +33,18 @@ use crate::actors::project_cache::{
     CheckEnvelopeResponse, CheckedEnvelope, ProjectCache, ProjectError, ProjectStateResponse,
     UpdateProjectState,
 };
-use crate::envelope::Envelope;
+use crate::envelope::{Envelope, ItemType};
 use crate::extractors::RequestMeta;
+use crate::metrics_extraction::errors::ErrorMetricsConfig;
 use crate::metrics_extraction::sessions::SessionMetricsConfig;
 use crate::metrics_extraction::transactions::TransactionMetricsConfig;
 use crate::metrics_extraction::TaggingRule;
 use crate::statsd::RelayCounters;
 use crate::utils::{EnvelopeLimiter, ErrorBoundary, Response};
 
+/// The maximum number of distinct metric names for which a project stores metadata.
+const MAX_METRIC_META: usize = 1000;
+
 /// The expiry status of a project state. Return value of [`ProjectState::check_expiry`].
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
 pub enum Expiry {
@@ -56,6 +66,9 @@ pub enum Feature {
     Profiling,
     #[serde(rename = "organizations:session-replay")]
     Replays,
+    /// Enables extraction of metrics from error events.
+    #[serde(rename = "organizations:error-metrics-extraction")]
+    ErrorMetrics,
 
     /// Unused.
     ///
@@ -69,6 +82,10 @@ pub enum Feature {
     Unknown,
 }
 
+fn is_false(val: &bool) -> bool {
+    !*val
+}
+
 /// These are config values that the user can modify in the UI.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename_all = "camelCase")]
@@ -88,6 +105,17 @@ pub struct ProjectConfig {
     /// Configuration for data scrubbers.
     #[serde(skip_serializing_if = "DataScrubbingConfig::is_disabled")]
     pub datascrubbing_settings: DataScrubbingConfig,
+    /// Apply the data scrubbing settings before the explicit PII config.
+    ///
+    /// Defaults to `false`, which applies `pii_config` first and `datascrubbing_settings` second.
+    #[serde(skip_serializing_if = "is_false")]
+    pub datascrubbing_before_pii_config: bool,
+    /// Remove the `server_name` from events to avoid leaking internal host names.
+    #[serde(skip_serializing_if = "is_false")]
+    pub scrub_server_name: bool,
+    /// Replace the directories of stack frame paths with a placeholder, keeping the file name.
+    #[serde(skip_serializing_if = "is_false")]
+    pub scrub_frame_paths: bool,
     /// Maximum event retention for the organization.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub event_retention: Option<u16>,
@@ -105,11 +133,25 @@ pub struct ProjectConfig {
     /// Configuration for extracting metrics from transaction events.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_metrics: Option<ErrorBoundary<TransactionMetricsConfig>>,
+    /// Configuration for extracting metrics from error events.
+    ///
+    /// Extraction additionally requires the error metrics feature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_metrics: Option<ErrorBoundary<ErrorMetricsConfig>>,
     /// The span attributes configuration.
     #[serde(skip_serializing_if = "BTreeSet::is_empty")]
     pub span_attributes: BTreeSet<SpanAttribute>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub metric_conditional_tagging: Vec<TaggingRule>,
+    /// Configuration for canonicalizing transaction names. Disabled if not present.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transaction_name_config: Option<TransactionNameConfig>,
+    /// Maximum age of ingested sessions in seconds, overriding the Relay's global setting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_session_secs_in_past: Option<i64>,
+    /// Envelope item types that are dropped for this project.
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub disabled_item_types: BTreeSet<ItemType>,
     /// Exposable features enabled for this project
     #[serde(skip_serializing_if = "BTreeSet::is_empty")]
     pub features: BTreeSet<Feature>,
@@ -124,14 +166,21 @@ impl Default for ProjectConfig {
             grouping_config: None,
             filter_settings: FiltersConfig::default(),
             datascrubbing_settings: DataScrubbingConfig::default(),
+            datascrubbing_before_pii_config: false,
+            scrub_server_name: false,
+            scrub_frame_paths: false,
             event_retention: None,
             quotas: Vec::new(),
             dynamic_sampling: None,
             breakdowns_v2: None,
             session_metrics: SessionMetricsConfig::default(),
             transaction_metrics: None,
+            error_metrics: None,
             span_attributes: BTreeSet::new(),
             metric_conditional_tagging: Vec::new(),
+            transaction_name_config: None,
+            max_session_secs_in_past: None,
+            disabled_item_types: BTreeSet::new(),
             features: BTreeSet::new(),
         }
     }
@@ -146,12 +195,24 @@ pub struct LimitedProjectConfig {
     pub pii_config: Option<PiiConfig>,
     #[serde(skip_serializing_if = "DataScrubbingConfig::is_disabled")]
     pub datascrubbing_settings: DataScrubbingConfig,
+    #[serde(skip_serializing_if = "is_false")]
+    pub datascrubbing_before_pii_config: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    pub scrub_server_name: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    pub scrub_frame_paths: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dynamic_sampling: Option<SamplingConfig>,
     #[serde(skip_serializing_if = "SessionMetricsConfig::is_disabled")]
     pub session_metrics: SessionMetricsConfig,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub transaction_metrics: Option<ErrorBoundary<TransactionMetricsConfig>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_metrics: Option<ErrorBoundary<ErrorMetricsConfig>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_session_secs_in_past: Option<i64>,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub disabled_item_types: BTreeSet<ItemType>,
     #[serde(skip_serializing_if = "BTreeSet::is_empty")]
     pub features: BTreeSet<Feature>,
 }
@@ -186,6 +247,12 @@ pub struct ProjectState {
     /// The organization id.
     #[serde(default)]
     pub organization_id: Option<u64>,
+    /// Features enabled for entire organizations, keyed by organization id.
+    ///
+    /// The features of this project's organization apply in addition to the features in the
+    /// project config.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub organization_features: BTreeMap<u64, BTreeSet<Feature>>,
 
     /// The time at which this project state was last updated.
     #[serde(skip, default = "Instant::now")]
@@ -208,6 +275,8 @@ pub struct LimitedProjectState {
     #[serde(with = "LimitedProjectConfig")]
     pub config: ProjectConfig,
     pub organization_id: Option<u64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub organization_features: BTreeMap<u64, BTreeSet<Feature>>,
 }
 
 impl ProjectState {
@@ -221,6 +290,7 @@ impl ProjectState {
             slug: None,
             config: ProjectConfig::default(),
             organization_id: None,
+            organization_features: BTreeMap::new(),
             last_fetch: Instant::now(),
             invalid: false,
         }
@@ -407,6 +477,7 @@ impl ProjectState {
     /// request should be discarded, by indicating the reason. The checks preformed for this are:
     ///
     ///  - Allowed origin headers
+    ///  - Signatures of trusted Relays, if required
     ///  - Disabled or unknown projects
     ///  - Disabled project keys (DSN)
     pub fn check_request(&self, meta: &RequestMeta, config: &Config) -> Result<(), DiscardReason> {
@@ -421,6 +492,15 @@ impl ProjectState {
             return Err(DiscardReason::Cors);
         }
 
+        // All requests must be signed by one of the trusted Relays. The signature is verified
+        // when the request is received, so only the signing key needs to be checked here.
+        if config.require_trusted_relay_signature() {
+            match meta.signed_by() {
+                Some(public_key) if self.config.trusted_relays.contains(public_key) => (),
+                _ => return Err(DiscardReason::RelaySignature),
+            }
+        }
+
         // sanity-check that the state has a matching public key loaded.
         if !self.is_matching_key(meta.public_key()) {
             relay_log::error!("public key mismatch on state {}", meta.public_key());
@@ -439,8 +519,15 @@ impl ProjectState {
         self
     }
 
+    /// Returns `true` if the feature is enabled for the project or its organization.
     pub fn has_feature(&self, feature: Feature) -> bool {
-        self.config.features.contains(&feature)
+        if self.config.features.contains(&feature) {
+            return true;
+        }
+
+        self.organization_id
+            .and_then(|id| self.organization_features.get(&id))
+            .map_or(false, |features| features.contains(&feature))
     }
 }
 
@@ -486,19 +573,123 @@ impl StateChannel {
     }
 }
 
+/// A project state held by the [`Project`], optionally compressed to reduce memory usage.
+///
+/// See [`Config::compress_cached_project_states`].
+#[derive(Debug)]
+enum CachedProjectState {
+    /// The state is held as-is.
+    Plain(Arc<ProjectState>),
+    /// The state is held as zlib-compressed JSON.
+    ///
+    /// Fields that are not serialized are stored separately. The decompressed state is reused for
+    /// as long as it is referenced elsewhere, so that concurrent envelopes share one instance.
+    Compressed {
+        payload: Vec<u8>,
+        last_fetch: Instant,
+        invalid: bool,
+        decompressed: RefCell<Weak<ProjectState>>,
+    },
+}
+
+impl CachedProjectState {
+    /// Creates a cached state, compressing it if `compress` is set.
+    ///
+    /// If compression fails, the state is held uncompressed.
+    fn new(state: Arc<ProjectState>, compress: bool) -> Self {
+        if !compress {
+            return Self::Plain(state);
+        }
+
+        match Self::compress(&state) {
+            Ok(payload) => Self::Compressed {
+                payload,
+                last_fetch: state.last_fetch,
+                invalid: state.invalid,
+                decompressed: RefCell::new(Arc::downgrade(&state)),
+            },
+            Err(error) => {
+                relay_log::error!("failed to compress project state: {}", LogError(&error));
+                Self::Plain(state)
+            }
+        }
+    }
+
+    fn compress(state: &ProjectState) -> io::Result<Vec<u8>> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut encoder, state)?;
+        encoder.flush()?;
+        encoder.finish()
+    }
+
+    /// Returns the project state, decompressing it if necessary.
+    fn get(&self) -> Option<Arc<ProjectState>> {
+        let (payload, last_fetch, invalid, decompressed) = match self {
+            Self::Plain(state) => return Some(state.clone()),
+            Self::Compressed {
+                payload,
+                last_fetch,
+                invalid,
+                decompressed,
+            } => (payload, *last_fetch, *invalid, decompressed),
+        };
+
+        if let Some(state) = decompressed.borrow().upgrade() {
+            return Some(state);
+        }
+
+        match serde_json::from_reader::<_, ProjectState>(ZlibDecoder::new(payload.as_slice())) {
+            Ok(mut state) => {
+                state.last_fetch = last_fetch;
+                state.invalid = invalid;
+                let state = Arc::new(state);
+                decompressed.replace(Arc::downgrade(&state));
+                Some(state)
+            }
+            Err(error) => {
+                relay_log::error!("failed to decompress project state: {}", LogError(&error));
+                None
+            }
+        }
+    }
+}
+
+/// The time at which a cached rate limit was last received.
+///
+/// Rate limits are identified by their categories and scope, the same way [`RateLimits`] merges
+/// them.
+#[derive(Debug)]
+struct RateLimitReceived {
+    categories: DataCategories,
+    scope: RateLimitScope,
+    received_at: Instant,
+}
+
+impl RateLimitReceived {
+    /// Returns `true` if this receive time belongs to the given rate limit.
+    fn is_for(&self, limit: &RateLimit) -> bool {
+        self.categories == limit.categories && self.scope == limit.scope
+    }
+}
+
 /// Structure representing organization and project configuration for a project key.
 ///
 /// This structure no longer uniquely identifies a project. Instead, it identifies a project key.
 /// Projects can define multiple keys, in which case this structure is duplicated for each instance.
 pub struct Project {
     last_updated_at: Instant,
+    last_accessed: u64,
     project_key: ProjectKey,
     config: Arc<Config>,
-    state: Option<Arc<ProjectState>>,
+    state: Option<CachedProjectState>,
     state_channel: Option<StateChannel>,
     rate_limits: RateLimits,
+    rate_limits_received: Vec<RateLimitReceived>,
     last_no_cache: Instant,
+    state_loaded_at: Option<Instant>,
+    invalid_state_since: Option<Instant>,
     metrics_allowed: bool,
+    metric_meta: BTreeMap<String, MetricMeta>,
 }
 
 impl Project {
@@ -506,13 +697,18 @@ impl Project {
     pub fn new(key: ProjectKey, config: Arc<Config>) -> Self {
         Project {
             last_updated_at: Instant::now(),
+            last_accessed: 0,
             project_key: key,
             config,
             state: None,
             state_channel: None,
             rate_limits: RateLimits::new(),
+            rate_limits_received: Vec::new(),
             last_no_cache: Instant::now(),
+            state_loaded_at: None,
+            invalid_state_since: None,
             metrics_allowed: true,
+            metric_meta: BTreeMap::new(),
         }
     }
 
@@ -523,18 +719,90 @@ impl Project {
         }
     }
 
+    /// Returns the rate limits cached for this project key.
+    pub fn rate_limits(&self) -> &RateLimits {
+        &self.rate_limits
+    }
+
     pub fn merge_rate_limits(&mut self, rate_limits: RateLimits) {
+        if self.config.rate_limit_max_age().is_some() {
+            self.track_received_rate_limits(&rate_limits);
+        }
         self.rate_limits.merge(rate_limits);
     }
 
-    /// Returns a reference to the project state if available.
-    pub fn state(&self) -> Option<&ProjectState> {
-        self.state.as_deref()
+    /// Records the receive time of all active rate limits.
+    fn track_received_rate_limits(&mut self, rate_limits: &RateLimits) {
+        let now = Instant::now();
+
+        for limit in rate_limits.iter() {
+            if limit.retry_after.expired() {
+                continue;
+            }
+
+            // Categories are sorted when they are merged into `RateLimits`.
+            let mut limit = limit.clone();
+            limit.categories.sort();
+
+            match self
+                .rate_limits_received
+                .iter_mut()
+                .find(|r| r.is_for(&limit))
+            {
+                Some(received) => received.received_at = now,
+                None => self.rate_limits_received.push(RateLimitReceived {
+                    categories: limit.categories,
+                    scope: limit.scope,
+                    received_at: now,
+                }),
+            }
+        }
     }
 
-    /// Returns a reference to the project state if available.
+    /// Discards cached rate limits that were last received longer ago than the maximum age.
+    ///
+    /// See [`Config::rate_limit_max_age`].
+    fn discard_stale_rate_limits(&mut self) {
+        let max_age = match self.config.rate_limit_max_age() {
+            Some(max_age) => max_age,
+            None => return,
+        };
+
+        let (stale, received): (Vec<_>, Vec<_>) = std::mem::take(&mut self.rate_limits_received)
+            .into_iter()
+            .partition(|r| r.received_at.elapsed() >= max_age);
+        self.rate_limits_received = received;
+
+        if stale.is_empty() {
+            return;
+        }
+
+        relay_log::trace!("discarding stale rate limits of {}", self.project_key);
+        for limit in std::mem::take(&mut self.rate_limits) {
+            if !stale.iter().any(|r| r.is_for(&limit)) {
+                self.rate_limits.add(limit);
+            }
+        }
+    }
+
+    /// Returns the project state if available.
+    ///
+    /// If the state is cached compressed, this decompresses it on every call.
+    pub fn state(&self) -> Option<Arc<ProjectState>> {
+        self.state.as_ref().and_then(CachedProjectState::get)
+    }
+
+    /// Returns the project state if available.
+    ///
+    /// This is an alias of [`state`](Self::state).
     pub fn state_clone(&self) -> Option<Arc<ProjectState>> {
-        self.state.clone()
+        self.state()
+    }
+
+    /// Replaces the cached project state, compressing it if configured.
+    fn set_state(&mut self, state: Option<Arc<ProjectState>>) {
+        let compress = self.config.compress_cached_project_states();
+        self.state = state.map(|state| CachedProjectState::new(state, compress));
     }
 
     /// The last time the project state was updated
@@ -542,6 +810,23 @@ impl Project {
         self.last_updated_at
     }
 
+    /// The access sequence number at which the project was last used by the project cache.
+    pub fn last_accessed(&self) -> u64 {
+        self.last_accessed
+    }
+
+    /// Records an access by the project cache for least recently used eviction.
+    pub fn mark_accessed(&mut self, sequence: u64) {
+        self.last_accessed = sequence;
+    }
+
+    /// Returns `true` if a project state fetch is in progress.
+    ///
+    /// Envelopes may be waiting for this fetch, so the project must not be evicted.
+    pub fn is_fetching_state(&self) -> bool {
+        self.state_channel.is_some()
+    }
+
     /// Refresh the update time of the project in order to delay eviction.
     ///
     /// Called by the project cache when the project state is refreshed.
@@ -569,6 +854,37 @@ impl Project {
         }
     }
 
+    /// Stores the given [metric metadata](MetricMeta) for this project.
+    ///
+    /// Existing metadata for a metric name is replaced by the most recent submission. Returns the
+    /// entries that are new or differ from the stored ones, which need to be forwarded. Once
+    /// metadata for `MAX_METRIC_META` metric names is stored, entries for new names are dropped.
+    pub fn add_metric_meta(&mut self, meta: Vec<MetricMeta>) -> Vec<MetricMeta> {
+        let mut changed = Vec::new();
+        if !self.metrics_allowed {
+            return changed;
+        }
+
+        for entry in meta {
+            match self.metric_meta.get_mut(&entry.name) {
+                Some(existing) if *existing == entry => (),
+                Some(existing) => {
+                    *existing = entry.clone();
+                    changed.push(entry);
+                }
+                None if self.metric_meta.len() < MAX_METRIC_META => {
+                    self.metric_meta.insert(entry.name.clone(), entry.clone());
+                    changed.push(entry);
+                }
+                None => {
+                    relay_log::debug!("dropping metric meta: too many metric names");
+                }
+            }
+        }
+
+        changed
+    }
+
     pub fn get_or_fetch_state(
         &mut self,
         mut no_cache: bool,
@@ -586,8 +902,9 @@ impl Project {
             }
         }
 
-        let state = self.state.as_ref();
+        let state = self.state();
         let expiry = state
+            .as_ref()
             .map(|s| s.check_expiry(&self.config))
             .unwrap_or(Expiry::Expired);
 
@@ -599,10 +916,10 @@ impl Project {
             (None, _) | (_, Expiry::Expired) => None,
 
             // The project is semi-outdated, fetch new state but return old one.
-            (Some(state), Expiry::Stale) => Some(state.clone()),
+            (Some(state), Expiry::Stale) => Some(state),
 
             // The project is not outdated, return early here to jump over fetching logic below.
-            (Some(state), Expiry::Updated) => return Response::ok(state.clone()),
+            (Some(state), Expiry::Updated) => return Response::ok(state),
         };
 
         let receiver = match self.state_channel {
@@ -653,13 +970,54 @@ impl Project {
         }
 
         self.state_channel = None;
-        self.state = state_result.map(|resp| resp.state);
+        let state = match state_result.map(|resp| resp.state) {
+            Some(state) if state.invalid() => Some(self.recover_invalid_state(state)),
+            Some(state) => {
+                self.invalid_state_since = None;
+                Some(state)
+            }
+            None => None,
+        };
+        self.set_state(state.clone());
         self.update_metrics_allowed();
 
-        if let Some(ref state) = self.state {
+        // Only the initial load can race with rate limits cached before the state was known.
+        // Refreshing the state must not lift cached rate limits again.
+        if state.is_some() && self.state_loaded_at.is_none() {
+            self.state_loaded_at = Some(Instant::now());
+        }
+
+        if let Some(state) = state {
             relay_log::debug!("project state {} updated", self.project_key);
-            channel.send(state.clone());
+            channel.send(state);
+        }
+    }
+
+    /// Returns the previous valid state in place of an invalid update if it is still within the
+    /// grace period.
+    ///
+    /// The grace period counts from the first invalid update since the last valid state. The kept
+    /// state expires like a freshly fetched state, so that it is not refetched on every access. See
+    /// [`Config::invalid_project_state_grace_period`].
+    fn recover_invalid_state(&mut self, invalid: Arc<ProjectState>) -> Arc<ProjectState> {
+        let previous = match self.state() {
+            Some(previous) if !previous.invalid() => previous,
+            _ => return invalid,
+        };
+
+        let invalid_since = *self.invalid_state_since.get_or_insert_with(Instant::now);
+        if invalid_since.elapsed() >= self.config.invalid_project_state_grace_period() {
+            return invalid;
         }
+
+        relay_log::warn!(
+            "received invalid project state {}, keeping previous state",
+            self.project_key
+        );
+
+        let mut state = ProjectState::clone(&previous);
+        state.last_fetch = Instant::now();
+        Arc::new(state)
     }
 
     fn fetch_state(&mut self, no_cache: bool) {
@@ -667,6 +1025,17 @@ impl Project {
         ProjectCache::from_registry().do_send(UpdateProjectState::new(self.project_key, no_cache));
     }
 
+    /// Returns `true` if the project state was loaded too recently to trust cached rate limits.
+    ///
+    /// See [`Config::rate_limit_grace_after_state_load`].
+    fn in_rate_limit_grace(&self) -> bool {
+        let grace = self.config.rate_limit_grace_after_state_load();
+        match self.state_loaded_at {
+            Some(loaded_at) => loaded_at.elapsed() < grace,
+            None => false,
+        }
+    }
+
     /// Creates `Scoping` for this project if the state is loaded.
     ///
     /// Returns `Some` if the project state has been fetched and contains a project identifier,
@@ -700,15 +1069,26 @@ impl Project {
         mut envelope: Envelope,
         scoping: &Scoping,
     ) -> Result<CheckedEnvelope, DiscardReason> {
-        if let Some(state) = self.state() {
+        let state = self.state();
+        if let Some(ref state) = state {
             state.check_request(envelope.meta(), &self.config)?;
         }
 
         self.rate_limits.clean_expired();
+        self.discard_stale_rate_limits();
+
+        // Right after a state load, cached rate limits may be stale. Only enforce the quotas of
+        // the project state until the grace period has passed.
+        let empty_limits = RateLimits::new();
+        let cached_limits = if self.in_rate_limit_grace() {
+            &empty_limits
+        } else {
+            &self.rate_limits
+        };
 
-        let quotas = self.state().map(|s| s.get_quotas()).unwrap_or(&[]);
+        let quotas = state.as_deref().map(|s| s.get_quotas()).unwrap_or(&[]);
         let envelope_limiter = EnvelopeLimiter::new(|item_scoping, _| {
-            Ok(self.rate_limits.check_with_quotas(quotas, item_scoping))
+            Ok(cached_limits.check_with_quotas(quotas, item_scoping))
         });
 
         let (enforcement, rate_limits) = envelope_limiter.enforce(&mut envelope, scoping)?;
@@ -732,3 +1112,333 @@ impl Project {
         CheckEnvelopeResponse { result, scoping }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use relay_common::EventId;
+    use relay_quotas::RetryAfter;
+
+    use crate::envelope::{ContentType, Item, ItemType};
+
+    use super::*;
+
+    fn create_project(config: Config) -> Project {
+        let project_key = ProjectKey::parse("e12d836b15bb49d7bbf99e64295d995b").unwrap();
+        let mut project = Project::new(project_key, Arc::new(config));
+        project.set_state(Some(Arc::new(ProjectState::allowed())));
+        project.state_loaded_at = Some(Instant::now());
+
+        let mut rate_limits = RateLimits::new();
+        rate_limits.add(RateLimit {
+            categories: DataCategories::new(),
+            scope: RateLimitScope::Project(ProjectId::new(42)),
+            reason_code: None,
+            retry_after: RetryAfter::from_secs(60),
+        });
+        project.merge_rate_limits(rate_limits);
+
+        project
+    }
+
+    fn create_envelope() -> Envelope {
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        let mut envelope = Envelope::from_request(Some(EventId::new()), RequestMeta::new(dsn));
+        let mut item = Item::new(ItemType::Event);
+        item.set_payload(ContentType::Json, r###"{"message": "hello"}"###);
+        envelope.add_item(item);
+        envelope
+    }
+
+    #[test]
+    fn test_rate_limit_grace_after_state_load() {
+        let config = Config::from_json_value(serde_json::json!({
+            "cache": {
+                "rate_limit_grace_after_state_load": 60
+            }
+        }))
+        .unwrap();
+
+        let mut project = create_project(config);
+        let response = project.check_envelope(create_envelope());
+        let checked = response.result.unwrap();
+
+        assert!(checked.envelope.is_some());
+        assert!(checked.rate_limits.is_ok());
+    }
+
+    #[test]
+    fn test_rate_limit_after_grace() {
+        relay_test::setup();
+
+        let mut project = create_project(Config::default());
+        let checked = relay_test::with_system(move || {
+            project.check_envelope(create_envelope()).result.unwrap()
+        });
+
+        assert!(checked.envelope.is_none());
+        assert!(checked.rate_limits.is_limited());
+    }
+
+    #[test]
+    fn test_rate_limit_grace_only_after_first_load() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "cache": {
+                "rate_limit_grace_after_state_load": 60
+            }
+        }))
+        .unwrap();
+
+        let mut project = create_project(config);
+        let first_load = Instant::now().checked_sub(Duration::from_secs(61));
+        project.state_loaded_at = first_load;
+
+        // Refreshing the state does not start another grace period.
+        project.state_channel = Some(StateChannel::new());
+        let response = ProjectStateResponse::new(Arc::new(ProjectState::allowed()));
+        project.update_state(Some(response), false);
+        assert_eq!(project.state_loaded_at, first_load);
+
+        let checked = relay_test::with_system(move || {
+            project.check_envelope(create_envelope()).result.unwrap()
+        });
+
+        assert!(checked.envelope.is_none());
+        assert!(checked.rate_limits.is_limited());
+    }
+
+    #[test]
+    fn test_rate_limit_max_age() {
+        let config = Config::from_json_value(serde_json::json!({
+            "cache": {
+                "rate_limit_max_age": 30
+            }
+        }))
+        .unwrap();
+
+        let mut project = create_project(config);
+        for received in &mut project.rate_limits_received {
+            received.received_at = Instant::now().checked_sub(Duration::from_secs(31)).unwrap();
+        }
+
+        // A limit received later is retained, even though it was merged with the stale one.
+        let mut rate_limits = RateLimits::new();
+        rate_limits.add(RateLimit {
+            categories: DataCategories::new(),
+            scope: RateLimitScope::Organization(43),
+            reason_code: None,
+            retry_after: RetryAfter::from_secs(60),
+        });
+        project.merge_rate_limits(rate_limits);
+
+        let response = project.check_envelope(create_envelope());
+        let checked = response.result.unwrap();
+
+        assert!(checked.envelope.is_some());
+        assert!(checked.rate_limits.is_ok());
+
+        let scopes: Vec<_> = project.rate_limits.iter().map(|l| &l.scope).collect();
+        assert_eq!(scopes, vec![&RateLimitScope::Organization(43)]);
+        assert_eq!(project.rate_limits_received.len(), 1);
+    }
+
+    #[test]
+    fn test_invalid_state_grace_period() {
+        let config = Config::from_json_value(serde_json::json!({
+            "cache": {
+                "invalid_state_grace_period": 60
+            }
+        }))
+        .unwrap();
+
+        let mut project = create_project(config);
+
+        // The previous state is already expired, which is when updates are fetched.
+        let mut previous = ProjectState::allowed();
+        previous.last_fetch = Instant::now()
+            .checked_sub(Duration::from_secs(600))
+            .unwrap();
+        project.set_state(Some(Arc::new(previous)));
+
+        project.state_channel = Some(StateChannel::new());
+        let response = ProjectStateResponse::new(Arc::new(ProjectState::err()));
+        project.update_state(Some(response), false);
+
+        let state = project.state().unwrap();
+        assert!(!state.invalid());
+        assert!(!state.disabled());
+        assert!(state.last_fetch.elapsed() < Duration::from_secs(60));
+
+        // Once the grace period since the first invalid update has passed, the state is invalid.
+        project.invalid_state_since = Instant::now().checked_sub(Duration::from_secs(61));
+        project.state_channel = Some(StateChannel::new());
+        let response = ProjectStateResponse::new(Arc::new(ProjectState::err()));
+        project.update_state(Some(response), false);
+
+        assert!(project.state().unwrap().invalid());
+    }
+
+    #[test]
+    fn test_invalid_state_without_grace_period() {
+        let mut project = create_project(Config::default());
+        project.state_channel = Some(StateChannel::new());
+        let response = ProjectStateResponse::new(Arc::new(ProjectState::err()));
+        project.update_state(Some(response), false);
+
+        assert!(project.state().unwrap().invalid());
+    }
+
+    #[test]
+    fn test_compressed_state() {
+        let mut state = ProjectState::allowed();
+        state.project_id = Some(ProjectId::new(42));
+        state.organization_id = Some(1);
+        state.config.event_retention = Some(30);
+        state.config.scrub_server_name = true;
+        state.config.features.insert(Feature::Profiling);
+        let state = Arc::new(state);
+
+        let cached = CachedProjectState::new(state.clone(), true);
+        assert!(matches!(cached, CachedProjectState::Compressed { .. }));
+
+        // The original state is reused while it is still referenced.
+        let reused = cached.get().unwrap();
+        assert!(Arc::ptr_eq(&reused, &state));
+        drop(reused);
+
+        let expected = serde_json::to_value(&*state).unwrap();
+        let (last_fetch, invalid) = (state.last_fetch, state.invalid);
+        drop(state);
+
+        let restored = cached.get().unwrap();
+        assert_eq!(serde_json::to_value(&*restored).unwrap(), expected);
+        assert_eq!(restored.last_fetch, last_fetch);
+        assert_eq!(restored.invalid, invalid);
+
+        // The decompressed state is shared until all references are dropped.
+        assert!(Arc::ptr_eq(&cached.get().unwrap(), &restored));
+    }
+
+    #[test]
+    fn test_compressed_state_config() {
+        let config = Config::from_json_value(serde_json::json!({
+            "cache": {"compress_project_states": true}
+        }))
+        .unwrap();
+
+        let project = create_project(config);
+        assert!(matches!(
+            project.state,
+            Some(CachedProjectState::Compressed { .. })
+        ));
+
+        let state = project.state().unwrap();
+        assert!(!state.disabled());
+    }
+
+    #[test]
+    fn test_trusted_relay_signature() {
+        let config = Config::from_json_value(serde_json::json!({
+            "relay": {"require_trusted_relay_signature": true}
+        }))
+        .unwrap();
+
+        let (_, public_key) = relay_auth::generate_key_pair();
+        let (_, other_key) = relay_auth::generate_key_pair();
+
+        let mut state = ProjectState::allowed();
+        state.config.trusted_relays = vec![public_key.clone()];
+
+        let signed_meta = |public_key: Option<PublicKey>| {
+            let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+                .parse()
+                .unwrap();
+            let mut meta = RequestMeta::new(dsn);
+            if let Some(public_key) = public_key {
+                meta.set_signed_by(public_key);
+            }
+            meta
+        };
+
+        let trusted = signed_meta(Some(public_key));
+        assert_eq!(state.check_request(&trusted, &config), Ok(()));
+
+        let untrusted = signed_meta(Some(other_key));
+        assert_eq!(
+            state.check_request(&untrusted, &config),
+            Err(DiscardReason::RelaySignature)
+        );
+
+        // Requests without a verified signature are rejected, too.
+        let unsigned = signed_meta(None);
+        assert_eq!(
+            state.check_request(&unsigned, &config),
+            Err(DiscardReason::RelaySignature)
+        );
+    }
+
+    #[test]
+    fn test_organization_features() {
+        let mut state: ProjectState = serde_json::from_value(serde_json::json!({
+            "organizationId": 1,
+            "organizationFeatures": {
+                "1": ["organizations:profiling"],
+                "2": ["organizations:session-replay"]
+            }
+        }))
+        .unwrap();
+
+        assert!(state.config.features.is_empty());
+        assert!(state.has_feature(Feature::Profiling));
+        assert!(!state.has_feature(Feature::Replays));
+
+        // Features of other organizations do not apply.
+        state.organization_id = Some(3);
+        assert!(!state.has_feature(Feature::Profiling));
+    }
+
+    fn metric_meta(name: &str, cardinality: Option<u64>) -> MetricMeta {
+        MetricMeta {
+            name: name.to_owned(),
+            unit: relay_common::MetricUnit::None,
+            cardinality,
+        }
+    }
+
+    #[test]
+    fn test_metric_meta_changed() {
+        let mut project = create_project(Config::default());
+
+        let first = vec![metric_meta("c:custom/hits@none", None)];
+        assert_eq!(project.add_metric_meta(first.clone()), first);
+
+        // Unchanged metadata does not need to be forwarded again.
+        assert!(project.add_metric_meta(first).is_empty());
+
+        let updated = vec![metric_meta("c:custom/hits@none", Some(10))];
+        assert_eq!(project.add_metric_meta(updated.clone()), updated);
+    }
+
+    #[test]
+    fn test_metric_meta_limit() {
+        let mut project = create_project(Config::default());
+
+        let meta = (0..MAX_METRIC_META)
+            .map(|i| metric_meta(&format!("c:custom/hits_{}@none", i), None))
+            .collect::<Vec<_>>();
+        assert_eq!(project.add_metric_meta(meta).len(), MAX_METRIC_META);
+
+        // New names are dropped, but existing entries can still be updated.
+        let meta = vec![
+            metric_meta("c:custom/other@none", None),
+            metric_meta("c:custom/hits_0@none", Some(10)),
+        ];
+        let changed = project.add_metric_meta(meta);
+        assert_eq!(changed, vec![metric_meta("c:custom/hits_0@none", Some(10))]);
+        assert_eq!(project.metric_meta.len(), MAX_METRIC_META);
+    }
+}
@@ -9,12 +9,12 @@ use futures::{future, Future};
 
 use relay_common::ProjectKey;
 use relay_config::{Config, RelayMode};
-use relay_metrics::{self, AggregateMetricsError, Bucket, FlushBuckets, Metric};
-use relay_quotas::{RateLimits, Scoping};
+use relay_metrics::{self, AggregateMetricsError, Bucket, FlushBuckets, Metric, MetricMeta};
+use relay_quotas::{RateLimitScope, RateLimits, Scoping};
 use relay_redis::RedisPool;
 use relay_statsd::metric;
 
-use crate::actors::envelopes::{EnvelopeManager, SendMetrics};
+use crate::actors::envelopes::{EnvelopeManager, SendMetricMeta, SendMetrics};
 use crate::actors::outcome::DiscardReason;
 use crate::actors::project::{Expiry, Project, ProjectState};
 use crate::actors::project_local::LocalProjectSource;
@@ -40,6 +40,7 @@ impl ResponseError for ProjectError {}
 pub struct ProjectCache {
     config: Arc<Config>,
     projects: HashMap<ProjectKey, Project>,
+    access_sequence: u64,
     local_source: Addr<LocalProjectSource>,
     upstream_source: Addr<UpstreamProjectSource>,
     #[cfg(feature = "processing")]
@@ -65,6 +66,7 @@ impl ProjectCache {
         ProjectCache {
             config,
             projects: HashMap::new(),
+            access_sequence: 0,
             local_source,
             upstream_source,
             #[cfg(feature = "processing")]
@@ -83,12 +85,47 @@ impl ProjectCache {
         metric!(timer(RelayTimers::ProjectStateEvictionDuration) = eviction_start.elapsed());
     }
 
+    /// Evicts least recently used projects until there is room for another project.
+    ///
+    /// Projects that are fetching their state are retained, since envelopes may be waiting for the
+    /// state. Envelopes that are already being processed hold their own reference to the state.
+    fn evict_least_recently_used(&mut self) {
+        let max_projects = match self.config.max_cached_projects() {
+            Some(max_projects) => max_projects,
+            None => return,
+        };
+
+        while self.projects.len() >= max_projects {
+            let evictable = self
+                .projects
+                .iter()
+                .filter(|(_, project)| !project.is_fetching_state())
+                .min_by_key(|(_, project)| project.last_accessed())
+                .map(|(project_key, _)| *project_key);
+
+            match evictable {
+                Some(project_key) => {
+                    relay_log::trace!("evicting least recently used project {}", project_key);
+                    self.projects.remove(&project_key);
+                    metric!(counter(RelayCounters::ProjectCacheLruEviction) += 1);
+                }
+                None => break,
+            }
+        }
+    }
+
     fn get_or_create_project(&mut self, project_key: ProjectKey) -> &mut Project {
         metric!(histogram(RelayHistograms::ProjectStateCacheSize) = self.projects.len() as u64);
 
+        if !self.projects.contains_key(&project_key) {
+            self.evict_least_recently_used();
+        }
+
         let config = self.config.clone();
+        self.access_sequence += 1;
 
-        self.projects
+        let project = self
+            .projects
             .entry(project_key)
             .and_modify(|_| {
                 metric!(counter(RelayCounters::ProjectCacheHit) += 1);
@@ -96,7 +133,33 @@ impl ProjectCache {
             .or_insert_with(move || {
                 metric!(counter(RelayCounters::ProjectCacheMiss) += 1);
                 Project::new(project_key, config)
-            })
+            });
+
+        project.mark_accessed(self.access_sequence);
+        project
+    }
+
+    /// Caches rate limits on the projects they apply to.
+    ///
+    /// Limits scoped to another key are only cached if a project for that key already exists, so
+    /// that rate limits from upstream responses do not create cache entries for arbitrary keys.
+    fn merge_rate_limits(&mut self, project_key: ProjectKey, rate_limits: RateLimits) {
+        let mut project_limits = RateLimits::new();
+        for rate_limit in rate_limits {
+            match rate_limit.scope {
+                RateLimitScope::Key(key) if key != project_key => {
+                    if let Some(project) = self.projects.get_mut(&key) {
+                        let mut key_limits = RateLimits::new();
+                        key_limits.add(rate_limit);
+                        project.merge_rate_limits(key_limits);
+                    }
+                }
+                _ => project_limits.add(rate_limit),
+            }
+        }
+
+        let project = self.get_or_create_project(project_key);
+        project.merge_rate_limits(project_limits);
     }
 }
 
@@ -456,6 +519,11 @@ impl Handler<CheckEnvelope> for ProjectCache {
     }
 }
 
+/// Caches rate limits on the project of the given key.
+///
+/// Rate limits scoped to a specific key are cached on the project of that key, so that a limit of
+/// one key does not block other keys of the same project. They are dropped if that key is not in
+/// the cache. All other limits are cached on the project of `project_key`.
 pub struct UpdateRateLimits {
     project_key: ProjectKey,
     rate_limits: RateLimits,
@@ -482,8 +550,8 @@ impl Handler<UpdateRateLimits> for ProjectCache {
             project_key,
             rate_limits,
         } = message;
-        let project = self.get_or_create_project(project_key);
-        project.merge_rate_limits(rate_limits);
+
+        self.merge_rate_limits(project_key, rate_limits);
     }
 }
 /// A message containing a list of [`Metric`]s to be inserted into the aggregator.
@@ -553,6 +621,67 @@ impl Handler<MergeBuckets> for ProjectCache {
     }
 }
 
+#[derive(Debug)]
+pub struct AddMetricMeta {
+    project_key: ProjectKey,
+    meta: Vec<MetricMeta>,
+}
+
+impl AddMetricMeta {
+    /// Creates a new message containing a list of [`MetricMeta`] entries.
+    pub fn new(project_key: ProjectKey, meta: Vec<MetricMeta>) -> Self {
+        Self { project_key, meta }
+    }
+}
+
+impl Message for AddMetricMeta {
+    type Result = ();
+}
+
+impl Handler<AddMetricMeta> for ProjectCache {
+    type Result = ();
+
+    fn handle(&mut self, message: AddMetricMeta, context: &mut Self::Context) -> Self::Result {
+        let config = self.config.clone();
+        let project_key = message.project_key;
+        let project = self.get_or_create_project(project_key);
+
+        // Metadata can only be forwarded with a project state. Wait for the state to be fetched
+        // and retry once it is available.
+        let state = match project.state() {
+            Some(state) => state,
+            None => {
+                if let Response::Future(future) = project.get_or_fetch_state(false) {
+                    let address = context.address();
+                    let future = future
+                        .map(move |_| address.do_send(message))
+                        .map_err(|_| ());
+                    context.spawn(future.into_actor(self));
+                }
+                return;
+            }
+        };
+
+        let scoping = match project.scoping() {
+            Some(scoping) => scoping,
+            None => return,
+        };
+
+        if state.check_disabled(config.as_ref()).is_err() {
+            return;
+        }
+
+        let meta = project.add_metric_meta(message.meta);
+        if !meta.is_empty() {
+            EnvelopeManager::from_registry().do_send(SendMetricMeta {
+                meta,
+                scoping,
+                project_key,
+            });
+        }
+    }
+}
+
 impl Handler<FlushBuckets> for ProjectCache {
     type Result = ResponseFuture<(), Vec<Bucket>>;
 
@@ -605,3 +734,86 @@ impl Handler<FlushBuckets> for ProjectCache {
         Box::new(future)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use relay_quotas::{RateLimit, RetryAfter};
+
+    use super::*;
+
+    #[test]
+    fn test_evict_least_recently_used() {
+        relay_test::setup();
+
+        let config = Config::from_json_value(serde_json::json!({
+            "cache": {"max_projects": 2}
+        }))
+        .unwrap();
+
+        let key1 = ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap();
+        let key2 = ProjectKey::parse("e12d836b15bb49d7bbf99e64295d995b").unwrap();
+        let key3 = ProjectKey::parse("f0e1d2c3b4a5968778695a4b3c2d1e0f").unwrap();
+
+        relay_test::with_system(move || {
+            let mut cache = ProjectCache::new(Arc::new(config), None);
+            cache.get_or_create_project(key1);
+            cache.get_or_create_project(key2);
+
+            // Accessing the first project makes the second one the least recently used.
+            cache.get_or_create_project(key1);
+            cache.get_or_create_project(key3);
+
+            assert_eq!(cache.projects.len(), 2);
+            assert!(cache.projects.contains_key(&key1));
+            assert!(!cache.projects.contains_key(&key2));
+            assert!(cache.projects.contains_key(&key3));
+        });
+    }
+
+    #[test]
+    fn test_merge_key_scoped_rate_limits() {
+        relay_test::setup();
+
+        let key1 = ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap();
+        let key2 = ProjectKey::parse("e12d836b15bb49d7bbf99e64295d995b").unwrap();
+        let unknown_key = ProjectKey::parse("f0e1d2c3b4a5968778695a4b3c2d1e0f").unwrap();
+
+        let rate_limit = |scope| RateLimit {
+            categories: Default::default(),
+            scope,
+            reason_code: None,
+            retry_after: RetryAfter::from_secs(60),
+        };
+
+        relay_test::with_system(move || {
+            let mut cache = ProjectCache::new(Arc::new(Config::default()), None);
+            cache.get_or_create_project(key1);
+            cache.get_or_create_project(key2);
+
+            let mut rate_limits = RateLimits::new();
+            rate_limits.add(rate_limit(RateLimitScope::Organization(42)));
+            rate_limits.add(rate_limit(RateLimitScope::Key(key2)));
+            rate_limits.add(rate_limit(RateLimitScope::Key(unknown_key)));
+            cache.merge_rate_limits(key1, rate_limits);
+
+            // Limits of other keys are not cached on the project of the request.
+            let scopes: Vec<_> = cache.projects[&key1]
+                .rate_limits()
+                .iter()
+                .map(|limit| limit.scope.clone())
+                .collect();
+            assert_eq!(scopes, vec![RateLimitScope::Organization(42)]);
+
+            let scopes: Vec<_> = cache.projects[&key2]
+                .rate_limits()
+                .iter()
+                .map(|limit| limit.scope.clone())
+                .collect();
+            assert_eq!(scopes, vec![RateLimitScope::Key(key2)]);
+
+            // No project is created for keys that are not in the cache.
+            assert_eq!(cache.projects.len(), 2);
+            assert!(!cache.projects.contains_key(&unknown_key));
+        });
+    }
+}
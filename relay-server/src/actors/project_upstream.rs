@@ -18,7 +18,7 @@ use relay_statsd::metric;
 use crate::actors::project::ProjectState;
 use crate::actors::project_cache::{FetchProjectState, ProjectError, ProjectStateResponse};
 use crate::actors::upstream::{RequestPriority, SendQuery, UpstreamQuery, UpstreamRelay};
-use crate::statsd::{RelayCounters, RelayHistograms, RelayTimers};
+use crate::statsd::{RelayCounters, RelayGauges, RelayHistograms, RelayTimers};
 use crate::utils::{self, ErrorBoundary};
 
 #[macro_use]
@@ -146,6 +146,29 @@ impl UpstreamProjectSource {
         self.config.query_batch_interval() + self.backoff.next_backoff()
     }
 
+    /// Returns the keys of the projects to request in the next batched upstream query.
+    ///
+    /// At most `max_concurrent_queries` batches are requested at once. If
+    /// `max_concurrent_project_fetches` is configured, the number of projects is further limited
+    /// and the remaining projects stay queued for the next query.
+    fn next_projects(&self) -> Vec<ProjectKey> {
+        let mut max_projects =
+            self.config.query_batch_size() * self.config.max_concurrent_queries();
+        if let Some(max_fetches) = self.config.max_concurrent_project_fetches() {
+            max_projects = max_projects.min(max_fetches);
+        }
+
+        // Pop N items from state_channels. Intuitively, we would use
+        // `self.state_channels.drain().take(n)`, but that clears the entire hashmap regardless how
+        // much of the iterator is consumed.
+        //
+        // Instead, we have to collect the keys we want into a separate vector and pop them
+        // one-by-one.
+        (self.state_channels.keys().copied())
+            .take(max_projects)
+            .collect()
+    }
+
     /// Schedules a batched upstream query with exponential backoff.
     fn schedule_fetch(&mut self, context: &mut Context<Self>) {
         utils::run_later(self.next_backoff(), Self::fetch_states).spawn(context)
@@ -162,17 +185,7 @@ impl UpstreamProjectSource {
         }
 
         let batch_size = self.config.query_batch_size();
-        let num_batches = self.config.max_concurrent_queries();
-
-        // Pop N items from state_channels. Intuitively, we would use
-        // `self.state_channels.drain().take(n)`, but that clears the entire hashmap regardless how
-        // much of the iterator is consumed.
-        //
-        // Instead, we have to collect the keys we want into a separate vector and pop them
-        // one-by-one.
-        let projects: Vec<_> = (self.state_channels.keys().copied())
-            .take(batch_size * num_batches)
-            .collect();
+        let projects = self.next_projects();
 
         // Separate regular channels from those with the `nocache` flag. The latter go in separate
         // requests, since the upstream will block the response.
@@ -183,6 +196,7 @@ impl UpstreamProjectSource {
         let total_count = cache_channels.len() + nocache_channels.len();
 
         metric!(histogram(RelayHistograms::ProjectStatePending) = self.state_channels.len() as u64);
+        metric!(gauge(RelayGauges::ProjectStateFetchesInFlight) = total_count as u64);
 
         relay_log::debug!(
             "updating project states for {}/{} projects (attempt {})",
@@ -230,6 +244,7 @@ impl UpstreamProjectSource {
             .into_actor(self)
             .and_then(move |responses, slf, ctx| {
                 metric!(timer(RelayTimers::ProjectStateRequestDuration) = request_start.elapsed());
+                metric!(gauge(RelayGauges::ProjectStateFetchesInFlight) = 0);
 
                 for (channels_batch, response) in responses {
                     match response {
@@ -378,3 +393,30 @@ impl Handler<FetchProjectState> for UpstreamProjectSource {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_max_concurrent_fetches() {
+        let config = Config::from_json_value(serde_json::json!({
+            "cache": {"max_concurrent_fetches": 1}
+        }))
+        .unwrap();
+
+        let key1 = ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap();
+        let key2 = ProjectKey::parse("e12d836b15bb49d7bbf99e64295d995b").unwrap();
+
+        let mut source = UpstreamProjectSource::new(Arc::new(config));
+        for key in &[key1, key2] {
+            let channel = ProjectStateChannel::new(Duration::from_secs(30));
+            source.state_channels.insert(*key, channel);
+        }
+
+        // Only one project is requested, the other one stays queued for the next query.
+        let projects = source.next_projects();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(source.state_channels.len(), 2);
+    }
+}
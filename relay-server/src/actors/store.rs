@@ -18,7 +18,7 @@ use relay_common::{ProjectId, UnixTimestamp, Uuid};
 use relay_config::{Config, KafkaTopic};
 use relay_general::protocol::{self, EventId, SessionAggregates, SessionStatus, SessionUpdate};
 use relay_log::LogError;
-use relay_metrics::{Bucket, BucketValue, MetricNamespace, MetricResourceIdentifier};
+use relay_metrics::{Bucket, BucketValue, MetricMeta, MetricNamespace, MetricResourceIdentifier};
 use relay_quotas::Scoping;
 use relay_statsd::metric;
 
@@ -59,7 +59,10 @@ struct Producers {
     sessions: Producer,
     metrics_sessions: Producer,
     metrics_transactions: Producer,
+    metrics_errors: Producer,
+    metric_meta: Producer,
     profiles: Producer,
+    profile_chunks: Producer,
     replay_recordings: Producer,
 }
 
@@ -78,7 +81,10 @@ impl Producers {
             KafkaTopic::Sessions => Some(&self.sessions),
             KafkaTopic::MetricsSessions => Some(&self.metrics_sessions),
             KafkaTopic::MetricsTransactions => Some(&self.metrics_transactions),
+            KafkaTopic::MetricsErrors => Some(&self.metrics_errors),
+            KafkaTopic::MetricMeta => Some(&self.metric_meta),
             KafkaTopic::Profiles => Some(&self.profiles),
+            KafkaTopic::ProfileChunks => Some(&self.profile_chunks),
             KafkaTopic::ReplayRecordings => Some(&self.replay_recordings),
         }
     }
@@ -145,7 +151,18 @@ impl StoreForwarder {
                 &mut reused_producers,
                 KafkaTopic::MetricsTransactions,
             )?,
+            metrics_errors: make_producer(
+                &*config,
+                &mut reused_producers,
+                KafkaTopic::MetricsErrors,
+            )?,
+            metric_meta: make_producer(&*config, &mut reused_producers, KafkaTopic::MetricMeta)?,
             profiles: make_producer(&*config, &mut reused_producers, KafkaTopic::Profiles)?,
+            profile_chunks: make_producer(
+                &*config,
+                &mut reused_producers,
+                KafkaTopic::ProfileChunks,
+            )?,
             replay_recordings: make_producer(
                 &*config,
                 &mut reused_producers,
@@ -394,6 +411,7 @@ impl StoreForwarder {
         let topic = match mri.map(|mri| mri.namespace) {
             Ok(MetricNamespace::Transactions) => KafkaTopic::MetricsTransactions,
             Ok(MetricNamespace::Sessions) => KafkaTopic::MetricsSessions,
+            Ok(MetricNamespace::Errors) => KafkaTopic::MetricsErrors,
             Ok(MetricNamespace::Unsupported) | Err(_) => {
                 relay_log::with_scope(
                     |scope| {
@@ -438,6 +456,39 @@ impl StoreForwarder {
         Ok(())
     }
 
+    fn produce_metric_meta(
+        &self,
+        org_id: u64,
+        project_id: ProjectId,
+        item: &Item,
+    ) -> Result<(), StoreError> {
+        let metas = match MetricMeta::parse_all(&item.payload()) {
+            Ok(metas) => metas,
+            Err(error) => {
+                relay_log::error!("failed to parse metric meta: {}", LogError(&error));
+                metric!(counter(RelayCounters::MetricMetaParsingFailed) += 1);
+                return Ok(());
+            }
+        };
+
+        for meta in metas {
+            let message = MetricMetaKafkaMessage {
+                org_id,
+                project_id,
+                meta,
+            };
+
+            relay_log::trace!("Sending metric meta to kafka");
+            self.produce(KafkaTopic::MetricMeta, KafkaMessage::MetricMeta(message))?;
+            metric!(
+                counter(RelayCounters::ProcessingMessageProduced) += 1,
+                event_type = "metric_meta"
+            );
+        }
+
+        Ok(())
+    }
+
     fn send_session_message(&self, message: SessionKafkaMessage) -> Result<(), StoreError> {
         relay_log::trace!("Sending session item to kafka");
         self.produce(KafkaTopic::Sessions, KafkaMessage::Session(message))?;
@@ -472,6 +523,33 @@ impl StoreForwarder {
         Ok(())
     }
 
+    fn produce_profile_chunk(
+        &self,
+        organization_id: u64,
+        project_id: ProjectId,
+        key_id: Option<u64>,
+        start_time: Instant,
+        item: &Item,
+    ) -> Result<(), StoreError> {
+        let message = ProfileKafkaMessage {
+            organization_id,
+            project_id,
+            key_id,
+            received: UnixTimestamp::from_instant(start_time).as_secs(),
+            payload: item.payload(),
+        };
+        relay_log::trace!("Sending profile chunk to Kafka");
+        self.produce(
+            KafkaTopic::ProfileChunks,
+            KafkaMessage::ProfileChunk(message),
+        )?;
+        metric!(
+            counter(RelayCounters::ProcessingMessageProduced) += 1,
+            event_type = "profile_chunk"
+        );
+        Ok(())
+    }
+
     fn produce_replay_recording_chunks(
         &self,
         replay_id: EventId,
@@ -706,6 +784,14 @@ struct SessionKafkaMessage {
     retention_days: u16,
 }
 
+#[derive(Clone, Debug, Serialize)]
+struct MetricMetaKafkaMessage {
+    org_id: u64,
+    project_id: ProjectId,
+    #[serde(flatten)]
+    meta: MetricMeta,
+}
+
 #[derive(Clone, Debug, Serialize)]
 struct MetricKafkaMessage {
     org_id: u64,
@@ -738,7 +824,9 @@ enum KafkaMessage {
     UserReport(UserReportKafkaMessage),
     Session(SessionKafkaMessage),
     Metric(MetricKafkaMessage),
+    MetricMeta(MetricMetaKafkaMessage),
     Profile(ProfileKafkaMessage),
+    ProfileChunk(ProfileKafkaMessage),
     ReplayRecording(ReplayRecordingKafkaMessage),
     ReplayRecordingChunk(ReplayRecordingChunkKafkaMessage),
 }
@@ -752,7 +840,9 @@ impl KafkaMessage {
             KafkaMessage::UserReport(_) => "user_report",
             KafkaMessage::Session(_) => "session",
             KafkaMessage::Metric(_) => "metric",
+            KafkaMessage::MetricMeta(_) => "metric_meta",
             KafkaMessage::Profile(_) => "profile",
+            KafkaMessage::ProfileChunk(_) => "profile_chunk",
             KafkaMessage::ReplayRecording(_) => "replay_recording",
             KafkaMessage::ReplayRecordingChunk(_) => "replay_recording_chunk",
         }
@@ -767,7 +857,9 @@ impl KafkaMessage {
             Self::UserReport(message) => message.event_id.0,
             Self::Session(_message) => Uuid::nil(), // Explicit random partitioning for sessions
             Self::Metric(_message) => Uuid::nil(),  // TODO(ja): Determine a partitioning key
+            Self::MetricMeta(_message) => Uuid::nil(),
             Self::Profile(_message) => Uuid::nil(),
+            Self::ProfileChunk(_message) => Uuid::nil(),
             Self::ReplayRecording(message) => message.replay_id.0,
             Self::ReplayRecordingChunk(message) => message.replay_id.0,
         };
@@ -788,6 +880,9 @@ impl KafkaMessage {
             KafkaMessage::Metric(message) => {
                 serde_json::to_vec(message).map_err(StoreError::InvalidJson)
             }
+            KafkaMessage::MetricMeta(message) => {
+                serde_json::to_vec(message).map_err(StoreError::InvalidJson)
+            }
             _ => rmp_serde::to_vec_named(&self).map_err(StoreError::InvalidMsgPack),
         }
     }
@@ -878,6 +973,9 @@ impl Handler<StoreEnvelope> for StoreForwarder {
                 ItemType::MetricBuckets => {
                     self.produce_metrics(scoping.organization_id, scoping.project_id, item)?
                 }
+                ItemType::MetricMeta => {
+                    self.produce_metric_meta(scoping.organization_id, scoping.project_id, item)?
+                }
                 ItemType::Profile => self.produce_profile(
                     scoping.organization_id,
                     scoping.project_id,
@@ -885,6 +983,13 @@ impl Handler<StoreEnvelope> for StoreForwarder {
                     start_time,
                     item,
                 )?,
+                ItemType::ProfileChunk => self.produce_profile_chunk(
+                    scoping.organization_id,
+                    scoping.project_id,
+                    scoping.key_id,
+                    start_time,
+                    item,
+                )?,
                 ItemType::ReplayRecording => {
                     let replay_recording = self.produce_replay_recording_chunks(
                         event_id.ok_or(StoreError::NoEventId)?,
@@ -35,7 +35,7 @@ use serde::ser::Serialize;
 
 use relay_auth::{RegisterChallenge, RegisterRequest, RegisterResponse, Registration};
 use relay_common::{tryf, RetryBackoff};
-use relay_config::{Config, RelayMode};
+use relay_config::{Config, RelayMode, UpstreamDescriptor};
 use relay_log::{self, LogError};
 use relay_quotas::{
     DataCategories, QuotaScope, RateLimit, RateLimitScope, RateLimits, RetryAfter, Scoping,
@@ -43,7 +43,7 @@ use relay_quotas::{
 use relay_statsd::metric;
 
 use crate::http::{HttpError, Request, RequestBuilder, Response, StatusCode};
-use crate::statsd::{RelayHistograms, RelayTimers};
+use crate::statsd::{RelayCounters, RelayHistograms, RelayTimers};
 use crate::utils::{self, ApiErrorResponse, IntoTracked, RelayErrorAction, TrackedFutureFinished};
 
 #[derive(Fail, Debug)]
@@ -62,8 +62,11 @@ pub enum UpstreamRequestError {
     #[fail(display = "upstream requests rate limited")]
     RateLimited(UpstreamRateLimits),
 
+    /// The upstream responded with an error status code.
+    ///
+    /// Contains the parsed error response and the raw response body.
     #[fail(display = "upstream request returned error {}", _0)]
-    ResponseError(StatusCode, #[cause] ApiErrorResponse),
+    ResponseError(StatusCode, #[cause] ApiErrorResponse, String),
 
     #[fail(display = "channel closed")]
     ChannelClosed,
@@ -74,7 +77,7 @@ impl UpstreamRequestError {
     fn is_network_error(&self) -> bool {
         match self {
             Self::SendFailed(_) => true,
-            Self::ResponseError(code, _) => matches!(code.as_u16(), 502 | 503 | 504),
+            Self::ResponseError(code, _, _) => matches!(code.as_u16(), 502 | 503 | 504),
             Self::Http(http) => http.is_network_error(),
             _ => false,
         }
@@ -85,7 +88,7 @@ impl UpstreamRequestError {
     /// This Relay should cease communication with the upstream and may shut down.
     fn is_permanent_rejection(&self) -> bool {
         match self {
-            Self::ResponseError(status_code, response) => {
+            Self::ResponseError(status_code, response, _) => {
                 *status_code == StatusCode::FORBIDDEN
                     && response.relay_action() == RelayErrorAction::Stop
             }
@@ -103,11 +106,19 @@ impl UpstreamRequestError {
             // Rate limits are a special case of `ResponseError(429, _)`.
             Self::RateLimited(_) => true,
             // Everything except network errors indicates the upstream has handled this request.
-            Self::ResponseError(_, _) | Self::Http(_) => !self.is_network_error(),
+            Self::ResponseError(_, _, _) | Self::Http(_) => !self.is_network_error(),
             // Remaining kinds indicate a failure to send the request.
             Self::NoCredentials | Self::SendFailed(_) | Self::ChannelClosed => false,
         }
     }
+
+    /// Returns the raw body of an upstream error response, if available.
+    pub fn response_body(&self) -> Option<&str> {
+        match self {
+            Self::ResponseError(_, _, body) => Some(body),
+            _ => None,
+        }
+    }
 }
 
 /// Represents the current auth state.
@@ -348,23 +359,29 @@ fn handle_response(
     };
 
     // At this point, we consume the Response. This means we need to consume the response
-    // payload stream, regardless of the status code. Parsing the JSON body may fail, which is a
-    // non-fatal failure as the upstream is not expected to always include a valid JSON response.
-    let future = response
-        .json(max_response_size)
-        .then(move |json_result: Result<_, HttpError>| {
-            if let Some(upstream_limits) = upstream_limits {
-                Err(UpstreamRequestError::RateLimited(upstream_limits))
-            } else {
-                // Coerce the result into an empty `ApiErrorResponse` if parsing JSON did not succeed.
-                let api_response = json_result.unwrap_or_default();
-                Err(UpstreamRequestError::ResponseError(status, api_response))
-            }
-        });
+    // payload stream, regardless of the status code. Reading the body may fail, which is a
+    // non-fatal failure as the upstream is not expected to always include a valid response.
+    let future = response.bytes(max_response_size).then(move |body_result| {
+        if let Some(upstream_limits) = upstream_limits {
+            Err(UpstreamRequestError::RateLimited(upstream_limits))
+        } else {
+            Err(response_error(status, &body_result.unwrap_or_default()))
+        }
+    });
 
     Box::new(future)
 }
 
+/// Creates a `ResponseError` from the status code and raw body of an upstream response.
+///
+/// The body is parsed as `ApiErrorResponse` if possible, and is otherwise coerced into an empty
+/// response. The raw body is retained in any case.
+fn response_error(status: StatusCode, body: &[u8]) -> UpstreamRequestError {
+    let api_response = serde_json::from_slice(body).unwrap_or_default();
+    let body = String::from_utf8_lossy(body).into_owned();
+    UpstreamRequestError::ResponseError(status, api_response, body)
+}
+
 pub struct UpstreamRelay {
     /// backoff policy for the registration messages
     auth_backoff: RetryBackoff,
@@ -511,6 +528,16 @@ impl UpstreamRelay {
         });
     }
 
+    /// Probes the upstream for liveness, unless a connection check is already running.
+    fn send_heartbeat(&mut self, ctx: &mut Context<Self>) {
+        if self.is_network_outage() {
+            return;
+        }
+
+        let request = EnqueuedRequest::new(SendHeartbeat);
+        self.enqueue(request, ctx, EnqueuePosition::Front);
+    }
+
     /// Records an occurrence of a network error.
     ///
     /// If the network errors persist throughout the http outage grace period, an outage is
@@ -530,15 +557,23 @@ impl UpstreamRelay {
     }
 
     fn send_request(&mut self, mut request: EnqueuedRequest, ctx: &mut Context<Self>) {
-        let uri = self
-            .config
-            .upstream_descriptor()
-            .get_url(request.request.path().as_ref());
-
-        let host_header = self
-            .config
-            .http_host_header()
-            .unwrap_or_else(|| self.config.upstream_descriptor().host());
+        let (uri, host_header) = match request.request.upstream() {
+            Some(upstream) => (
+                upstream.get_url(request.request.path().as_ref()),
+                upstream.host().to_owned(),
+            ),
+            None => {
+                let upstream = self.config.upstream_descriptor();
+                let host_header = self
+                    .config
+                    .http_host_header()
+                    .unwrap_or_else(|| upstream.host());
+                (
+                    upstream.get_url(request.request.path().as_ref()),
+                    host_header.to_owned(),
+                )
+            }
+        };
 
         let method =
             reqwest::Method::from_bytes(request.request.method().as_ref().as_bytes()).unwrap();
@@ -618,7 +653,7 @@ impl UpstreamRelay {
                 sc = client_response.status();
                 (sc.as_str(), "success")
             }
-            Err(UpstreamRequestError::ResponseError(status_code, _)) => {
+            Err(UpstreamRequestError::ResponseError(status_code, _, _)) => {
                 (status_code.as_str(), "response_error")
             }
             Err(UpstreamRequestError::Http(HttpError::Io(_))) => ("-", "payload_failed"),
@@ -672,14 +707,19 @@ impl UpstreamRelay {
         ctx: &mut Context<Self>,
     ) {
         UpstreamRelay::meter_result(send_start, &request, &send_result);
-        if matches!(send_result, Err(ref err) if err.is_network_error()) {
+
+        // Requests to secondary upstreams do not affect the connection state of the primary.
+        let is_primary = request.request.upstream().is_none();
+        let is_network_error = matches!(send_result, Err(ref err) if err.is_network_error());
+
+        if is_primary && is_network_error {
             self.handle_network_error(ctx);
 
             if request.request.retry() {
                 request.previous_retries += 1;
                 return self.enqueue(request, ctx, EnqueuePosition::Back);
             }
-        } else {
+        } else if is_primary {
             // we managed a request without a network error, reset the first time we got a network
             // error and resume sending events.
             self.reset_network_error();
@@ -763,6 +803,10 @@ impl Actor for UpstreamRelay {
         if self.should_authenticate() {
             context.notify(Authenticate);
         }
+
+        if let Some(interval) = self.config.upstream_heartbeat_interval() {
+            context.run_interval(interval, Self::send_heartbeat);
+        }
     }
 
     fn stopped(&mut self, _ctx: &mut Self::Context) {
@@ -1018,6 +1062,61 @@ impl UpstreamRequest for GetHealthcheck {
     }
 }
 
+/// Periodically probes the upstream server, see [`Config::upstream_heartbeat_interval`].
+///
+/// Unlike [`GetHealthcheck`], heartbeats are sent during regular operation. Network errors count
+/// towards the outage grace period like any other request.
+struct SendHeartbeat;
+
+impl UpstreamRequest for SendHeartbeat {
+    fn method(&self) -> Method {
+        Method::GET
+    }
+
+    fn path(&self) -> Cow<'_, str> {
+        Cow::Borrowed("/api/0/relays/live/")
+    }
+
+    fn retry(&self) -> bool {
+        false
+    }
+
+    fn priority(&self) -> RequestPriority {
+        RequestPriority::Low
+    }
+
+    fn set_relay_id(&self) -> bool {
+        true
+    }
+
+    fn intercept_status_errors(&self) -> bool {
+        true
+    }
+
+    fn build(&mut self, builder: RequestBuilder) -> Result<Request, HttpError> {
+        builder.finish()
+    }
+
+    fn respond(
+        &mut self,
+        result: Result<Response, UpstreamRequestError>,
+    ) -> ResponseFuture<(), ()> {
+        let future: ResponseFuture<_, _> = match result {
+            Ok(response) => Box::new(response.consume().map_err(UpstreamRequestError::Http)),
+            Err(err) => Box::new(future::err(err)),
+        };
+
+        Box::new(future.then(|result| {
+            let result = if result.is_ok() { "success" } else { "failure" };
+            metric!(
+                counter(RelayCounters::UpstreamHeartbeat) += 1,
+                result = result
+            );
+            Ok(())
+        }))
+    }
+}
+
 pub trait ResponseTransformer: 'static {
     type Result: 'static + IntoFuture;
 
@@ -1081,6 +1180,14 @@ pub trait UpstreamRequest: Send {
         true
     }
 
+    /// A secondary upstream to send this request to instead of the configured upstream.
+    ///
+    /// Network errors of such requests are not retried and do not count towards network outages
+    /// of the primary upstream. Defaults to `None`.
+    fn upstream(&self) -> Option<&UpstreamDescriptor<'_>> {
+        None
+    }
+
     /// Called whenever the request will be send over HTTP (possible multiple times)
     fn build(&mut self, builder: RequestBuilder) -> Result<Request, HttpError>;
 
@@ -1276,3 +1383,37 @@ impl UpstreamQuery for RegisterResponse {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_error_body() {
+        let body = br#"{"detail":"invalid event envelope"}"#;
+        let error = response_error(StatusCode::BAD_REQUEST, body);
+
+        assert_eq!(
+            error.response_body(),
+            Some(r#"{"detail":"invalid event envelope"}"#)
+        );
+        assert_eq!(
+            error.to_string(),
+            "upstream request returned error 400 Bad Request"
+        );
+        assert!(error.is_received());
+    }
+
+    #[test]
+    fn test_response_error_body_not_json() {
+        let error = response_error(StatusCode::BAD_REQUEST, b"bad request");
+
+        assert_eq!(error.response_body(), Some("bad request"));
+        match error {
+            UpstreamRequestError::ResponseError(_, response, _) => {
+                assert_eq!(response.to_string(), "no error details")
+            }
+            _ => panic!("expected a response error"),
+        }
+    }
+}
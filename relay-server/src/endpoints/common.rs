@@ -12,6 +12,7 @@ use futures::prelude::*;
 use serde::Deserialize;
 
 use relay_common::{clone, tryf};
+use relay_config::Config;
 use relay_general::protocol::{EventId, EventType};
 use relay_log::LogError;
 use relay_quotas::RateLimits;
@@ -101,6 +102,9 @@ impl BadStoreRequest {
 
             BadStoreRequest::QueueFailed(event_error) => match event_error {
                 QueueEnvelopeError::TooManyEnvelopes => Outcome::Invalid(DiscardReason::Internal),
+                QueueEnvelopeError::EmptyEnvelope => Outcome::Invalid(DiscardReason::EmptyEnvelope),
+                // The request is retried by the client, so the data is not dropped.
+                QueueEnvelopeError::ReadOnly => return None,
             },
             BadStoreRequest::ProjectFailed(project_error) => match project_error {
                 ProjectError::FetchFailed => Outcome::Invalid(DiscardReason::ProjectState),
@@ -152,12 +156,18 @@ impl ResponseError for BadStoreRequest {
                 ProjectError::ScheduleFailed => HttpResponse::ServiceUnavailable().json(&body),
             },
 
-            BadStoreRequest::ScheduleFailed | BadStoreRequest::QueueFailed(_) => {
+            BadStoreRequest::ScheduleFailed
+            | BadStoreRequest::QueueFailed(QueueEnvelopeError::TooManyEnvelopes) => {
                 // These errors indicate that something's wrong with our actor system, most likely
                 // mailbox congestion or a faulty shutdown. Indicate an unavailable service to the
                 // client. It might retry event submission at a later time.
                 HttpResponse::ServiceUnavailable().json(&body)
             }
+            BadStoreRequest::QueueFailed(QueueEnvelopeError::ReadOnly) => {
+                // Relay does not accept data during upstream maintenance. Clients are expected to
+                // retry once the maintenance is over.
+                HttpResponse::ServiceUnavailable().json(&body)
+            }
             BadStoreRequest::EventRejected(_) => {
                 // The event has been discarded, which is generally indicated with a 403 error.
                 // Originally, Sentry also used this status code for event filters, but these are
@@ -306,6 +316,29 @@ pub fn cors(app: ServiceApp) -> CorsBuilder<ServiceState> {
     builder
 }
 
+/// Removes unknown items from the envelope and checks whether there are items left to handle.
+///
+/// Envelopes submitted without any items fail with [`QueueEnvelopeError::EmptyEnvelope`] if
+/// [`Config::reject_empty_envelopes`] is enabled. Envelopes that are empty only after removing
+/// unknown items fail with the control flow error [`BadStoreRequest::EmptyEnvelope`], which still
+/// results in a `200 OK` response.
+fn filter_envelope_items(config: &Config, envelope: &mut Envelope) -> Result<(), BadStoreRequest> {
+    if config.reject_empty_envelopes() && envelope.is_empty() {
+        return Err(BadStoreRequest::QueueFailed(
+            QueueEnvelopeError::EmptyEnvelope,
+        ));
+    }
+
+    utils::remove_unknown_items(config, envelope);
+
+    if envelope.is_empty() {
+        // envelope is empty, cannot send outcomes
+        Err(BadStoreRequest::EmptyEnvelope)
+    } else {
+        Ok(())
+    }
+}
+
 /// Handles Sentry events.
 ///
 /// Sentry events may come either directly from a http request ( the store endpoint calls this
@@ -338,9 +371,16 @@ where
         version = &format!("{}", version)
     );
 
+    // Reject requests before checking the project, which could trigger upstream fetches.
+    let config = request.state().config();
+    if config.read_only_mode() {
+        tryf!(Err(BadStoreRequest::QueueFailed(
+            QueueEnvelopeError::ReadOnly
+        )));
+    }
+
     let project_key = meta.public_key();
     let start_time = meta.start_time();
-    let config = request.state().config();
 
     let envelope_context = Rc::new(RefCell::new(EnvelopeContext::from_request(&meta)));
 
@@ -352,14 +392,8 @@ where
             // If configured, remove unknown items at the very beginning. If the envelope is
             // empty, we fail the request with a special control flow error to skip checks and
             // queueing, that still results in a `200 OK` response.
-            utils::remove_unknown_items(&config, &mut envelope);
-
-            if envelope.is_empty() {
-                // envelope is empty, cannot send outcomes
-                Err(BadStoreRequest::EmptyEnvelope)
-            } else {
-                Ok(envelope)
-            }
+            filter_envelope_items(&config, &mut envelope)?;
+            Ok(envelope)
         }))
         .and_then(clone!(envelope_context, |envelope| {
             ProjectCache::from_registry()
@@ -489,8 +523,57 @@ pub fn normpath(route: &str) -> String {
 
 #[cfg(test)]
 mod tests {
+    use crate::envelope::Item;
+
     use super::*;
 
+    fn create_envelope() -> Envelope {
+        let dsn = "https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42"
+            .parse()
+            .unwrap();
+
+        Envelope::from_request(None, RequestMeta::new(dsn))
+    }
+
+    #[test]
+    fn test_reject_empty_envelope() {
+        let config = Config::from_json_value(serde_json::json!({
+            "routing": {
+                "reject_empty_envelopes": true,
+            }
+        }))
+        .unwrap();
+
+        let mut envelope = create_envelope();
+        assert!(matches!(
+            filter_envelope_items(&config, &mut envelope),
+            Err(BadStoreRequest::QueueFailed(
+                QueueEnvelopeError::EmptyEnvelope
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_accept_envelope_empty_after_unknown_items() {
+        let config = Config::from_json_value(serde_json::json!({
+            "routing": {
+                "accept_unknown_items": false,
+                "reject_empty_envelopes": true,
+            }
+        }))
+        .unwrap();
+
+        let mut envelope = create_envelope();
+        envelope.add_item(Item::new(ItemType::Unknown("foo".to_owned())));
+
+        // The control flow error is turned into a successful response.
+        assert!(matches!(
+            filter_envelope_items(&config, &mut envelope),
+            Err(BadStoreRequest::EmptyEnvelope)
+        ));
+        assert!(envelope.is_empty());
+    }
+
     #[test]
     fn test_normpath() {
         assert_eq!(
@@ -554,4 +637,14 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_read_only_mode() {
+        let error = BadStoreRequest::QueueFailed(QueueEnvelopeError::ReadOnly);
+        assert!(error.to_outcome().is_none());
+        assert_eq!(
+            error.error_response().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
 }
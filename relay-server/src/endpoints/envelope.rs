@@ -1,30 +1,93 @@
 //! Handles envelope store requests.
 
+use std::time::Duration;
+
 use actix::prelude::*;
 use actix_web::{HttpRequest, HttpResponse};
-use futures::Future;
+use futures::{future, Future};
 use serde::Serialize;
 
+use relay_auth::RelayId;
+use relay_config::RelayInfo;
 use relay_general::protocol::EventId;
 
+use crate::actors::relays::{GetRelay, RelayCache};
 use crate::body::StoreBody;
 use crate::endpoints::common::{self, BadStoreRequest};
 use crate::envelope::Envelope;
 use crate::extractors::{EnvelopeMeta, RequestMeta};
 use crate::service::{ServiceApp, ServiceState};
 
+/// The maximum age of a Relay signature on a forwarded envelope.
+const MAX_SIGNATURE_AGE: Duration = Duration::from_secs(5 * 60);
+
+/// Resolves the Relay that sent the request along with the signature of the request body.
+///
+/// Resolves to `None` if the request has no Relay id or signature, or if the Relay is unknown.
+fn signing_relay(
+    request: &HttpRequest<ServiceState>,
+) -> ResponseFuture<Option<(RelayInfo, String)>, BadStoreRequest> {
+    let headers = request.headers();
+
+    let relay_id = headers
+        .get("X-Sentry-Relay-Id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<RelayId>().ok());
+
+    let signature = headers
+        .get("X-Sentry-Relay-Signature")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned);
+
+    let (relay_id, signature) = match (relay_id, signature) {
+        (Some(relay_id), Some(signature)) => (relay_id, signature),
+        _ => return Box::new(future::ok(None)),
+    };
+
+    let future = RelayCache::from_registry()
+        .send(GetRelay { relay_id })
+        .then(move |result| {
+            let relay = match result {
+                Ok(Ok(result)) => result.relay,
+                _ => None,
+            };
+
+            Ok::<_, BadStoreRequest>(relay.map(|relay| (relay, signature)))
+        });
+
+    Box::new(future)
+}
+
 fn extract_envelope(
     request: &HttpRequest<ServiceState>,
-    meta: RequestMeta,
+    mut meta: RequestMeta,
 ) -> ResponseFuture<Envelope, BadStoreRequest> {
-    let max_payload_size = request.state().config().max_envelope_size();
+    let config = request.state().config();
+    let max_payload_size = config.max_envelope_size();
+
+    let signing_relay: ResponseFuture<_, _> = if config.require_trusted_relay_signature() {
+        signing_relay(request)
+    } else {
+        Box::new(future::ok(None))
+    };
+
     let future = StoreBody::new(request, max_payload_size)
         .map_err(BadStoreRequest::PayloadError)
-        .and_then(move |data| {
+        .join(signing_relay)
+        .and_then(move |(data, signing_relay)| {
             if data.is_empty() {
                 return Err(BadStoreRequest::EmptyBody);
             }
 
+            // Verify the signature once here, so that only the verified key is kept with the
+            // envelope. Whether the key is trusted is checked against the project state later.
+            if let Some((relay, signature)) = signing_relay {
+                let public_key = relay.public_key;
+                if public_key.verify_timestamp(&data, &signature, Some(MAX_SIGNATURE_AGE)) {
+                    meta.set_signed_by(public_key);
+                }
+            }
+
             Envelope::parse_request(data, meta).map_err(BadStoreRequest::InvalidEnvelope)
         });
 
@@ -1,21 +1,88 @@
 //! Returns captured events.
 
 use actix_web::actix::*;
-use actix_web::{http::Method, HttpResponse, Path};
+use actix_web::http::{header, Method};
+use actix_web::{HttpRequest, HttpResponse, Path};
 use futures::future::Future;
+use serde::Serialize;
 
 use crate::actors::envelopes::{EnvelopeManager, GetCapturedEnvelope};
-use crate::envelope;
-use crate::service::ServiceApp;
+use crate::envelope::{self, Envelope};
+use crate::service::{ServiceApp, ServiceState};
+use crate::utils::EnvelopeSummary;
 
 use relay_general::protocol::EventId;
+use relay_quotas::DataCategory;
+
+/// Summary of a single item in a captured envelope.
+#[derive(Debug, Serialize)]
+struct CapturedItemSummary {
+    #[serde(rename = "type")]
+    ty: String,
+    size: usize,
+}
+
+/// Structured summary of a captured envelope, returned for `Accept: application/json`.
+#[derive(Debug, Serialize)]
+struct CapturedEnvelopeSummary {
+    event_id: Option<EventId>,
+    event_category: Option<DataCategory>,
+    attachment_quantity: usize,
+    session_quantity: usize,
+    profile_quantity: usize,
+    items: Vec<CapturedItemSummary>,
+}
+
+impl CapturedEnvelopeSummary {
+    fn new(envelope: &Envelope) -> Self {
+        let summary = EnvelopeSummary::compute(envelope);
+
+        let items = envelope
+            .items()
+            .map(|item| CapturedItemSummary {
+                ty: item.ty().to_string(),
+                size: item.len(),
+            })
+            .collect();
+
+        Self {
+            event_id: envelope.event_id(),
+            event_category: summary.event_category,
+            attachment_quantity: summary.attachment_quantity,
+            session_quantity: summary.session_quantity,
+            profile_quantity: summary.profile_quantity,
+            items,
+        }
+    }
+}
+
+/// Returns `true` if the request's `Accept` header asks for a JSON response.
+fn accepts_json(request: &HttpRequest<ServiceState>) -> bool {
+    request
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |accept| {
+            accept
+                .split(',')
+                .filter_map(|media_range| media_range.split(';').next())
+                .any(|media_type| media_type.trim() == "application/json")
+        })
+}
+
+fn get_captured_event(
+    (request, event_id): (HttpRequest<ServiceState>, Path<EventId>),
+) -> ResponseFuture<HttpResponse, MailboxError> {
+    let json = accepts_json(&request);
 
-fn get_captured_event(event_id: Path<EventId>) -> ResponseFuture<HttpResponse, MailboxError> {
     let future = EnvelopeManager::from_registry()
         .send(GetCapturedEnvelope {
             event_id: *event_id,
         })
-        .map(|captured_event| match captured_event {
+        .map(move |captured_event| match captured_event {
+            Some(Ok(envelope)) if json => {
+                HttpResponse::Ok().json(CapturedEnvelopeSummary::new(&envelope))
+            }
             Some(Ok(envelope)) => HttpResponse::Ok()
                 .content_type(envelope::CONTENT_TYPE)
                 .body(envelope.to_vec().unwrap()),
@@ -34,3 +101,43 @@ pub fn configure_app(app: ServiceApp) -> ServiceApp {
         r.method(Method::GET).with(get_captured_event);
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bytes::Bytes;
+
+    use crate::envelope::{ContentType, Item, ItemType};
+
+    #[test]
+    fn test_captured_envelope_summary() {
+        let bytes = Bytes::from(
+            "{\"event_id\":\"9ec79c33ec9942ab8353589fcb2e04dc\",\"dsn\":\"https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42\"}",
+        );
+        let mut envelope = Envelope::parse_bytes(bytes).unwrap();
+
+        let mut item = Item::new(ItemType::Event);
+        item.set_payload(ContentType::Json, r#"{"message": "hello"}"#);
+        envelope.add_item(item);
+
+        let mut item = Item::new(ItemType::Attachment);
+        item.set_payload(ContentType::OctetStream, "0123456789");
+        envelope.add_item(item);
+
+        let summary = CapturedEnvelopeSummary::new(&envelope);
+        let json = serde_json::to_value(&summary).unwrap();
+
+        let item_types: Vec<_> = json["items"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["type"].as_str().unwrap())
+            .collect();
+
+        assert_eq!(item_types, vec!["event", "attachment"]);
+        assert_eq!(json["event_id"], "9ec79c33ec9942ab8353589fcb2e04dc");
+        assert_eq!(json["event_category"], "error");
+        assert_eq!(json["attachment_quantity"], 10);
+    }
+}
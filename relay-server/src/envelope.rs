@@ -40,6 +40,7 @@ use chrono::{DateTime, Utc};
 use failure::Fail;
 use relay_common::UnixTimestamp;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use smallvec::SmallVec;
 
 use relay_general::protocol::{EventId, EventType};
@@ -99,10 +100,14 @@ pub enum ItemType {
     Metrics,
     /// Buckets of preaggregated metrics encoded as JSON.
     MetricBuckets,
+    /// Metadata describing custom metrics, such as their unit, encoded as JSON.
+    MetricMeta,
     /// Client internal report (eg: outcomes).
     ClientReport,
     /// Profile event payload encoded in JSON
     Profile,
+    /// Chunk of a continuous profile encoded in JSON, independent of transactions.
+    ProfileChunk,
     /// Replay Recording data
     ReplayRecording,
     /// A new item type that is yet unknown by this version of Relay.
@@ -142,8 +147,10 @@ impl fmt::Display for ItemType {
             Self::Sessions => write!(f, "sessions"),
             Self::Metrics => write!(f, "metrics"),
             Self::MetricBuckets => write!(f, "metric_buckets"),
+            Self::MetricMeta => write!(f, "metric_meta"),
             Self::ClientReport => write!(f, "client_report"),
             Self::Profile => write!(f, "profile"),
+            Self::ProfileChunk => write!(f, "profile_chunk"),
             Self::ReplayRecording => write!(f, "replay_recording"),
             Self::Unknown(s) => s.fmt(f),
         }
@@ -167,8 +174,10 @@ impl std::str::FromStr for ItemType {
             "sessions" => Self::Sessions,
             "metrics" => Self::Metrics,
             "metric_buckets" => Self::MetricBuckets,
+            "metric_meta" => Self::MetricMeta,
             "client_report" => Self::ClientReport,
             "profile" => Self::Profile,
+            "profile_chunk" => Self::ProfileChunk,
             "replay_recording" => Self::ReplayRecording,
             other => Self::Unknown(other.to_owned()),
         })
@@ -363,6 +372,13 @@ pub enum AttachmentType {
     /// [`symbolic_unreal::Unreal4LogEntry`]: https://docs.rs/symbolic/*/symbolic/unreal/struct.Unreal4LogEntry.html
     #[serde(rename = "unreal.logs")]
     UnrealLogs,
+
+    /// A JSON description of the view hierarchy of a mobile application at the time of a crash.
+    ///
+    /// View hierarchies are validated in processing mode and scrubbed as JSON rather than as
+    /// binary data.
+    #[serde(rename = "event.view_hierarchy")]
+    ViewHierarchy,
 }
 
 impl Default for AttachmentType {
@@ -610,7 +626,8 @@ impl Item {
                 | AttachmentType::Breadcrumbs => true,
                 AttachmentType::Attachment
                 | AttachmentType::UnrealContext
-                | AttachmentType::UnrealLogs => false,
+                | AttachmentType::UnrealLogs
+                | AttachmentType::ViewHierarchy => false,
             },
 
             // Form data items may contain partial event payloads, but those are only ever valid if
@@ -624,9 +641,11 @@ impl Item {
             | ItemType::Sessions
             | ItemType::Metrics
             | ItemType::MetricBuckets
+            | ItemType::MetricMeta
             | ItemType::ClientReport
             | ItemType::ReplayRecording
-            | ItemType::Profile => false,
+            | ItemType::Profile
+            | ItemType::ProfileChunk => false,
 
             // The unknown item type can observe any behavior, most likely there are going to be no
             // item types added that create events.
@@ -651,9 +670,11 @@ impl Item {
             ItemType::Sessions => false,
             ItemType::Metrics => false,
             ItemType::MetricBuckets => false,
+            ItemType::MetricMeta => false,
             ItemType::ClientReport => false,
             ItemType::ReplayRecording => false,
             ItemType::Profile => true,
+            ItemType::ProfileChunk => false,
 
             // Since this Relay cannot interpret the semantics of this item, it does not know
             // whether it requires an event or not. Depending on the strategy, this can cause two
@@ -882,6 +903,30 @@ impl Envelope {
         self.items.iter()
     }
 
+    /// Returns `true` if any item in this envelope creates an event.
+    ///
+    /// See [`Item::creates_event`] for which items create events.
+    pub fn creates_event(&self) -> bool {
+        self.items().any(Item::creates_event)
+    }
+
+    /// Returns a compact summary of the item types in this envelope for logging.
+    ///
+    /// Item types are listed alphabetically with the number of their occurrences, for example
+    /// `attachment:2,event:1`.
+    pub fn item_types(&self) -> String {
+        let mut counts = BTreeMap::<String, usize>::new();
+        for item in self.items() {
+            *counts.entry(item.ty().to_string()).or_default() += 1;
+        }
+
+        counts
+            .iter()
+            .map(|(ty, count)| format!("{}:{}", ty, count))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
     /// Returns a mutable iterator over items in this envelope.
     ///
     /// Note that iteration order may change when using `take_item`.
@@ -986,6 +1031,25 @@ impl Envelope {
         Ok(())
     }
 
+    /// Computes a SHA-256 hash over the headers and payloads of all items.
+    ///
+    /// Envelope headers are not included, so the hash is stable across changes to `sent_at` and
+    /// other metadata. The hash is returned as lowercase hex string.
+    pub fn payload_hash(&self) -> Result<String, EnvelopeError> {
+        let mut hasher = Sha256::new();
+
+        for item in &self.items {
+            let headers =
+                serde_json::to_vec(&item.headers).map_err(EnvelopeError::HeaderIoFailed)?;
+            hasher.input(&headers);
+            hasher.input(b"\n");
+            hasher.input(&item.payload);
+            hasher.input(b"\n");
+        }
+
+        Ok(format!("{:x}", hasher.result()))
+    }
+
     /// Serializes this envelope into a buffer.
     pub fn to_vec(&self) -> Result<Vec<u8>, EnvelopeError> {
         let mut vec = Vec::new(); // TODO: Preallocate?
@@ -1157,6 +1221,17 @@ mod tests {
         assert_eq!(items[0].ty(), &ItemType::Attachment);
     }
 
+    #[test]
+    fn test_envelope_creates_event() {
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta());
+        envelope.add_item(Item::new(ItemType::Event));
+        assert!(envelope.creates_event());
+
+        let mut envelope = Envelope::from_request(None, request_meta());
+        envelope.add_item(Item::new(ItemType::Session));
+        assert!(!envelope.creates_event());
+    }
+
     #[test]
     fn test_envelope_take_item() {
         let event_id = EventId::new();
@@ -1471,6 +1546,25 @@ mod tests {
         Envelope::parse_request(bytes, request_meta()).unwrap();
     }
 
+    #[test]
+    fn test_parse_request_custom_header() {
+        let bytes = Bytes::from(
+            "{\"event_id\":\"9ec79c33ec9942ab8353589fcb2e04dc\",\"x-tenant-id\":\"acme\"}",
+        );
+        let envelope = Envelope::parse_request(bytes, request_meta()).unwrap();
+        assert_eq!(
+            envelope.get_header("x-tenant-id"),
+            Some(&Value::from("acme"))
+        );
+
+        let serialized = envelope.to_vec().unwrap();
+        let envelope = Envelope::parse_bytes(Bytes::from(serialized)).unwrap();
+        assert_eq!(
+            envelope.get_header("x-tenant-id"),
+            Some(&Value::from("acme"))
+        );
+    }
+
     #[test]
     fn test_serialize_envelope_empty() {
         let event_id = EventId("9ec79c33ec9942ab8353589fcb2e04dc".parse().unwrap());
@@ -1558,4 +1652,74 @@ mod tests {
             assert_eq!(item.ty(), &ItemType::Attachment);
         }
     }
+
+    #[test]
+    fn test_split_envelope_sample_rate() {
+        let bytes = Bytes::from(
+            "\
+             {\"event_id\":\"9ec79c33ec9942ab8353589fcb2e04dc\",\"dsn\":\"https://e12d836b15bb49d7bbf99e64295d995b:@sentry.io/42\",\"trace\":{\"trace_id\":\"89143b0763095bd9c9955e8175d1fb23\",\"public_key\":\"e12d836b15bb49d7bbf99e64295d995b\",\"sample_rate\":\"0.5\"}}\n\
+             {\"type\":\"event\"}\n\
+             {}\n\
+             {\"type\":\"session\"}\n\
+             {}\n\
+             ",
+        );
+
+        let mut envelope = Envelope::parse_bytes(bytes).unwrap();
+        let event_envelope = envelope.split_by(Item::requires_event).unwrap();
+
+        // The remaining envelope only contains the session, but retains the trace sample rate.
+        assert_eq!(envelope.len(), 1);
+        assert_eq!(envelope.items().next().unwrap().ty(), &ItemType::Session);
+        assert_eq!(envelope.trace_context().unwrap().sample_rate, Some(0.5));
+        assert_eq!(
+            event_envelope.trace_context().unwrap().sample_rate,
+            Some(0.5)
+        );
+
+        // The sample rate is retained when the envelope is serialized for forwarding.
+        let serialized = String::from_utf8(envelope.to_vec().unwrap()).unwrap();
+        assert!(serialized.contains(r#""sample_rate":0.5"#));
+    }
+
+    #[test]
+    fn test_payload_hash() {
+        let create_envelope = |payload: &'static str| {
+            let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta());
+            envelope.add_item({
+                let mut item = Item::new(ItemType::Event);
+                item.set_payload(ContentType::Json, payload);
+                item
+            });
+            envelope
+        };
+
+        let mut envelope = create_envelope(r#"{"message": "hello world"}"#);
+        let hash = envelope.payload_hash().unwrap();
+        assert_eq!(hash.len(), 64);
+
+        // Envelope headers do not affect the hash.
+        let mut other = create_envelope(r#"{"message": "hello world"}"#);
+        other.set_sent_at(Utc::now());
+        assert_eq!(other.payload_hash().unwrap(), hash);
+
+        let different = create_envelope(r#"{"message": "goodbye"}"#);
+        assert_ne!(different.payload_hash().unwrap(), hash);
+
+        envelope.set_header("payload_hash", hash.clone());
+        let serialized = String::from_utf8(envelope.to_vec().unwrap()).unwrap();
+        assert!(serialized.contains(&format!(r#""payload_hash":"{}""#, hash)));
+    }
+
+    #[test]
+    fn test_item_types() {
+        let mut envelope = Envelope::from_request(Some(EventId::new()), request_meta());
+        assert_eq!(envelope.item_types(), "");
+
+        envelope.add_item(Item::new(ItemType::Event));
+        envelope.add_item(Item::new(ItemType::Attachment));
+        envelope.add_item(Item::new(ItemType::Attachment));
+
+        assert_eq!(envelope.item_types(), "attachment:2,event:1");
+    }
 }
@@ -9,6 +9,8 @@ use bytes::Bytes;
 use flate2::write::{GzDecoder, ZlibDecoder};
 use futures::{Async, Poll, Stream};
 use relay_config::HttpEncoding;
+use zstd::stream::raw::Decoder as ZstdOperation;
+use zstd::stream::zio::Writer as ZstdWriter;
 
 use crate::extractors::SharedPayload;
 
@@ -73,6 +75,12 @@ impl Write for Sink {
     }
 }
 
+/// Streaming zstd decoder writing into a [`Sink`].
+///
+/// Unlike `zstd::stream::write::Decoder`, the writer exposes `finish`, which fails if the input
+/// ends within an incomplete frame.
+type ZstdDecoder = ZstdWriter<Sink, ZstdOperation<'static>>;
+
 /// Writes data into the given `write`, returning `true` on overflow.
 fn write_overflowing<W: Write>(write: &mut W, slice: &[u8]) -> io::Result<bool> {
     match write.write_all(slice).and_then(|()| write.flush()) {
@@ -85,12 +93,22 @@ fn write_overflowing<W: Write>(write: &mut W, slice: &[u8]) -> io::Result<bool>
     }
 }
 
+/// Returns the stored error of a failed decoder, or a generic error if it was already returned.
+fn take_error(error: &mut Option<io::Error>) -> io::Error {
+    error
+        .take()
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, "decoder failed"))
+}
+
 /// Internal dispatch for all supported [`HttpEncoding`]s.
 enum DecoderInner {
     Identity(Box<Sink>),
     Br(Box<BrotliDecoder<Sink>>),
     Gzip(Box<GzDecoder<Sink>>),
     Deflate(Box<ZlibDecoder<Sink>>),
+    Zstd(Box<ZstdDecoder>),
+    /// The decoder could not be created. The error is returned on the first use.
+    Failed(Option<io::Error>),
 }
 
 /// Stateful decoder for all supported [`HttpEncoding`]s.
@@ -128,6 +146,11 @@ impl Decoder {
             HttpEncoding::Br => DecoderInner::Br(Box::new(BrotliDecoder::new(sink))),
             HttpEncoding::Gzip => DecoderInner::Gzip(Box::new(GzDecoder::new(sink))),
             HttpEncoding::Deflate => DecoderInner::Deflate(Box::new(ZlibDecoder::new(sink))),
+            // Creating the decoder only fails if the decompression context cannot be allocated.
+            HttpEncoding::Zstd => match ZstdOperation::new() {
+                Ok(operation) => DecoderInner::Zstd(Box::new(ZstdWriter::new(sink, operation))),
+                Err(error) => DecoderInner::Failed(Some(error)),
+            },
         };
 
         Self { inner }
@@ -146,6 +169,8 @@ impl Decoder {
             DecoderInner::Br(inner) => write_overflowing(inner, &bytes),
             DecoderInner::Gzip(inner) => write_overflowing(inner, &bytes),
             DecoderInner::Deflate(inner) => write_overflowing(inner, &bytes),
+            DecoderInner::Zstd(inner) => write_overflowing(inner, &bytes),
+            DecoderInner::Failed(error) => Err(take_error(error)),
         }
     }
 
@@ -165,6 +190,11 @@ impl Decoder {
                 inner.try_finish()?;
                 inner.get_mut().take()
             }
+            DecoderInner::Zstd(inner) => {
+                inner.finish()?;
+                inner.writer_mut().take()
+            }
+            DecoderInner::Failed(error) => return Err(take_error(error)),
         })
     }
 
@@ -178,6 +208,8 @@ impl Decoder {
             DecoderInner::Br(inner) => inner.get_mut().take(),
             DecoderInner::Gzip(inner) => inner.get_mut().take(),
             DecoderInner::Deflate(inner) => inner.get_mut().take(),
+            DecoderInner::Zstd(inner) => inner.writer_mut().take(),
+            DecoderInner::Failed(_) => Bytes::new(),
         }
     }
 }
@@ -189,6 +221,8 @@ impl fmt::Debug for Decoder {
             DecoderInner::Br(_inner) => f.debug_tuple("Br").finish(),
             DecoderInner::Gzip(inner) => f.debug_tuple("Gzip").field(inner).finish(),
             DecoderInner::Deflate(inner) => f.debug_tuple("Deflate").field(inner).finish(),
+            DecoderInner::Zstd(_inner) => f.debug_tuple("Zstd").finish(),
+            DecoderInner::Failed(error) => f.debug_tuple("Failed").field(error).finish(),
         }
     }
 }
@@ -259,4 +293,23 @@ mod tests {
         assert!(!decoder.decode(b"\x1f\x8b\x08\x00\x06\xb4\x8ba\x00\x03\xcbH\xcd\xc9\xc9\xe7\x02\x00 0:6\x06\x00\x00\x00"[..].into()).unwrap());
         assert_eq!(&*decoder.take(), b"hello\n");
     }
+
+    #[test]
+    fn test_decoder_zstd() {
+        let encoded = zstd::stream::encode_all(&b"hello\n"[..], 3).unwrap();
+
+        let mut decoder = Decoder::from_encoding(HttpEncoding::Zstd, 10000);
+        assert!(!decoder.decode(encoded.into()).unwrap());
+        assert_eq!(&*decoder.finish().unwrap(), b"hello\n");
+    }
+
+    #[test]
+    fn test_decoder_zstd_truncated() {
+        let encoded = zstd::stream::encode_all(&b"hello\n"[..], 3).unwrap();
+        let truncated = &encoded[..encoded.len() - 2];
+
+        let mut decoder = Decoder::from_encoding(HttpEncoding::Zstd, 10000);
+        assert!(!decoder.decode(truncated.to_vec().into()).unwrap());
+        assert!(decoder.finish().is_err());
+    }
 }
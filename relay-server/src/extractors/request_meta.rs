@@ -11,6 +11,7 @@ use futures::{future, Future};
 use serde::{Deserialize, Serialize};
 use url::Url;
 
+use relay_auth::PublicKey;
 use relay_common::{
     Auth, Dsn, ParseAuthError, ParseDsnError, ParseProjectIdError, ParseProjectKeyError, ProjectId,
     ProjectKey, Scheme,
@@ -200,6 +201,12 @@ pub struct RequestMeta<D = PartialDsn> {
     // NOTE: This is internal-only and not exposed to Envelope headers.
     #[serde(skip, default = "Instant::now")]
     start_time: Instant,
+
+    /// The public key of the Relay that signed the request body, once the signature is verified.
+    //
+    // NOTE: This is internal-only and not exposed to Envelope headers.
+    #[serde(skip)]
+    signed_by: Option<PublicKey>,
 }
 
 impl<D> RequestMeta<D> {
@@ -255,6 +262,18 @@ impl<D> RequestMeta<D> {
     pub fn start_time(&self) -> Instant {
         self.start_time
     }
+
+    /// The public key of the Relay that signed the request body, if verified.
+    pub fn signed_by(&self) -> Option<&PublicKey> {
+        self.signed_by.as_ref()
+    }
+
+    /// Marks the request body as signed by the Relay with the given public key.
+    ///
+    /// This must only be called after verifying the signature.
+    pub fn set_signed_by(&mut self, public_key: PublicKey) {
+        self.signed_by = Some(public_key);
+    }
 }
 
 impl RequestMeta {
@@ -270,6 +289,7 @@ impl RequestMeta {
             user_agent: Some(crate::constants::SERVER.to_owned()),
             no_cache: false,
             start_time: Instant::now(),
+            signed_by: None,
         }
     }
 
@@ -286,6 +306,7 @@ impl RequestMeta {
             user_agent: Some("sentry/agent".to_string()),
             no_cache: false,
             start_time: Instant::now(),
+            signed_by: None,
         }
     }
 
@@ -368,6 +389,7 @@ impl PartialMeta {
                 .map(str::to_owned),
             no_cache: false,
             start_time: StartTime::extract(request).into_inner(),
+            signed_by: None,
         }
     }
 
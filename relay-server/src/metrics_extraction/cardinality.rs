@@ -0,0 +1,290 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+
+use relay_common::ProjectKey;
+use relay_metrics::Metric;
+use relay_statsd::metric;
+
+use crate::statsd::RelayCounters;
+
+/// The default interval after which the recorded tag combinations of a project are reset.
+const DEFAULT_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Distinct tag combinations of a single project within the current time window.
+#[derive(Debug)]
+struct ProjectCardinality {
+    window_start: Instant,
+    seen: HashMap<String, BTreeSet<u64>>,
+}
+
+impl ProjectCardinality {
+    fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            seen: HashMap::new(),
+        }
+    }
+
+    fn is_expired(&self, window: Duration) -> bool {
+        self.window_start.elapsed() >= window
+    }
+
+    /// Starts a new time window and forgets all tag combinations if the current one has passed.
+    fn reset_if_expired(&mut self, window: Duration) {
+        if self.is_expired(window) {
+            self.window_start = Instant::now();
+            self.seen.clear();
+        }
+    }
+}
+
+/// Cardinality state of all projects.
+#[derive(Debug)]
+struct Projects {
+    states: HashMap<ProjectKey, Arc<Mutex<ProjectCardinality>>>,
+    last_purge: Instant,
+}
+
+impl Projects {
+    /// Removes projects without activity in the last window, at most once per window.
+    fn purge_expired(&mut self, window: Duration) {
+        if self.last_purge.elapsed() < window {
+            return;
+        }
+
+        self.last_purge = Instant::now();
+        self.states.retain(|_, state| {
+            let state = state.lock().unwrap_or_else(PoisonError::into_inner);
+            !state.is_expired(window)
+        });
+    }
+}
+
+/// Tracks the distinct tag combinations of extracted metrics per project and metric name.
+///
+/// Once the number of tag combinations of a metric reaches the configured budget, metrics with
+/// new combinations are dropped until the time window of the project ends. Combinations that have
+/// been seen within the window are always retained.
+///
+/// Every project has its own lock, so that workers processing different projects do not contend.
+#[derive(Debug)]
+pub struct CardinalityLimiter {
+    window: Duration,
+    projects: RwLock<Projects>,
+}
+
+impl CardinalityLimiter {
+    /// Creates a new limiter without any recorded tag combinations.
+    pub fn new() -> Self {
+        Self::with_window(DEFAULT_WINDOW)
+    }
+
+    /// Creates a new limiter that resets the tag combinations of a project after `window`.
+    pub fn with_window(window: Duration) -> Self {
+        Self {
+            window,
+            projects: RwLock::new(Projects {
+                states: HashMap::new(),
+                last_purge: Instant::now(),
+            }),
+        }
+    }
+
+    /// Returns the cardinality state of the given project, creating it if necessary.
+    fn project(&self, project_key: ProjectKey) -> Arc<Mutex<ProjectCardinality>> {
+        let projects = self.projects.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(state) = projects.states.get(&project_key) {
+            return state.clone();
+        }
+        drop(projects);
+
+        let mut projects = self
+            .projects
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        projects.purge_expired(self.window);
+        projects
+            .states
+            .entry(project_key)
+            .or_insert_with(|| Arc::new(Mutex::new(ProjectCardinality::new())))
+            .clone()
+    }
+
+    /// Removes all metrics that would exceed the cardinality budget of their metric name.
+    ///
+    /// Metrics for which `exempt` returns `true` are always retained and not tracked.
+    pub fn retain<F>(
+        &self,
+        project_key: ProjectKey,
+        limit: usize,
+        metrics: &mut Vec<Metric>,
+        exempt: F,
+    ) where
+        F: Fn(&Metric) -> bool,
+    {
+        let project = self.project(project_key);
+        let mut project = project.lock().unwrap_or_else(PoisonError::into_inner);
+        project.reset_if_expired(self.window);
+
+        metrics.retain(|metric| {
+            if exempt(metric) {
+                return true;
+            }
+
+            let mut hasher = DefaultHasher::new();
+            metric.tags.hash(&mut hasher);
+            let combination = hasher.finish();
+
+            let combinations = project.seen.entry(metric.name.clone()).or_default();
+
+            if combinations.contains(&combination) {
+                return true;
+            }
+
+            if combinations.len() < limit {
+                combinations.insert(combination);
+                return true;
+            }
+
+            relay_log::trace!(
+                "dropping metric {} because of cardinality limit",
+                metric.name
+            );
+            metric!(
+                counter(RelayCounters::MetricCardinalityLimited) += 1,
+                metric = &metric.name
+            );
+            false
+        });
+    }
+}
+
+impl Default for CardinalityLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use relay_common::UnixTimestamp;
+    use relay_metrics::MetricValue;
+
+    use super::*;
+
+    fn metric(name: &str, transaction: &str) -> Metric {
+        let mut tags = BTreeMap::new();
+        tags.insert("transaction".to_owned(), transaction.to_owned());
+
+        Metric {
+            name: name.to_owned(),
+            value: MetricValue::Counter(1.0),
+            timestamp: UnixTimestamp::from_secs(1_600_000_000),
+            tags,
+        }
+    }
+
+    fn names(metrics: &[Metric]) -> Vec<&str> {
+        metrics
+            .iter()
+            .map(|m| m.tags["transaction"].as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_cardinality_limit() {
+        let limiter = CardinalityLimiter::new();
+        let project_key = ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap();
+
+        let mut metrics = vec![
+            metric("c:transactions/count_per_root_project@none", "a"),
+            metric("c:transactions/count_per_root_project@none", "b"),
+            metric("c:transactions/count_per_root_project@none", "c"),
+        ];
+        limiter.retain(project_key, 2, &mut metrics, |_| false);
+        assert_eq!(names(&metrics), vec!["a", "b"]);
+
+        // Known combinations are retained, further distinct combinations are dropped.
+        let mut metrics = vec![
+            metric("c:transactions/count_per_root_project@none", "d"),
+            metric("c:transactions/count_per_root_project@none", "b"),
+        ];
+        limiter.retain(project_key, 2, &mut metrics, |_| false);
+        assert_eq!(names(&metrics), vec!["b"]);
+    }
+
+    #[test]
+    fn test_cardinality_limit_per_name() {
+        let limiter = CardinalityLimiter::new();
+        let project_key = ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap();
+
+        let mut metrics = vec![
+            metric("d:transactions/duration@millisecond", "a"),
+            metric("d:transactions/duration@millisecond", "b"),
+            metric("d:transactions/measurements.lcp@millisecond", "b"),
+        ];
+        limiter.retain(project_key, 1, &mut metrics, |_| false);
+        assert_eq!(names(&metrics), vec!["a", "b"]);
+        assert_eq!(
+            metrics[1].name,
+            "d:transactions/measurements.lcp@millisecond"
+        );
+    }
+
+    #[test]
+    fn test_cardinality_limit_per_project() {
+        let limiter = CardinalityLimiter::new();
+        let project_key1 = ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap();
+        let project_key2 = ProjectKey::parse("e12d836b15bb49d7bbf99e64295d995b").unwrap();
+
+        let mut metrics = vec![
+            metric("d:transactions/duration@millisecond", "a"),
+            metric("d:transactions/duration@millisecond", "b"),
+        ];
+        limiter.retain(project_key1, 1, &mut metrics, |_| false);
+        assert_eq!(names(&metrics), vec!["a"]);
+
+        // The budget of another project is not affected.
+        let mut metrics = vec![metric("d:transactions/duration@millisecond", "b")];
+        limiter.retain(project_key2, 1, &mut metrics, |_| false);
+        assert_eq!(names(&metrics), vec!["b"]);
+    }
+
+    #[test]
+    fn test_cardinality_limit_window() {
+        let project_key = ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap();
+
+        let limiter = CardinalityLimiter::new();
+        let mut metrics = vec![metric("d:transactions/duration@millisecond", "a")];
+        limiter.retain(project_key, 1, &mut metrics, |_| false);
+        let mut metrics = vec![metric("d:transactions/duration@millisecond", "b")];
+        limiter.retain(project_key, 1, &mut metrics, |_| false);
+        assert!(metrics.is_empty());
+
+        // Once the window has passed, new combinations are accepted again.
+        let limiter = CardinalityLimiter::with_window(Duration::from_secs(0));
+        let mut metrics = vec![metric("d:transactions/duration@millisecond", "a")];
+        limiter.retain(project_key, 1, &mut metrics, |_| false);
+        let mut metrics = vec![metric("d:transactions/duration@millisecond", "b")];
+        limiter.retain(project_key, 1, &mut metrics, |_| false);
+        assert_eq!(names(&metrics), vec!["b"]);
+    }
+
+    #[test]
+    fn test_cardinality_limit_exempt() {
+        let limiter = CardinalityLimiter::new();
+        let project_key = ProjectKey::parse("a94ae32be2584e0bbd7a4cbb95971fee").unwrap();
+
+        let mut metrics = vec![
+            metric("d:transactions/measurements.time_to_full_display@none", "a"),
+            metric("d:transactions/measurements.time_to_full_display@none", "b"),
+        ];
+        limiter.retain(project_key, 1, &mut metrics, |_| true);
+        assert_eq!(names(&metrics), vec!["a", "b"]);
+    }
+}
@@ -16,6 +16,26 @@ pub struct TaggingRule {
     pub tag_value: String,
 }
 
+/// A counter that is extracted from transactions matching a condition.
+///
+/// This is the equivalent of a `count_if` aggregation, evaluated at ingestion time with the same
+/// condition grammar as [`TaggingRule`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConditionalCounter {
+    pub condition: RuleCondition,
+    /// The name of the counter within the transactions namespace, without type and unit.
+    pub name: String,
+}
+
+#[cfg(feature = "processing")]
+impl ConditionalCounter {
+    /// Returns `true` if the counter should be emitted for this event.
+    pub fn matches(&self, event: &Event) -> bool {
+        self.condition.supported() && self.condition.matches_event(event, None)
+    }
+}
+
 #[cfg(feature = "processing")]
 pub fn run_conditional_tagging(event: &Event, config: &[TaggingRule], metrics: &mut [Metric]) {
     for rule in config {
@@ -0,0 +1,182 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "processing")]
+use {
+    relay_common::UnixTimestamp,
+    relay_general::protocol::{AsPair, Event, EventType},
+    relay_metrics::{Metric, MetricNamespace, MetricUnit, MetricValue},
+    std::collections::BTreeMap,
+};
+
+/// Configuration for extracting metrics from error events.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct ErrorMetricsConfig {
+    /// Event tags that are added to extracted metrics in addition to the default tags.
+    ///
+    /// `os.name` is read from the event's OS context after normalization.
+    extract_custom_tags: BTreeSet<String>,
+}
+
+#[cfg(feature = "processing")]
+impl ErrorMetricsConfig {
+    /// Returns `true` if the given tag is extracted in addition to the default tags.
+    pub fn has_custom_tag(&self, name: &str) -> bool {
+        self.extract_custom_tags.contains(name)
+    }
+}
+
+#[cfg(feature = "processing")]
+const METRIC_NAMESPACE: MetricNamespace = MetricNamespace::Errors;
+
+/// Returns the tags of error metrics extracted from the given event.
+///
+/// Errors are always tagged with their level. Release and environment are added if present on the
+/// event. Events without a level are counted as `error`, which matches the default applied during
+/// normalization.
+#[cfg(feature = "processing")]
+fn extract_error_tags(event: &Event, custom_tags: &BTreeSet<String>) -> BTreeMap<String, String> {
+    let mut tags = BTreeMap::new();
+    if let Some(release) = event.release.as_str() {
+        tags.insert("release".to_owned(), release.to_owned());
+    }
+    if let Some(environment) = event.environment.as_str() {
+        tags.insert("environment".to_owned(), environment.to_owned());
+    }
+
+    let level = event
+        .level
+        .value()
+        .map_or_else(|| "error".to_owned(), ToString::to_string);
+    tags.insert("level".to_owned(), level);
+
+    if !custom_tags.is_empty() {
+        if let Some(event_tags) = event.tags.value() {
+            for tag_entry in &**event_tags {
+                if let Some(entry) = tag_entry.value() {
+                    let (key, value) = entry.as_pair();
+                    if let (Some(key), Some(value)) = (key.as_str(), value.as_str()) {
+                        if custom_tags.contains(key) {
+                            tags.insert(key.to_owned(), value.to_owned());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    tags
+}
+
+/// Extracts a counter of error events into `target`.
+///
+/// The counter is timestamped with the event's timestamp, falling back to `received_at` if the
+/// event does not declare one. Returns `true` if a metric was extracted.
+#[cfg(feature = "processing")]
+pub fn extract_error_metrics(
+    config: &ErrorMetricsConfig,
+    event: &Event,
+    received_at: UnixTimestamp,
+    target: &mut Vec<Metric>,
+) -> bool {
+    if event.ty.value() == Some(&EventType::Transaction) {
+        return false;
+    }
+
+    let timestamp = event
+        .timestamp
+        .value()
+        .and_then(|ts| UnixTimestamp::from_datetime(ts.into_inner()))
+        .unwrap_or(received_at);
+
+    target.push(Metric::new_mri(
+        METRIC_NAMESPACE,
+        "count",
+        MetricUnit::None,
+        MetricValue::Counter(1.0),
+        timestamp,
+        extract_error_tags(event, &config.extract_custom_tags),
+    ));
+
+    true
+}
+
+#[cfg(test)]
+#[cfg(feature = "processing")]
+mod tests {
+    use super::*;
+
+    use relay_general::types::Annotated;
+
+    #[test]
+    fn test_extract_error_metrics() {
+        let json = r#"
+        {
+            "type": "error",
+            "timestamp": "2021-04-26T08:00:00+0100",
+            "release": "1.2.3",
+            "environment": "production",
+            "level": "warning",
+            "tags": {
+                "customer": "acme",
+                "bogus": "absolutely"
+            }
+        }
+        "#;
+
+        let event = Annotated::from_json(json).unwrap();
+        let config: ErrorMetricsConfig = serde_json::from_str(
+            r#"
+        {
+            "extractCustomTags": ["customer"]
+        }
+        "#,
+        )
+        .unwrap();
+
+        let mut metrics = vec![];
+        let received_at = UnixTimestamp::from_secs(0);
+        assert!(extract_error_metrics(
+            &config,
+            event.value().unwrap(),
+            received_at,
+            &mut metrics
+        ));
+
+        assert_eq!(metrics.len(), 1);
+        let metric = &metrics[0];
+        assert_eq!(metric.name, "c:errors/count@none");
+        assert_eq!(metric.value, MetricValue::Counter(1.0));
+        assert_eq!(metric.timestamp, UnixTimestamp::from_secs(1619420400));
+
+        let mut expected_tags = BTreeMap::new();
+        expected_tags.insert("release".to_owned(), "1.2.3".to_owned());
+        expected_tags.insert("environment".to_owned(), "production".to_owned());
+        expected_tags.insert("level".to_owned(), "warning".to_owned());
+        expected_tags.insert("customer".to_owned(), "acme".to_owned());
+        assert_eq!(metric.tags, expected_tags);
+    }
+
+    #[test]
+    fn test_extract_error_metrics_transaction() {
+        let json = r#"
+        {
+            "type": "transaction",
+            "timestamp": "2021-04-26T08:00:00+0100",
+            "start_timestamp": "2021-04-26T07:59:01+0100"
+        }
+        "#;
+
+        let event = Annotated::from_json(json).unwrap();
+        let mut metrics = vec![];
+        assert!(!extract_error_metrics(
+            &ErrorMetricsConfig::default(),
+            event.value().unwrap(),
+            UnixTimestamp::from_secs(0),
+            &mut metrics
+        ));
+        assert!(metrics.is_empty());
+    }
+}
@@ -1,6 +1,11 @@
+#[cfg(feature = "processing")]
+pub mod cardinality;
 mod conditional_tagging;
+pub mod errors;
 pub mod sessions;
 pub mod transactions;
 mod utils;
 
-pub use conditional_tagging::TaggingRule;
+pub use conditional_tagging::{ConditionalCounter, TaggingRule};
+#[cfg(feature = "processing")]
+pub use utils::{add_os_name_tag, add_runtime_name_tag};
@@ -58,6 +58,20 @@ fn nil_to_none(distinct_id: Option<&String>) -> Option<&String> {
     Some(distinct_id)
 }
 
+/// Parses the SDK version from a client string, such as `sentry.cocoa/7.1.0`.
+///
+/// Returns `None` if the client string does not contain a version.
+fn sdk_version(client: &str) -> Option<&str> {
+    let (name, version) = client.split_once('/')?;
+    let version = version.split_whitespace().next()?;
+
+    if name.is_empty() || !version.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(version)
+}
+
 pub fn extract_session_metrics<T: SessionLike>(
     attributes: &SessionAttributes,
     session: &T,
@@ -79,6 +93,9 @@ pub fn extract_session_metrics<T: SessionLike>(
     }
     if let Some(client) = client {
         tags.insert("sdk".to_owned(), client.to_owned());
+        if let Some(version) = sdk_version(client) {
+            tags.insert("sdk.version".to_owned(), version.to_owned());
+        }
     }
 
     // Always capture with "init" tag for the first session update of a session. This is used
@@ -249,6 +266,66 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sdk_version() {
+        assert_eq!(sdk_version("sentry.cocoa/7.1.0"), Some("7.1.0"));
+        assert_eq!(sdk_version("sentry.python/1.5.0 (custom)"), Some("1.5.0"));
+        assert_eq!(sdk_version("sentry.cocoa"), None);
+        assert_eq!(sdk_version("sentry.cocoa/"), None);
+        assert_eq!(sdk_version("/7.1.0"), None);
+        assert_eq!(sdk_version("sentry.cocoa/latest"), None);
+    }
+
+    #[test]
+    fn test_extract_session_metrics_sdk_version() {
+        let mut metrics = vec![];
+
+        let session = SessionUpdate::parse(
+            r#"{
+                "init": true,
+                "started": "2021-04-26T08:00:00+0100",
+                "attrs": {
+                    "release": "1.0.0"
+                }
+            }"#
+            .as_bytes(),
+        )
+        .unwrap();
+
+        extract_session_metrics(
+            &session.attributes,
+            &session,
+            Some("sentry.cocoa/7.1.0"),
+            &mut metrics,
+        );
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].tags["sdk"], "sentry.cocoa/7.1.0");
+        assert_eq!(metrics[0].tags["sdk.version"], "7.1.0");
+    }
+
+    #[test]
+    fn test_extract_session_metrics_no_sdk_version() {
+        let mut metrics = vec![];
+
+        let session = SessionUpdate::parse(
+            r#"{
+                "init": true,
+                "started": "2021-04-26T08:00:00+0100",
+                "attrs": {
+                    "release": "1.0.0"
+                }
+            }"#
+            .as_bytes(),
+        )
+        .unwrap();
+
+        extract_session_metrics(&session.attributes, &session, Some("custom"), &mut metrics);
+
+        assert_eq!(metrics.len(), 1);
+        assert!(!metrics[0].tags.contains_key("sdk.version"));
+    }
+
     #[test]
     #[cfg(feature = "processing")]
     fn test_extract_session_metrics() {
@@ -490,6 +567,7 @@ mod tests {
                     "environment": "development",
                     "release": "my-project-name@1.0.0",
                     "sdk": "sentry-test/1.0",
+                    "sdk.version": "1.0",
                     "session.status": "init",
                 },
             },
@@ -503,6 +581,7 @@ mod tests {
                     "environment": "development",
                     "release": "my-project-name@1.0.0",
                     "sdk": "sentry-test/1.0",
+                    "sdk.version": "1.0",
                     "session.status": "errored_preaggr",
                 },
             },
@@ -516,6 +595,7 @@ mod tests {
                     "environment": "development",
                     "release": "my-project-name@1.0.0",
                     "sdk": "sentry-test/1.0",
+                    "sdk.version": "1.0",
                     "session.status": "abnormal",
                 },
             },
@@ -529,6 +609,7 @@ mod tests {
                     "environment": "development",
                     "release": "my-project-name@1.0.0",
                     "sdk": "sentry-test/1.0",
+                    "sdk.version": "1.0",
                     "session.status": "crashed",
                 },
             },
@@ -542,6 +623,7 @@ mod tests {
                     "environment": "development",
                     "release": "my-project-name@1.0.0",
                     "sdk": "sentry-test/1.0",
+                    "sdk.version": "1.0",
                     "session.status": "init",
                 },
             },
@@ -555,6 +637,7 @@ mod tests {
                     "environment": "development",
                     "release": "my-project-name@1.0.0",
                     "sdk": "sentry-test/1.0",
+                    "sdk.version": "1.0",
                     "session.status": "init",
                 },
             },
@@ -568,6 +651,7 @@ mod tests {
                     "environment": "development",
                     "release": "my-project-name@1.0.0",
                     "sdk": "sentry-test/1.0",
+                    "sdk.version": "1.0",
                     "session.status": "errored_preaggr",
                 },
             },
@@ -581,6 +665,7 @@ mod tests {
                     "environment": "development",
                     "release": "my-project-name@1.0.0",
                     "sdk": "sentry-test/1.0",
+                    "sdk.version": "1.0",
                     "session.status": "errored",
                 },
             },
@@ -1,17 +1,21 @@
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet};
 
+use crate::metrics_extraction::ConditionalCounter;
+
 #[cfg(feature = "processing")]
 use {
     crate::metrics_extraction::conditional_tagging::run_conditional_tagging,
     crate::metrics_extraction::{utils, TaggingRule},
     relay_common::UnixTimestamp,
     relay_general::protocol::TraceContext,
-    relay_general::protocol::{AsPair, Event, EventType, Timestamp},
+    relay_general::protocol::{is_vital, AsPair, Event, EventType, Timestamp, CORE_WEB_VITALS},
     relay_general::protocol::{Context, ContextInner},
     relay_general::store,
     relay_general::types::Annotated,
-    relay_metrics::{DurationUnit, Metric, MetricNamespace, MetricUnit, MetricValue},
+    relay_metrics::{
+        DurationUnit, Metric, MetricNamespace, MetricResourceIdentifier, MetricUnit, MetricValue,
+    },
     std::fmt,
 };
 
@@ -43,6 +47,28 @@ struct SatisfactionConfig {
     transaction_thresholds: BTreeMap<String, SatisfactionThreshold>,
 }
 
+/// Controls how transactions without a transaction name are handled during metrics extraction.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum UnnamedTransactionBehavior {
+    /// Extract metrics without a `transaction` tag.
+    Omit,
+    /// Drop the transaction with an `invalid_transaction` outcome.
+    Drop,
+    /// Tag metrics with the `<unlabeled>` placeholder.
+    Placeholder,
+}
+
+impl Default for UnnamedTransactionBehavior {
+    fn default() -> Self {
+        Self::Omit
+    }
+}
+
+/// The `transaction` tag of metrics extracted from transactions without a name.
+#[cfg(feature = "processing")]
+const UNLABELED_TRANSACTION: &str = "<unlabeled>";
+
 /// Configuration for extracting metrics from transaction payloads.
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename_all = "camelCase")]
@@ -50,6 +76,50 @@ pub struct TransactionMetricsConfig {
     extract_metrics: BTreeSet<String>,
     extract_custom_tags: BTreeSet<String>,
     satisfaction_thresholds: Option<SatisfactionConfig>,
+    cardinality_limit: Option<usize>,
+    priority_measurements: BTreeSet<String>,
+    conditional_counters: Vec<ConditionalCounter>,
+    /// Value of the `dist` tag for transactions without a dist. If not set, the tag is omitted.
+    dist_placeholder: Option<String>,
+    unnamed_transactions: UnnamedTransactionBehavior,
+    /// Tag the duration metric with the number of standard web vitals present on the event.
+    vitals_count_tag: bool,
+}
+
+#[cfg(feature = "processing")]
+impl TransactionMetricsConfig {
+    /// Returns the maximum number of distinct tag combinations per extracted metric, if any.
+    pub fn cardinality_limit(&self) -> Option<usize> {
+        self.cardinality_limit
+    }
+
+    /// Returns the names of measurements that are exempt from trimming and cardinality limits.
+    pub fn priority_measurements(&self) -> &BTreeSet<String> {
+        &self.priority_measurements
+    }
+
+    /// Returns how transactions without a transaction name are handled.
+    pub fn unnamed_transactions(&self) -> UnnamedTransactionBehavior {
+        self.unnamed_transactions
+    }
+
+    /// Returns `true` if the given tag is extracted in addition to the default tags.
+    pub fn has_custom_tag(&self, name: &str) -> bool {
+        self.extract_custom_tags.contains(name)
+    }
+
+    /// Returns `true` if the metric was extracted from a priority measurement.
+    pub fn is_priority_metric(&self, mri: &str) -> bool {
+        let name = match MetricResourceIdentifier::parse(mri) {
+            Ok(mri) => mri.name,
+            Err(_) => return false,
+        };
+
+        match name.strip_prefix("measurements.") {
+            Some(measurement) => self.priority_measurements.contains(measurement),
+            None => false,
+        }
+    }
 }
 
 #[cfg(feature = "processing")]
@@ -94,6 +164,15 @@ fn extract_http_method(transaction: &Event) -> Option<String> {
     Some(method.to_owned())
 }
 
+/// Extract the number of integrations reported by the SDK.
+#[cfg(feature = "processing")]
+fn extract_sdk_integrations(event: &Event) -> Option<String> {
+    let client_sdk = event.client_sdk.value()?;
+    let integrations = client_sdk.integrations.value()?;
+    let count = integrations.iter().filter(|i| i.value().is_some()).count();
+    Some(count.to_string())
+}
+
 /// Satisfaction value used for Apdex and User Misery
 /// <https://docs.sentry.io/product/performance/metrics/#apdex>
 #[cfg(feature = "processing")]
@@ -163,20 +242,29 @@ fn extract_user_satisfaction(
 #[cfg(feature = "processing")]
 fn extract_universal_tags(
     event: &Event,
-    custom_tags: &BTreeSet<String>,
+    config: &TransactionMetricsConfig,
 ) -> BTreeMap<String, String> {
+    let custom_tags = &config.extract_custom_tags;
+
     let mut tags = BTreeMap::new();
     if let Some(release) = event.release.as_str() {
         tags.insert("release".to_owned(), release.to_owned());
     }
-    if let Some(dist) = extract_dist(event) {
+    let dist_placeholder = config.dist_placeholder.as_ref();
+    if let Some(dist) = extract_dist(event).or_else(|| dist_placeholder.cloned()) {
         tags.insert("dist".to_owned(), dist);
     }
     if let Some(environment) = event.environment.as_str() {
         tags.insert("environment".to_owned(), environment.to_owned());
     }
-    if let Some(transaction) = event.transaction.as_str() {
-        tags.insert("transaction".to_owned(), transaction.to_owned());
+    match event.transaction.as_str() {
+        Some(transaction) => {
+            tags.insert("transaction".to_owned(), transaction.to_owned());
+        }
+        None if config.unnamed_transactions == UnnamedTransactionBehavior::Placeholder => {
+            tags.insert("transaction".to_owned(), UNLABELED_TRANSACTION.to_owned());
+        }
+        None => (),
     }
 
     // The platform tag should not increase dimensionality in most cases, because most
@@ -215,6 +303,21 @@ fn extract_universal_tags(
                 }
             }
         }
+
+        // Metrics are extracted before store normalization, so the device class tag is not yet
+        // on the event and needs to be derived from the device context.
+        if custom_tags.contains("device.class") {
+            if let Some(device_class) = store::DeviceClass::from_event(event) {
+                tags.insert("device.class".to_owned(), device_class.name().to_owned());
+            }
+        }
+
+        // The integrations list has unbounded cardinality, so only the count is tagged.
+        if custom_tags.contains("sdk.integrations") {
+            if let Some(integrations) = extract_sdk_integrations(event) {
+                tags.insert("sdk.integrations".to_owned(), integrations);
+            }
+        }
     }
 
     tags
@@ -253,12 +356,45 @@ fn get_metric_measurement_unit(metric: &str) -> Option<MetricUnit> {
     }
 }
 
+/// Returns the number of core web vitals with a value on the event.
+#[cfg(feature = "processing")]
+fn count_web_vitals(event: &Event) -> usize {
+    let measurements = match event.measurements.value() {
+        Some(measurements) => measurements,
+        None => return 0,
+    };
+
+    CORE_WEB_VITALS
+        .iter()
+        .filter(|name| {
+            measurements
+                .get(**name)
+                .and_then(Annotated::value)
+                .map_or(false, |measurement| measurement.value.value().is_some())
+        })
+        .count()
+}
+
+/// Returns the unit if it is known to Relay, otherwise [`MetricUnit::None`].
+///
+/// SDKs may send non-standard unit strings, which must not be propagated into metric names.
+#[cfg(feature = "processing")]
+fn known_unit(name: &str, unit: MetricUnit) -> MetricUnit {
+    if let MetricUnit::Custom(custom) = unit {
+        relay_log::trace!("unknown unit {} on {}, falling back to none", custom, name);
+        return MetricUnit::None;
+    }
+
+    unit
+}
+
 #[cfg(feature = "processing")]
 pub fn extract_transaction_metrics(
     config: &TransactionMetricsConfig,
     breakdowns_config: Option<&store::BreakdownsConfig>,
     conditional_tagging_config: &[TaggingRule],
     event: &Event,
+    trace_context: Option<&relay_sampling::TraceContext>,
     target: &mut Vec<Metric>,
 ) -> bool {
     if config.extract_metrics.is_empty() {
@@ -276,7 +412,7 @@ pub fn extract_transaction_metrics(
         }
     };
 
-    extract_transaction_metrics_inner(config, breakdowns_config, event, push_metric);
+    extract_transaction_metrics_inner(config, breakdowns_config, event, trace_context, push_metric);
 
     let added_slice = &mut target[before_len..];
     run_conditional_tagging(event, conditional_tagging_config, added_slice);
@@ -288,6 +424,7 @@ fn extract_transaction_metrics_inner(
     config: &TransactionMetricsConfig,
     breakdowns_config: Option<&store::BreakdownsConfig>,
     event: &Event,
+    trace_context: Option<&relay_sampling::TraceContext>,
     mut push_metric: impl FnMut(Metric),
 ) {
     if event.ty.value() != Some(&EventType::Transaction) {
@@ -306,7 +443,7 @@ fn extract_transaction_metrics_inner(
         None => return,
     };
 
-    let tags = extract_universal_tags(event, &config.extract_custom_tags);
+    let tags = extract_universal_tags(event, config);
 
     // Measurements
     if let Some(measurements) = event.measurements.value() {
@@ -321,12 +458,17 @@ fn extract_transaction_metrics_inner(
                 None => continue,
             };
 
+            if value < 0.0 && is_vital(name) {
+                relay_log::trace!("dropping negative value {} of measurements.{}", value, name);
+                continue;
+            }
+
             let mut tags_for_measurement = tags.clone();
             if let Some(rating) = get_measurement_rating(name, value) {
                 tags_for_measurement.insert("measurement_rating".to_owned(), rating);
             }
 
-            let stated_unit = measurement.unit.value().copied();
+            let stated_unit = measurement.unit.value().map(|unit| known_unit(name, *unit));
             let default_unit = get_metric_measurement_unit(name);
             if let (Some(default), Some(stated)) = (default_unit, stated_unit) {
                 if default != stated {
@@ -360,12 +502,15 @@ fn extract_transaction_metrics_inner(
                     None => continue,
                 };
 
-                let unit = measurement.unit.value();
+                let unit = measurement
+                    .unit
+                    .value()
+                    .map(|unit| known_unit(measurement_name, *unit));
 
                 push_metric(Metric::new_mri(
                     METRIC_NAMESPACE,
                     format!("breakdowns.{}.{}", breakdown, measurement_name),
-                    unit.copied().unwrap_or(MetricUnit::None),
+                    unit.unwrap_or(MetricUnit::None),
                     MetricValue::Distribution(value),
                     unix_timestamp,
                     tags.clone(),
@@ -374,6 +519,20 @@ fn extract_transaction_metrics_inner(
         }
     }
 
+    // Conditional counters
+    for counter in &config.conditional_counters {
+        if counter.matches(event) {
+            push_metric(Metric::new_mri(
+                METRIC_NAMESPACE,
+                counter.name.as_str(),
+                MetricUnit::None,
+                MetricValue::Counter(1.0),
+                unix_timestamp,
+                tags.clone(),
+            ));
+        }
+    }
+
     let user_satisfaction = extract_user_satisfaction(
         &config.satisfaction_thresholds,
         event,
@@ -387,6 +546,15 @@ fn extract_transaction_metrics_inner(
 
     // Duration
     let duration_millis = relay_common::chrono_to_positive_millis(end_timestamp - start_timestamp);
+    let duration_tags = if config.vitals_count_tag {
+        utils::with_tag(
+            &tags_with_satisfaction,
+            "vitals_count",
+            count_web_vitals(event),
+        )
+    } else {
+        tags_with_satisfaction.clone()
+    };
 
     push_metric(Metric::new_mri(
         METRIC_NAMESPACE,
@@ -394,7 +562,7 @@ fn extract_transaction_metrics_inner(
         MetricUnit::Duration(DurationUnit::MilliSecond),
         MetricValue::Distribution(duration_millis),
         unix_timestamp,
-        tags_with_satisfaction.clone(),
+        duration_tags,
     ));
 
     // User
@@ -415,6 +583,24 @@ fn extract_transaction_metrics_inner(
             ));
         }
     }
+
+    // Count per root project of the trace
+    if let Some(trace_context) = trace_context {
+        let mut tags = BTreeMap::new();
+        tags.insert(
+            "root_project".to_owned(),
+            trace_context.public_key.to_string(),
+        );
+
+        push_metric(Metric::new_mri(
+            METRIC_NAMESPACE,
+            "count_per_root_project",
+            MetricUnit::None,
+            MetricValue::Counter(1.0),
+            unix_timestamp,
+            tags,
+        ));
+    }
 }
 
 #[cfg(feature = "processing")]
@@ -515,6 +701,7 @@ mod tests {
             Some(&breakdowns_config),
             &[],
             event.value().unwrap(),
+            None,
             &mut metrics,
         );
         assert_eq!(metrics, &[]);
@@ -541,6 +728,7 @@ mod tests {
             Some(&breakdowns_config),
             &[],
             event.value().unwrap(),
+            None,
             &mut metrics,
         );
 
@@ -619,7 +807,14 @@ mod tests {
         let event = Annotated::from_json(json).unwrap();
 
         let mut metrics = vec![];
-        extract_transaction_metrics(&config, None, &[], event.value().unwrap(), &mut metrics);
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
 
         assert_eq!(metrics.len(), 3, "{:?}", metrics);
 
@@ -667,7 +862,14 @@ mod tests {
         let event = Annotated::from_json(json).unwrap();
 
         let mut metrics = vec![];
-        extract_transaction_metrics(&config, None, &[], event.value().unwrap(), &mut metrics);
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
 
         assert_eq!(metrics.len(), 2);
 
@@ -677,6 +879,40 @@ mod tests {
         assert_eq!(metrics[1].name, "d:transactions/measurements.lcp@none");
     }
 
+    #[test]
+    fn test_metric_measurement_unknown_unit() {
+        let json = r#"{
+            "type": "transaction",
+            "timestamp": "2021-04-26T08:00:00+0100",
+            "start_timestamp": "2021-04-26T07:59:01+0100",
+            "measurements": {
+                "foo": {"value": 1.1, "unit": "parsec"}
+            }
+        }"#;
+
+        let config: TransactionMetricsConfig = serde_json::from_str(
+            r#"{
+                "extractMetrics": ["d:transactions/measurements.foo@none"]
+            }"#,
+        )
+        .unwrap();
+
+        let event = Annotated::from_json(json).unwrap();
+
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, "d:transactions/measurements.foo@none");
+    }
+
     #[test]
     fn test_transaction_duration() {
         let json = r#"
@@ -709,7 +945,14 @@ mod tests {
         )
         .unwrap();
         let mut metrics = vec![];
-        extract_transaction_metrics(&config, None, &[], event.value().unwrap(), &mut metrics);
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
 
         assert_eq!(metrics.len(), 1);
 
@@ -729,6 +972,230 @@ mod tests {
         assert_eq!(duration_metric.tags["platform"], "other");
     }
 
+    #[test]
+    fn test_vitals_count_tag() {
+        let json = r#"
+        {
+            "type": "transaction",
+            "timestamp": "2021-04-26T08:00:00+0100",
+            "start_timestamp": "2021-04-26T07:59:01+0100",
+            "transaction": "mytransaction",
+            "measurements": {
+                "lcp": {"value": 2500.0},
+                "cls": {"value": 0.1},
+                "ttfb": {"value": 300.0}
+            }
+        }
+        "#;
+
+        let event = Annotated::from_json(json).unwrap();
+
+        let config: TransactionMetricsConfig = serde_json::from_str(
+            r#"
+        {
+            "extractMetrics": [
+                "d:transactions/duration@millisecond"
+            ],
+            "vitalsCountTag": true
+        }
+        "#,
+        )
+        .unwrap();
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, "d:transactions/duration@millisecond");
+        assert_eq!(metrics[0].tags["vitals_count"], "2");
+    }
+
+    #[test]
+    fn test_device_class_tag() {
+        let json = r#"
+        {
+            "type": "transaction",
+            "timestamp": "2021-04-26T08:00:00+0100",
+            "start_timestamp": "2021-04-26T07:59:01+0100",
+            "transaction": "mytransaction",
+            "contexts": {
+                "device": {
+                    "type": "device",
+                    "memory_size": 3221225472
+                }
+            }
+        }
+        "#;
+
+        let event = Annotated::from_json(json).unwrap();
+
+        let config: TransactionMetricsConfig = serde_json::from_str(
+            r#"
+        {
+            "extractMetrics": [
+                "d:transactions/duration@millisecond"
+            ],
+            "extractCustomTags": ["device.class"]
+        }
+        "#,
+        )
+        .unwrap();
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].tags["device.class"], "low");
+    }
+
+    #[test]
+    fn test_sdk_integrations_tag() {
+        let json = r#"
+        {
+            "type": "transaction",
+            "timestamp": "2021-04-26T08:00:00+0100",
+            "start_timestamp": "2021-04-26T07:59:01+0100",
+            "transaction": "mytransaction",
+            "sdk": {
+                "name": "sentry.python",
+                "version": "1.5.0",
+                "integrations": ["django", "redis", "celery"],
+                "packages": [
+                    {"name": "pypi:sentry-sdk", "version": "1.5.0"}
+                ]
+            }
+        }
+        "#;
+
+        let event = Annotated::from_json(json).unwrap();
+
+        let config: TransactionMetricsConfig = serde_json::from_str(
+            r#"
+        {
+            "extractMetrics": [
+                "d:transactions/duration@millisecond"
+            ],
+            "extractCustomTags": ["sdk.integrations"]
+        }
+        "#,
+        )
+        .unwrap();
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].tags["sdk.integrations"], "3");
+    }
+
+    #[test]
+    fn test_dist_placeholder() {
+        let json = r#"
+        {
+            "type": "transaction",
+            "timestamp": "2021-04-26T08:00:00+0100",
+            "start_timestamp": "2021-04-26T07:59:01+0100",
+            "transaction": "mytransaction"
+        }
+        "#;
+
+        let event = Annotated::from_json(json).unwrap();
+
+        let mut config: TransactionMetricsConfig = serde_json::from_str(
+            r#"
+        {
+            "extractMetrics": [
+                "d:transactions/duration@millisecond"
+            ]
+        }
+        "#,
+        )
+        .unwrap();
+
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+
+        assert_eq!(metrics.len(), 1);
+        assert!(!metrics[0].tags.contains_key("dist"));
+
+        config.dist_placeholder = Some("".to_owned());
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].tags["dist"], "");
+    }
+
+    #[test]
+    fn test_unnamed_transaction_placeholder() {
+        let json = r#"
+        {
+            "type": "transaction",
+            "timestamp": "2021-04-26T08:00:00+0100",
+            "start_timestamp": "2021-04-26T07:59:01+0100"
+        }
+        "#;
+
+        let event = Annotated::from_json(json).unwrap();
+
+        let config: TransactionMetricsConfig = serde_json::from_str(
+            r#"
+        {
+            "extractMetrics": [
+                "d:transactions/duration@millisecond"
+            ],
+            "unnamedTransactions": "placeholder"
+        }
+        "#,
+        )
+        .unwrap();
+
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].tags["transaction"], "<unlabeled>");
+    }
+
     #[test]
     fn test_user_satisfaction() {
         let json = r#"
@@ -769,7 +1236,14 @@ mod tests {
         )
         .unwrap();
         let mut metrics = vec![];
-        extract_transaction_metrics(&config, None, &[], event.value().unwrap(), &mut metrics);
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
         assert_eq!(metrics.len(), 2);
 
         let duration_metric = &metrics[0];
@@ -821,7 +1295,14 @@ mod tests {
         )
         .unwrap();
         let mut metrics = vec![];
-        extract_transaction_metrics(&config, None, &[], event.value().unwrap(), &mut metrics);
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
         assert_eq!(metrics.len(), 1);
 
         for metric in metrics {
@@ -863,7 +1344,14 @@ mod tests {
         )
         .unwrap();
         let mut metrics = vec![];
-        extract_transaction_metrics(&config, None, &[], event.value().unwrap(), &mut metrics);
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
         assert_eq!(metrics.len(), 1);
 
         for metric in metrics {
@@ -872,6 +1360,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_negative_web_vital() {
+        let json = r#"
+        {
+            "type": "transaction",
+            "transaction": "foo",
+            "start_timestamp": "2021-04-26T08:00:00+0100",
+            "timestamp": "2021-04-26T08:00:02+0100",
+            "measurements": {
+                "lcp": {"value": -41},
+                "custom": {"value": -5}
+            }
+        }
+        "#;
+
+        let event = Annotated::from_json(json).unwrap();
+
+        let config: TransactionMetricsConfig = serde_json::from_str(
+            r#"
+        {
+            "extractMetrics": [
+                "d:transactions/measurements.lcp@millisecond",
+                "d:transactions/measurements.custom@none"
+            ]
+        }
+        "#,
+        )
+        .unwrap();
+
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+
+        // Negative web vitals are dropped, other measurements may be negative.
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].name, "d:transactions/measurements.custom@none");
+        assert_eq!(metrics[0].value, MetricValue::Distribution(-5.0));
+    }
+
     #[test]
     fn test_conditional_tagging() {
         let json = r#"
@@ -931,6 +1464,7 @@ mod tests {
             None,
             &tagging_config,
             event.value().unwrap(),
+            None,
             &mut metrics,
         );
         assert_eq!(
@@ -1011,6 +1545,7 @@ mod tests {
             None,
             &tagging_config,
             event.value().unwrap(),
+            None,
             &mut metrics,
         );
         assert_eq!(
@@ -1032,4 +1567,163 @@ mod tests {
             )]
         );
     }
+
+    #[test]
+    fn test_conditional_counter() {
+        let config: TransactionMetricsConfig = serde_json::from_str(
+            r#"
+        {
+            "extractMetrics": [
+                "c:transactions/slow_transactions@none"
+            ],
+            "conditionalCounters": [
+                {
+                    "condition": {"op": "gt", "name": "event.duration", "value": 1000},
+                    "name": "slow_transactions"
+                }
+            ]
+        }
+        "#,
+        )
+        .unwrap();
+
+        let transaction = |end: &str| {
+            let json = format!(
+                r#"{{
+                    "type": "transaction",
+                    "transaction": "foo",
+                    "start_timestamp": "2021-04-26T08:00:00+0100",
+                    "timestamp": "{}"
+                }}"#,
+                end
+            );
+            Annotated::<Event>::from_json(&json).unwrap()
+        };
+
+        // 2000ms is above the threshold
+        let event = transaction("2021-04-26T08:00:02+0100");
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+        assert_eq!(
+            metrics,
+            &[Metric::new_mri(
+                METRIC_NAMESPACE,
+                "slow_transactions",
+                MetricUnit::None,
+                MetricValue::Counter(1.0),
+                UnixTimestamp::from_secs(1619420402),
+                {
+                    let mut tags = BTreeMap::new();
+                    tags.insert("transaction".to_owned(), "foo".to_owned());
+                    tags.insert("platform".to_owned(), "other".to_owned());
+                    tags
+                }
+            )]
+        );
+
+        // 500ms is below the threshold
+        let event = transaction("2021-04-26T08:00:00.500+0100");
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+        assert!(metrics.is_empty());
+    }
+
+    #[test]
+    fn test_priority_metric() {
+        let config: TransactionMetricsConfig = serde_json::from_str(
+            r#"
+        {
+            "priorityMeasurements": ["time_to_full_display"]
+        }
+        "#,
+        )
+        .unwrap();
+
+        assert!(config
+            .is_priority_metric("d:transactions/measurements.time_to_full_display@millisecond"));
+        assert!(!config.is_priority_metric("d:transactions/measurements.lcp@millisecond"));
+        assert!(!config.is_priority_metric("d:transactions/duration@millisecond"));
+    }
+
+    #[test]
+    fn test_count_per_root_project() {
+        let json = r#"
+        {
+            "type": "transaction",
+            "transaction": "foo",
+            "start_timestamp": "2021-04-26T08:00:00+0100",
+            "timestamp": "2021-04-26T08:00:02+0100"
+        }
+        "#;
+
+        let event = Annotated::from_json(json).unwrap();
+
+        let config: TransactionMetricsConfig = serde_json::from_str(
+            r#"
+        {
+            "extractMetrics": [
+                "c:transactions/count_per_root_project@none"
+            ]
+        }
+        "#,
+        )
+        .unwrap();
+
+        let trace_context: relay_sampling::TraceContext = serde_json::from_str(
+            r#"
+        {
+            "trace_id": "ff62a8b040f340bda5d830223def1d81",
+            "public_key": "a94ae32be2584e0bbd7a4cbb95971fee"
+        }
+        "#,
+        )
+        .unwrap();
+
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            Some(&trace_context),
+            &mut metrics,
+        );
+
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(
+            metrics[0].name,
+            "c:transactions/count_per_root_project@none"
+        );
+        assert_eq!(metrics[0].value, MetricValue::Counter(1.0));
+        assert_eq!(
+            metrics[0].tags["root_project"],
+            "a94ae32be2584e0bbd7a4cbb95971fee"
+        );
+
+        // Without a trace context, the counter is omitted.
+        let mut metrics = vec![];
+        extract_transaction_metrics(
+            &config,
+            None,
+            &[],
+            event.value().unwrap(),
+            None,
+            &mut metrics,
+        );
+        assert!(metrics.is_empty());
+    }
 }
@@ -1,6 +1,15 @@
 use std::collections::BTreeMap;
 use std::fmt;
 
+#[cfg(feature = "processing")]
+use {
+    relay_general::{
+        protocol::{Context, ContextInner, Event, OsContext, RuntimeContext},
+        types::Annotated,
+    },
+    relay_metrics::{Metric, MetricNamespace, MetricResourceIdentifier},
+};
+
 pub fn with_tag(
     tags: &BTreeMap<String, String>,
     name: &str,
@@ -10,3 +19,64 @@ pub fn with_tag(
     tags.insert(name.to_owned(), value.to_string());
     tags
 }
+
+/// Returns the name of the operating system from the event's OS context.
+#[cfg(feature = "processing")]
+fn extract_os_name(event: &Event) -> Option<String> {
+    let contexts = event.contexts.value()?;
+    match contexts
+        .get(OsContext::default_key())
+        .and_then(Annotated::value)
+    {
+        Some(ContextInner(Context::Os(os))) => os.name.value().cloned(),
+        _ => None,
+    }
+}
+
+/// Returns the name of the runtime from the event's runtime context.
+#[cfg(feature = "processing")]
+fn extract_runtime_name(event: &Event) -> Option<String> {
+    let contexts = event.contexts.value()?;
+    match contexts
+        .get(RuntimeContext::default_key())
+        .and_then(Annotated::value)
+    {
+        Some(ContextInner(Context::Runtime(runtime))) => runtime.name.value().cloned(),
+        _ => None,
+    }
+}
+
+/// Adds the `os.name` tag to metrics in the given namespaces.
+///
+/// Store normalization infers the OS context from the user agent, so this must be called with the
+/// normalized event after metrics have been extracted from it.
+#[cfg(feature = "processing")]
+pub fn add_os_name_tag(metrics: &mut [Metric], event: &Event, namespaces: &[MetricNamespace]) {
+    if let Some(os_name) = extract_os_name(event) {
+        add_tag(metrics, "os.name", &os_name, namespaces);
+    }
+}
+
+/// Adds the `runtime.name` tag to metrics in the given namespaces.
+///
+/// Store normalization derives the runtime name from the raw runtime description, so this must be
+/// called with the normalized event after metrics have been extracted from it.
+#[cfg(feature = "processing")]
+pub fn add_runtime_name_tag(metrics: &mut [Metric], event: &Event, namespaces: &[MetricNamespace]) {
+    if let Some(runtime_name) = extract_runtime_name(event) {
+        add_tag(metrics, "runtime.name", &runtime_name, namespaces);
+    }
+}
+
+/// Sets the tag on all metrics in the given namespaces.
+#[cfg(feature = "processing")]
+fn add_tag(metrics: &mut [Metric], name: &str, value: &str, namespaces: &[MetricNamespace]) {
+    for metric in metrics {
+        let tagged = MetricResourceIdentifier::parse(&metric.name)
+            .map_or(false, |mri| namespaces.contains(&mri.namespace));
+
+        if tagged {
+            metric.tags.insert(name.to_owned(), value.to_owned());
+        }
+    }
+}
@@ -5,12 +5,22 @@ pub enum RelayGauges {
     /// The state of Relay with respect to the upstream connection.
     /// Possible values are `0` for normal operations and `1` for a network outage.
     NetworkOutage,
+    /// The number of envelopes currently retained in capture mode.
+    ///
+    /// The number is bounded by `limits.max_captured_envelopes`.
+    CapturedEnvelopes,
+    /// The number of project states currently requested from the upstream.
+    ///
+    /// The number is bounded by `cache.max_concurrent_fetches`.
+    ProjectStateFetchesInFlight,
 }
 
 impl GaugeMetric for RelayGauges {
     fn name(&self) -> &'static str {
         match self {
             RelayGauges::NetworkOutage => "upstream.network_outage",
+            RelayGauges::CapturedEnvelopes => "capture.envelopes",
+            RelayGauges::ProjectStateFetchesInFlight => "project_state.fetches_in_flight",
         }
     }
 }
@@ -330,6 +340,12 @@ pub enum RelayCounters {
     /// some SDKs may send corrupted values.
     #[cfg(feature = "processing")]
     EventCorrupted,
+    /// Number of envelopes received, before they are queued for processing.
+    ///
+    /// This is only emitted if `metrics.envelopes_received` is enabled. This metric is tagged with:
+    ///  - `accepted`: `"true"` if the envelope was queued, `"false"` if it was rejected because
+    ///    the envelope buffer is full or the envelope is empty.
+    EnvelopeReceived,
     /// Number of envelopes accepted in the current time slot.
     ///
     /// This represents requests that have successfully passed rate limits and filters, and have
@@ -342,6 +358,10 @@ pub enum RelayCounters {
     ///
     /// To check the rejection reason, check `events.outcomes`, instead.
     EnvelopeRejected,
+    /// Number of queued envelopes evicted to make space for new envelopes.
+    ///
+    /// This is only emitted if the buffer overflow policy is set to `drop_oldest`.
+    EnvelopeEvicted,
     /// Number of outcomes and reasons for rejected Envelopes.
     ///
     /// This metric is tagged with:
@@ -457,8 +477,43 @@ pub enum RelayCounters {
     ///    be used to ingest events. Once the grace period expires, the cache is evicted and new
     ///    requests wait for an update.
     EvictingStaleProjectCaches,
+    /// Number of projects evicted from the project cache because it exceeded
+    /// `cache.max_projects`.
+    ///
+    /// The least recently used projects are evicted first. Projects that are fetching their state
+    /// are never evicted.
+    ProjectCacheLruEviction,
     /// Number of times that parsing a metrics bucket item from an envelope failed.
     MetricBucketsParsingFailed,
+    /// Number of times that parsing a metric meta item from an envelope failed.
+    MetricMetaParsingFailed,
+    /// Number of envelope items whose payload could not be deserialized.
+    ///
+    /// This metric is tagged with:
+    ///
+    ///  - `item_type`: The type of the envelope item, for example `event` or `session`.
+    ItemParsingFailed,
+    /// Number of heartbeats sent to the upstream.
+    ///
+    /// This metric is tagged with:
+    ///
+    ///  - `result`: `success` if the upstream responded, otherwise `failure`.
+    UpstreamHeartbeat,
+    /// Number of values modified by data scrubbing rules.
+    ///
+    /// This metric is only emitted if `metrics.pii_rule_hits` is enabled. It is tagged with:
+    ///
+    ///  - `rule_type`: The name of the built-in rule without the `@` prefix, for example
+    ///    `email` or `ip`, or `custom` for all user-defined rules.
+    PiiRuleHits,
+    /// Number of extracted metrics dropped because their metric exceeded its cardinality budget.
+    ///
+    /// The budget is configured per project in `transactionMetrics.cardinalityLimit`. This metric
+    /// is tagged with:
+    ///
+    ///  - `metric`: The name of the metric that exceeded the budget.
+    #[cfg(feature = "processing")]
+    MetricCardinalityLimited,
 }
 
 impl CounterMetric for RelayCounters {
@@ -466,8 +521,10 @@ impl CounterMetric for RelayCounters {
         match self {
             #[cfg(feature = "processing")]
             RelayCounters::EventCorrupted => "event.corrupted",
+            RelayCounters::EnvelopeReceived => "event.received",
             RelayCounters::EnvelopeAccepted => "event.accepted",
             RelayCounters::EnvelopeRejected => "event.rejected",
+            RelayCounters::EnvelopeEvicted => "event.evicted",
             RelayCounters::Outcomes => "events.outcomes",
             RelayCounters::ProjectStateGet => "project_state.get",
             RelayCounters::ProjectStateRequest => "project_state.request",
@@ -483,7 +540,14 @@ impl CounterMetric for RelayCounters {
             RelayCounters::Requests => "requests",
             RelayCounters::ResponsesStatusCodes => "responses.status_codes",
             RelayCounters::EvictingStaleProjectCaches => "project_cache.eviction",
+            RelayCounters::ProjectCacheLruEviction => "project_cache.lru_eviction",
             RelayCounters::MetricBucketsParsingFailed => "metrics.buckets.parsing_failed",
+            RelayCounters::MetricMetaParsingFailed => "metrics.meta.parsing_failed",
+            RelayCounters::ItemParsingFailed => "item.parsing_failed",
+            RelayCounters::UpstreamHeartbeat => "upstream.heartbeat",
+            RelayCounters::PiiRuleHits => "pii.rule_hits",
+            #[cfg(feature = "processing")]
+            RelayCounters::MetricCardinalityLimited => "metrics.cardinality_limited",
         }
     }
 }
@@ -18,11 +18,14 @@ use crate::actors::project_cache::{GetCachedProjectState, GetProjectState, Proje
 use crate::envelope::{Envelope, ItemType};
 
 /// Checks whether an event should be kept or removed by dynamic sampling.
+///
+/// The sample rate of the matching rule is raised to `min_sample_rate` if it is lower.
 pub fn should_keep_event(
     event: &Event,
     ip_addr: Option<IpAddr>,
     project_state: &ProjectState,
     processing_enabled: bool,
+    min_sample_rate: f64,
 ) -> SamplingResult {
     let sampling_config = match &project_state.config.dynamic_sampling {
         // without config there is not enough info to make up my mind
@@ -43,8 +46,19 @@ pub fn should_keep_event(
 
     let ty = rule_type_for_event(event);
     if let Some(rule) = get_matching_event_rule(sampling_config, event, ip_addr, ty) {
+        let mut sample_rate = rule.sample_rate;
+        if sample_rate < min_sample_rate {
+            relay_log::debug!(
+                "raising sample rate {} of rule {} to the minimum of {}",
+                sample_rate,
+                rule.id,
+                min_sample_rate
+            );
+            sample_rate = min_sample_rate;
+        }
+
         let random_number = pseudo_random_from_uuid(event_id);
-        if random_number < rule.sample_rate {
+        if random_number < sample_rate {
             return SamplingResult::Keep;
         }
         return SamplingResult::Drop(rule.id);
@@ -292,17 +306,33 @@ mod tests {
 
         assert_eq!(
             SamplingResult::Drop(RuleId(1)),
-            should_keep_event(&event, None, &proj_state, true)
+            should_keep_event(&event, None, &proj_state, true, 0.0)
         );
         let proj_state = get_project_state(Some(1.0), RuleType::Error);
         assert_eq!(
             SamplingResult::Keep,
-            should_keep_event(&event, None, &proj_state, true)
+            should_keep_event(&event, None, &proj_state, true, 0.0)
         );
         let proj_state = get_project_state(None, RuleType::Error);
         assert_eq!(
             SamplingResult::NoDecision,
-            should_keep_event(&event, None, &proj_state, true)
+            should_keep_event(&event, None, &proj_state, true, 0.0)
+        );
+    }
+
+    #[test]
+    /// A rule with a sample rate below the minimum is raised to the minimum.
+    fn test_should_keep_event_min_sample_rate() {
+        let event = Event {
+            id: Annotated::new(EventId::new()),
+            ty: Annotated::new(EventType::Error),
+            ..Event::default()
+        };
+
+        let proj_state = get_project_state(Some(0.0), RuleType::Error);
+        assert_eq!(
+            SamplingResult::Keep,
+            should_keep_event(&event, None, &proj_state, true, 1.0)
         );
     }
 
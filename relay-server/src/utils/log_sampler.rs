@@ -0,0 +1,61 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Deterministically samples a fraction of log messages.
+///
+/// Instead of sampling randomly, the sampler counts calls to [`sample`](Self::sample) and emits
+/// messages at evenly spaced intervals. For example, a sample rate of `0.25` emits every fourth
+/// message.
+#[derive(Debug)]
+pub struct LogSampler {
+    sample_rate: f64,
+    count: AtomicU64,
+}
+
+impl LogSampler {
+    /// Creates a new sampler with the given sample rate between `0.0` and `1.0`.
+    pub fn new(sample_rate: f64) -> Self {
+        Self {
+            sample_rate,
+            count: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns `true` if the next log message should be emitted.
+    pub fn sample(&self) -> bool {
+        let count = self.count.fetch_add(1, Ordering::Relaxed) as f64;
+        ((count + 1.0) * self.sample_rate).floor() > (count * self.sample_rate).floor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_sampled(sample_rate: f64, total: usize) -> usize {
+        let sampler = LogSampler::new(sample_rate);
+        (0..total).filter(|_| sampler.sample()).count()
+    }
+
+    #[test]
+    fn test_sample_all() {
+        assert_eq!(count_sampled(1.0, 100), 100);
+    }
+
+    #[test]
+    fn test_sample_none() {
+        assert_eq!(count_sampled(0.0, 100), 0);
+    }
+
+    #[test]
+    fn test_sample_fraction() {
+        assert_eq!(count_sampled(0.25, 100), 25);
+        assert_eq!(count_sampled(0.1, 100), 10);
+    }
+
+    #[test]
+    fn test_sample_interval() {
+        let sampler = LogSampler::new(0.5);
+        let sampled: Vec<_> = (0..4).map(|_| sampler.sample()).collect();
+        assert_eq!(sampled, vec![false, true, false, true]);
+    }
+}
@@ -2,6 +2,7 @@ mod actix;
 mod api;
 mod dynamic_sampling;
 mod error_boundary;
+mod log_sampler;
 mod multipart;
 mod param_parser;
 mod profile;
@@ -11,6 +12,7 @@ mod shutdown;
 mod sizes;
 mod timer;
 mod tracked_future;
+mod view_hierarchy;
 mod with_outcome;
 
 #[cfg(feature = "processing")]
@@ -24,6 +26,7 @@ pub use self::actix::*;
 pub use self::api::*;
 pub use self::dynamic_sampling::*;
 pub use self::error_boundary::*;
+pub use self::log_sampler::*;
 pub use self::multipart::*;
 pub use self::param_parser::*;
 pub use self::profile::*;
@@ -33,6 +36,7 @@ pub use self::shutdown::*;
 pub use self::sizes::*;
 pub use self::timer::*;
 pub use self::tracked_future::*;
+pub use self::view_hierarchy::*;
 pub use self::with_outcome::*;
 
 #[cfg(feature = "processing")]
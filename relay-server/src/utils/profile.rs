@@ -349,6 +349,96 @@ pub fn parse_rust_profile(item: &mut Item) -> Result<(), ProfileError> {
     Ok(())
 }
 
+#[derive(Debug, Deserialize)]
+struct PhpSample {
+    stack_id: usize,
+}
+
+/// The minimal shape of a PHP sampled profile required for validation.
+///
+/// Frames are not inspected beyond their count, so they are deserialized as ignored values.
+#[derive(Debug, Deserialize)]
+struct PhpSampledProfile {
+    samples: Vec<PhpSample>,
+    stacks: Vec<Vec<usize>>,
+    frames: Vec<de::IgnoredAny>,
+}
+
+impl PhpSampledProfile {
+    /// Returns `true` if all samples point to existing stacks, and all stacks to existing frames.
+    fn is_valid(&self) -> bool {
+        let valid_stacks = self
+            .stacks
+            .iter()
+            .flatten()
+            .all(|frame_id| *frame_id < self.frames.len());
+
+        let valid_samples = self
+            .samples
+            .iter()
+            .all(|sample| sample.stack_id < self.stacks.len());
+
+        valid_stacks && valid_samples
+    }
+}
+
+/// The minimal shape of a PHP profile required for validation.
+#[derive(Debug, Deserialize)]
+struct PhpProfile {
+    version: String,
+    profile: PhpSampledProfile,
+}
+
+/// Validates a PHP profile in the sample format.
+///
+/// The payload is left untouched, so that all fields not required for validation are forwarded.
+pub fn parse_php_profile(item: &mut Item) -> Result<(), ProfileError> {
+    let profile: PhpProfile =
+        serde_json::from_slice(&item.payload()).map_err(ProfileError::InvalidJson)?;
+
+    if profile.version.is_empty() {
+        return Err(ProfileError::InvalidSampledProfile);
+    }
+
+    if profile.profile.samples.is_empty() {
+        return Err(ProfileError::NotEnoughSamples);
+    }
+
+    if !profile.profile.is_valid() {
+        return Err(ProfileError::InvalidSampledProfile);
+    }
+
+    Ok(())
+}
+
+/// The minimal shape of a chunk of a continuous profile.
+#[derive(Debug, Deserialize)]
+struct ProfileChunk {
+    platform: String,
+    chunk_id: EventId,
+    samples: Vec<de::IgnoredAny>,
+}
+
+/// Validates the payload of a continuous profiling chunk.
+///
+/// Chunks are not bound to a transaction and are forwarded unmodified. They must declare a
+/// platform and a chunk identifier, and contain at least one sample.
+pub fn validate_profile_chunk(item: &Item) -> Result<(), ProfileError> {
+    let chunk: ProfileChunk =
+        serde_json::from_slice(&item.payload()).map_err(ProfileError::InvalidJson)?;
+
+    if chunk.platform.is_empty() {
+        return Err(ProfileError::PlatformNotSupported);
+    }
+
+    if chunk.samples.is_empty() {
+        return Err(ProfileError::NotEnoughSamples);
+    }
+
+    relay_log::trace!("validated profile chunk {}", chunk.chunk_id);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use bytes::Bytes;
@@ -416,6 +506,103 @@ mod tests {
         assert!(parse_rust_profile(&mut item).is_ok());
     }
 
+    #[test]
+    fn test_roundtrip_php() {
+        let mut item = Item::new(ItemType::Profile);
+        let payload = Bytes::from(&include_bytes!("../../tests/fixtures/profiles/php.json")[..]);
+
+        item.set_payload(ContentType::Json, payload.clone());
+
+        assert!(parse_php_profile(&mut item).is_ok());
+        assert_eq!(item.payload(), payload);
+
+        let profile: serde_json::Value = serde_json::from_slice(&item.payload()).unwrap();
+        assert_eq!(profile["os"]["name"], "Linux");
+        assert_eq!(profile["device"]["architecture"], "x86_64");
+        assert_eq!(profile["runtime"]["name"], "php");
+        assert_eq!(profile["timestamp"], "2022-06-01T12:00:00.000Z");
+        assert_eq!(profile["transactions"][0]["name"], "/api/users");
+
+        let frame = &profile["profile"]["frames"][0];
+        assert_eq!(
+            frame["abs_path"],
+            "/var/www/src/Repository/UserRepository.php"
+        );
+        assert_eq!(frame["module"], "App\\Repository\\UserRepository");
+        assert_eq!(frame["in_app"], true);
+    }
+
+    #[test]
+    fn test_php_profile_invalid_stack() {
+        let mut item = Item::new(ItemType::Profile);
+        item.set_payload(
+            ContentType::Json,
+            r#"{
+                "platform": "php",
+                "version": "1",
+                "profile_id": "3d8ab3a4b6164bd3a7ce0e2ae2f6a6c1",
+                "trace_id": "9b5f7b2a3c6e4d1f8a0b2c4d6e8f0a1b",
+                "transaction_id": "2c4e6a8b0d1f4e3a9b7c5d3e1f0a2b4c",
+                "transaction_name": "/api/users",
+                "duration_ns": 15000000,
+                "profile": {
+                    "samples": [{"stack_id": 1, "thread_id": 1, "elapsed_since_start_ns": 0}],
+                    "stacks": [[0]],
+                    "frames": [{"function": "main"}]
+                }
+            }"#,
+        );
+
+        assert!(matches!(
+            parse_php_profile(&mut item),
+            Err(ProfileError::InvalidSampledProfile)
+        ));
+    }
+
+    #[test]
+    fn test_validate_profile_chunk() {
+        let mut item = Item::new(ItemType::ProfileChunk);
+        item.set_payload(
+            ContentType::Json,
+            r#"{
+                "platform": "python",
+                "chunk_id": "0432a0a4c25f4697bf9f0a2fcbe6a814",
+                "samples": [{"stack_id": 0, "thread_id": "1", "timestamp": 1710958503.629}]
+            }"#,
+        );
+
+        assert!(validate_profile_chunk(&item).is_ok());
+    }
+
+    #[test]
+    fn test_validate_profile_chunk_invalid() {
+        let mut item = Item::new(ItemType::ProfileChunk);
+
+        // Missing chunk id
+        item.set_payload(
+            ContentType::Json,
+            r#"{"platform": "python", "samples": [{}]}"#,
+        );
+        assert!(matches!(
+            validate_profile_chunk(&item),
+            Err(ProfileError::InvalidJson(_))
+        ));
+
+        // No samples
+        item.set_payload(
+            ContentType::Json,
+            r#"{
+                "platform": "python",
+                "chunk_id": "0432a0a4c25f4697bf9f0a2fcbe6a814",
+                "samples": []
+            }"#,
+        );
+        assert!(matches!(
+            validate_profile_chunk(&item),
+            Err(ProfileError::NotEnoughSamples)
+        ));
+    }
+
     #[test]
     fn test_ios_debug_image_compatibility() {
         let image_json = r#"{"debug_id":"32420279-25E2-34E6-8BC7-8A006A8F2425","image_addr":"0x000000010258c000","code_file":"/private/var/containers/Bundle/Application/C3511752-DD67-4FE8-9DA2-ACE18ADFAA61/TrendingMovies.app/TrendingMovies","type":"macho","image_size":1720320,"image_vmaddr":"0x0000000100000000"}"#;
@@ -89,7 +89,7 @@ pub fn parse_rate_limits(scoping: &Scoping, string: &str) -> RateLimits {
 ///   to be set on the event item.
 /// - `Attachment`: If the attachment creates an event (e.g. for minidumps), the category is assumed
 ///   to be `Error`.
-fn infer_event_category(item: &Item) -> Option<DataCategory> {
+pub fn infer_event_category(item: &Item) -> Option<DataCategory> {
     match item.ty() {
         ItemType::Event => Some(DataCategory::Error),
         ItemType::Transaction => Some(DataCategory::Transaction),
@@ -101,9 +101,11 @@ fn infer_event_category(item: &Item) -> Option<DataCategory> {
         ItemType::Sessions => None,
         ItemType::Metrics => None,
         ItemType::MetricBuckets => None,
+        ItemType::MetricMeta => None,
         ItemType::FormData => None,
         ItemType::UserReport => None,
         ItemType::Profile => None,
+        ItemType::ProfileChunk => None,
         ItemType::ReplayRecording => None,
         ItemType::ClientReport => None,
         ItemType::Unknown(_) => None,
@@ -126,9 +128,12 @@ pub struct EnvelopeSummary {
     /// The number of all session updates.
     pub session_quantity: usize,
 
-    /// The number of profiles.
+    /// The number of profiles, including chunks of continuous profiles.
     pub profile_quantity: usize,
 
+    /// The number of replay recordings.
+    pub replay_quantity: usize,
+
     /// Indicates that the envelope contains regular attachments that do not create event payloads.
     pub has_plain_attachments: bool,
 }
@@ -160,7 +165,8 @@ impl EnvelopeSummary {
             match item.ty() {
                 ItemType::Attachment => summary.attachment_quantity += item.len().max(1),
                 ItemType::Session => summary.session_quantity += 1,
-                ItemType::Profile => summary.profile_quantity += 1,
+                ItemType::Profile | ItemType::ProfileChunk => summary.profile_quantity += 1,
+                ItemType::ReplayRecording => summary.replay_quantity += 1,
                 _ => (),
             }
         }
@@ -273,6 +279,7 @@ impl Enforcement {
 ///  - If the event is removed, all items depending on the event are removed (e.g. attachments).
 ///  - Attachments are not removed if they create events (e.g. minidumps).
 ///  - Sessions are handled separate to all of the above.
+///  - Profiles and profile chunks are removed together when profiles are rate limited.
 pub struct EnvelopeLimiter<F> {
     check: F,
     event_category: Option<DataCategory>,
@@ -358,6 +365,32 @@ where
         Ok((enforcement, rate_limits))
     }
 
+    /// Checks rate limits for the envelope and reports which items would be removed.
+    ///
+    /// Returns the type of every item in the envelope in order, along with `true` if the item is
+    /// rate limited. This runs the same checks as [`enforce`](Self::enforce), but leaves the
+    /// envelope unmodified. Required attachments that are only marked as rate limited are reported
+    /// as `false`, since they remain in the envelope.
+    #[cfg(test)]
+    pub fn limited_items(
+        mut self,
+        envelope: &Envelope,
+        scoping: &Scoping,
+    ) -> Result<Vec<(ItemType, bool)>, E> {
+        let mut summary = EnvelopeSummary::compute(envelope);
+        if let Some(event_category) = self.event_category {
+            summary.event_category = Some(event_category);
+        }
+
+        let (enforcement, _) = self.execute(&summary, scoping)?;
+        let items = envelope
+            .items()
+            .map(|item| (item.ty().clone(), self.removes_item(item, &enforcement)))
+            .collect();
+
+        Ok(items)
+    }
+
     fn execute(
         &mut self,
         summary: &EnvelopeSummary,
@@ -424,27 +457,42 @@ where
     }
 
     fn retain_item(&self, item: &mut Item, enforcement: &Enforcement) -> bool {
+        if self.removes_item(item, enforcement) {
+            return false;
+        }
+
+        // Attachments required for processing are retained, but marked as rate limited
+        if enforcement.attachments.is_active() && item.ty() == &ItemType::Attachment {
+            item.set_rate_limited(true);
+        }
+
+        true
+    }
+
+    fn removes_item(&self, item: &Item, enforcement: &Enforcement) -> bool {
         // Remove event items and all items that depend on this event
         if enforcement.event.is_active() && item.requires_event() {
-            return false;
+            return true;
         }
 
         // Remove attachments, except those required for processing
         if enforcement.attachments.is_active() && item.ty() == &ItemType::Attachment {
-            if item.creates_event() {
-                item.set_rate_limited(true);
-                return true;
-            }
-
-            return false;
+            return !item.creates_event();
         }
 
         // Remove sessions independently of events
         if enforcement.sessions.is_active() && item.ty() == &ItemType::Session {
-            return false;
+            return true;
         }
 
-        true
+        // Remove profiles and profile chunks, which are counted together
+        if enforcement.profiles.is_active()
+            && matches!(item.ty(), ItemType::Profile | ItemType::ProfileChunk)
+        {
+            return true;
+        }
+
+        false
     }
 }
 
@@ -740,6 +788,33 @@ mod tests {
         mock.assert_call(DataCategory::Session, None);
     }
 
+    #[test]
+    fn test_enforce_limit_attachment_bytes() {
+        let mut envelope = envelope![Event];
+
+        let mut item = Item::new(ItemType::Attachment);
+        item.set_payload(ContentType::OctetStream, vec![0; 1024]);
+        envelope.add_item(item);
+
+        // Attachment quotas are measured in bytes. This quota is exceeded by the attachment.
+        let (enforcement, limits) =
+            EnvelopeLimiter::new(|item_scoping: ItemScoping<'_>, quantity| {
+                let mut limits = RateLimits::new();
+                if item_scoping.category == DataCategory::Attachment && quantity > 100 {
+                    limits.add(rate_limit(DataCategory::Attachment));
+                }
+                Ok::<_, ()>(limits)
+            })
+            .enforce(&mut envelope, &scoping())
+            .unwrap();
+
+        assert!(limits.is_limited());
+        assert_eq!(envelope.len(), 1);
+        assert!(!enforcement.event.is_active());
+        assert_eq!(enforcement.attachments.category, DataCategory::Attachment);
+        assert_eq!(enforcement.attachments.quantity, 1024);
+    }
+
     #[test]
     fn test_enforce_skip_rate_limited() {
         let mut envelope = envelope![];
@@ -795,6 +870,25 @@ mod tests {
         mock.assert_call(DataCategory::Session, Some(2));
     }
 
+    #[test]
+    fn test_enforce_limit_profiles() {
+        let mut envelope = envelope![ProfileChunk, ProfileChunk, Session];
+
+        let mut mock = MockLimiter::default().deny(DataCategory::Profile);
+        let (enforcement, limits) = EnvelopeLimiter::new(|s, q| mock.check(s, q))
+            .enforce(&mut envelope, &scoping())
+            .unwrap();
+
+        // Rate limited chunks are reported as outcomes and must not be forwarded.
+        assert!(limits.is_limited());
+        assert!(enforcement.profiles.is_active());
+        assert_eq!(envelope.len(), 1);
+        assert!(envelope
+            .get_item_by(|i| i.ty() == &ItemType::ProfileChunk)
+            .is_none());
+        mock.assert_call(DataCategory::Profile, Some(2));
+    }
+
     #[test]
     #[cfg(feature = "processing")]
     fn test_enforce_limit_assumed_event() {
@@ -828,4 +922,52 @@ mod tests {
         mock.assert_call(DataCategory::Attachment, None);
         mock.assert_call(DataCategory::Session, None);
     }
+
+    #[test]
+    fn test_limited_items() {
+        let envelope = envelope![Attachment::Minidump, Attachment, Session];
+
+        let mut mock = MockLimiter::default()
+            .deny(DataCategory::Attachment)
+            .deny(DataCategory::Session);
+        let report = EnvelopeLimiter::new(|s, q| mock.check(s, q))
+            .limited_items(&envelope, &scoping())
+            .unwrap();
+
+        assert_eq!(
+            report,
+            vec![
+                (ItemType::Attachment, false),
+                (ItemType::Attachment, true),
+                (ItemType::Session, true),
+            ]
+        );
+
+        // The report leaves the envelope untouched and matches the items removed by enforcement.
+        let mut enforced = envelope.clone();
+        let mut mock = MockLimiter::default()
+            .deny(DataCategory::Attachment)
+            .deny(DataCategory::Session);
+        EnvelopeLimiter::new(|s, q| mock.check(s, q))
+            .enforce(&mut enforced, &scoping())
+            .unwrap();
+
+        assert_eq!(envelope.len(), 3);
+        let retained: Vec<_> = report
+            .into_iter()
+            .filter(|(_, limited)| !limited)
+            .map(|(ty, _)| ty)
+            .collect();
+        let remaining: Vec<_> = enforced.items().map(|item| item.ty().clone()).collect();
+        assert_eq!(retained, remaining);
+    }
+
+    #[test]
+    fn test_summary_replay_quantity() {
+        let envelope = envelope![ReplayRecording, ReplayRecording, Attachment];
+        let summary = EnvelopeSummary::compute(&envelope);
+
+        assert_eq!(summary.replay_quantity, 2);
+        assert_eq!(summary.attachment_quantity, 10);
+    }
 }
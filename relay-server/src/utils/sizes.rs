@@ -39,8 +39,9 @@ pub fn check_envelope_size_limits(config: &Config, envelope: &Envelope) -> bool
             ItemType::UserReport => (),
             ItemType::Metrics => (),
             ItemType::MetricBuckets => (),
+            ItemType::MetricMeta => (),
             ItemType::ClientReport => client_reports_size += item.len(),
-            ItemType::Profile => {
+            ItemType::Profile | ItemType::ProfileChunk => {
                 if item.len() > config.max_profile_size() {
                     return false;
                 }
@@ -0,0 +1,63 @@
+use failure::Fail;
+use serde::{de, Deserialize};
+
+#[derive(Debug, Fail)]
+pub enum ViewHierarchyError {
+    #[fail(display = "invalid json in view hierarchy")]
+    InvalidJson(#[cause] serde_json::Error),
+    #[fail(display = "missing rendering system")]
+    MissingRenderingSystem,
+}
+
+/// The minimal structure of a view hierarchy attachment required for ingestion.
+#[derive(Debug, Deserialize)]
+struct MinimalViewHierarchy {
+    rendering_system: String,
+    /// Must be present, but the windows are not inspected.
+    #[serde(rename = "windows")]
+    _windows: Vec<de::IgnoredAny>,
+}
+
+/// Validates the payload of a view hierarchy attachment.
+///
+/// View hierarchies are JSON objects that must declare the `rendering_system` of the application
+/// and contain a `windows` array. The contents of the windows are not validated.
+pub fn validate_view_hierarchy(payload: &[u8]) -> Result<(), ViewHierarchyError> {
+    let view_hierarchy: MinimalViewHierarchy =
+        serde_json::from_slice(payload).map_err(ViewHierarchyError::InvalidJson)?;
+
+    if view_hierarchy.rendering_system.is_empty() {
+        return Err(ViewHierarchyError::MissingRenderingSystem);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_view_hierarchy() {
+        let payload = br#"{"rendering_system": "UIKIT", "windows": [{"type": "UIWindow"}]}"#;
+        assert!(validate_view_hierarchy(payload).is_ok());
+    }
+
+    #[test]
+    fn test_view_hierarchy_missing_windows() {
+        let payload = br#"{"rendering_system": "UIKIT"}"#;
+        assert!(matches!(
+            validate_view_hierarchy(payload),
+            Err(ViewHierarchyError::InvalidJson(_))
+        ));
+    }
+
+    #[test]
+    fn test_view_hierarchy_empty_rendering_system() {
+        let payload = br#"{"rendering_system": "", "windows": []}"#;
+        assert!(matches!(
+            validate_view_hierarchy(payload),
+            Err(ViewHierarchyError::MissingRenderingSystem)
+        ));
+    }
+}
@@ -57,6 +57,7 @@
 //!
 //! [Metric Types]: https://github.com/statsd/statsd/blob/master/docs/metric_types.md
 
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::net::{ToSocketAddrs, UdpSocket};
 use std::ops::{Deref, DerefMut};
@@ -145,7 +146,8 @@ lazy_static! {
 }
 
 thread_local! {
-    static CURRENT_CLIENT: Option<Arc<MetricsClient>> = METRICS_CLIENT.read().clone();
+    static CURRENT_CLIENT: RefCell<Option<Arc<MetricsClient>>> =
+        RefCell::new(METRICS_CLIENT.read().clone());
     static RNG_UNIFORM_DISTRIBUTION: Uniform<f32> = Uniform::new(0.0, 1.0);
 }
 
@@ -229,14 +231,38 @@ where
     R: Default,
 {
     CURRENT_CLIENT.with(|client| {
-        if let Some(client) = client {
-            f(&*client)
+        if let Some(client) = client.borrow().as_deref() {
+            f(client)
         } else {
             R::default()
         }
     })
 }
 
+/// Captures all metrics emitted by the given callback on the current thread.
+///
+/// Returns the emitted metrics in their raw statsd format, for instance `"counter:1|c"`. The
+/// previously configured client is restored after the callback returns.
+#[cfg(feature = "test")]
+pub fn with_capturing_test_client(f: impl FnOnce()) -> Vec<String> {
+    let (rx, sink) = cadence::SpyMetricSink::new();
+    let test_client = MetricsClient {
+        statsd_client: StatsdClient::from_sink("", sink),
+        default_tags: BTreeMap::new(),
+        sample_rate: 1.0,
+    };
+
+    CURRENT_CLIENT.with(|client| {
+        let previous = client.replace(Some(Arc::new(test_client)));
+        f();
+        client.replace(previous);
+    });
+
+    rx.iter()
+        .map(|metric| String::from_utf8(metric).unwrap())
+        .collect()
+}
+
 /// A metric for capturing timings.
 ///
 /// Timings are a positive number of milliseconds between a start and end time. Examples include
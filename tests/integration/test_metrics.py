@@ -1,10 +1,12 @@
 from datetime import datetime, timedelta, timezone
 import json
+import queue
 import signal
 from time import sleep
 
 import pytest
 import requests
+from sentry_sdk.envelope import Envelope, Item, PayloadRef
 
 from .test_envelope import generate_transaction_item
 
@@ -103,6 +105,32 @@ def test_metrics_backdated(mini_sentry, relay):
     ]
 
 
+def test_metric_meta(mini_sentry, relay):
+    relay = relay(mini_sentry, options=TEST_CONFIG)
+
+    project_id = 42
+    mini_sentry.add_basic_project_config(project_id)
+
+    meta = [{"name": "d:custom/response_time@millisecond", "unit": "millisecond"}]
+    meta_envelope = Envelope()
+    meta_envelope.add_item(
+        Item(payload=PayloadRef(bytes=json.dumps(meta).encode()), type="metric_meta")
+    )
+    relay.send_envelope(project_id, meta_envelope)
+
+    envelope = mini_sentry.captured_events.get(timeout=3)
+    assert len(envelope.items) == 1
+
+    meta_item = envelope.items[0]
+    assert meta_item.type == "metric_meta"
+    assert json.loads(meta_item.get_bytes().decode()) == meta
+
+    # Unchanged metadata is not forwarded again.
+    relay.send_envelope(project_id, meta_envelope)
+    with pytest.raises(queue.Empty):
+        mini_sentry.captured_events.get(timeout=1)
+
+
 def test_metrics_with_processing(mini_sentry, relay_with_processing, metrics_consumer):
     relay = relay_with_processing(options=TEST_CONFIG)
     metrics_consumer = metrics_consumer()
@@ -249,6 +277,7 @@ def test_session_metrics_non_processing(
                 "name": "c:sessions/session@none",
                 "tags": {
                     "sdk": "raven-node/2.6.3",
+                    "sdk.version": "2.6.3",
                     "environment": "production",
                     "release": "sentry-test@1.0.0",
                     "session.status": "init",
@@ -262,6 +291,7 @@ def test_session_metrics_non_processing(
                 "name": "d:sessions/duration@second",
                 "tags": {
                     "sdk": "raven-node/2.6.3",
+                    "sdk.version": "2.6.3",
                     "environment": "production",
                     "release": "sentry-test@1.0.0",
                     "session.status": "exited",
@@ -275,6 +305,7 @@ def test_session_metrics_non_processing(
                 "name": "s:sessions/user@none",
                 "tags": {
                     "sdk": "raven-node/2.6.3",
+                    "sdk.version": "2.6.3",
                     "environment": "production",
                     "release": "sentry-test@1.0.0",
                     "session.status": "init",
@@ -386,6 +417,7 @@ def test_session_metrics_processing(
         "value": 1.0,
         "tags": {
             "sdk": "raven-node/2.6.3",
+            "sdk.version": "2.6.3",
             "environment": "production",
             "release": "sentry-test@1.0.0",
             "session.status": "init",
@@ -401,6 +433,7 @@ def test_session_metrics_processing(
         "value": [1617781333],
         "tags": {
             "sdk": "raven-node/2.6.3",
+            "sdk.version": "2.6.3",
             "environment": "production",
             "release": "sentry-test@1.0.0",
             "session.status": "init",
@@ -416,6 +449,7 @@ def test_session_metrics_processing(
         "value": [1947.49],
         "tags": {
             "sdk": "raven-node/2.6.3",
+            "sdk.version": "2.6.3",
             "environment": "production",
             "release": "sentry-test@1.0.0",
             "session.status": "exited",